@@ -1 +1,6 @@
-import('./pkg').catch(console.error);
+import('./pkg')
+  .then(({ Game }) => {
+    const game = new Game(document.getElementById('canvas'));
+    game.start();
+  })
+  .catch(console.error);
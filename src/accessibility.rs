@@ -0,0 +1,189 @@
+use getset::{
+    Getters,
+    Setters,
+};
+use js_sys::{
+    Object,
+    Reflect,
+    JSON,
+};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    dom,
+    input::{
+        Action,
+        InputState,
+    },
+};
+
+/// The key the settings are stored under in local storage.
+const STORAGE_KEY: &str = "starrock.accessibility";
+
+/// Settings that make the game easier to play for people who can't hold down keys for long, or
+/// that are sensitive to motion.
+#[derive(Clone, Debug, Getters, Setters)]
+pub struct AccessibilitySettings
+{
+    /// Pressing the boost key toggles boost instead of boosting while the key is held down.
+    #[getset(get = "pub", set = "pub")]
+    toggle_boost: bool,
+
+    /// Fire whenever the gun is ready, without holding down the fire key.
+    #[getset(get = "pub", set = "pub")]
+    auto_fire: bool,
+
+    /// The time scale the game runs at.
+    #[getset(get = "pub", set = "pub")]
+    game_speed: f32,
+
+    /// Disables animated backgrounds and camera effects.
+    #[getset(get = "pub", set = "pub")]
+    reduced_motion: bool,
+}
+
+impl Default for AccessibilitySettings
+{
+    fn default() -> AccessibilitySettings
+    {
+        AccessibilitySettings {
+            toggle_boost: false,
+            auto_fire: false,
+            game_speed: 1.,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl AccessibilitySettings
+{
+    /// Loads the settings from local storage. Settings that are missing or invalid get their
+    /// default value.
+    pub fn load() -> AccessibilitySettings
+    {
+        let mut settings = AccessibilitySettings::default();
+
+        let object = dom::window()
+            .local_storage()
+            .ok()
+            .flatten()
+            .and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten())
+            .and_then(|json| JSON::parse(&json).ok());
+
+        if let Some(object) = object {
+            let get = |key: &str| Reflect::get(&object, &JsValue::from_str(key)).ok();
+
+            if let Some(value) = get("toggleBoost").and_then(|value| value.as_bool()) {
+                settings.toggle_boost = value;
+            }
+            if let Some(value) = get("autoFire").and_then(|value| value.as_bool()) {
+                settings.auto_fire = value;
+            }
+            if let Some(value) = get("gameSpeed").and_then(|value| value.as_f64()) {
+                settings.game_speed = value as f32;
+            }
+            if let Some(value) = get("reducedMotion").and_then(|value| value.as_bool()) {
+                settings.reduced_motion = value;
+            }
+        }
+
+        settings
+    }
+
+    /// Stores the settings in local storage.
+    pub fn save(&self) -> Result<(), JsValue>
+    {
+        let object = Object::new();
+        Reflect::set(&object, &"toggleBoost".into(), &self.toggle_boost.into())?;
+        Reflect::set(&object, &"autoFire".into(), &self.auto_fire.into())?;
+        Reflect::set(&object, &"gameSpeed".into(), &self.game_speed.into())?;
+        Reflect::set(
+            &object,
+            &"reducedMotion".into(),
+            &self.reduced_motion.into(),
+        )?;
+
+        let json: String = JSON::stringify(&object)?.into();
+
+        dom::window()
+            .local_storage()?
+            .ok_or("local storage is not available")?
+            .set_item(STORAGE_KEY, &json)
+    }
+}
+
+/// Applies the input related accessibility settings to the input of each step.
+///
+/// The boost key is sampled once per rendered frame rather than once per step, since frames perform
+/// no step when the game runs slower than normal and a short press would be missed otherwise.
+#[derive(Debug, Default)]
+pub struct InputAssist
+{
+    boost_toggled: bool,
+    boost_was_held: bool,
+}
+
+impl InputAssist
+{
+    pub fn new() -> InputAssist
+    {
+        InputAssist::default()
+    }
+
+    /// Toggles boost when the boost key has been pressed since the last frame, `input` being the
+    /// input of the keys held down this frame.
+    pub fn sample(&mut self, settings: &AccessibilitySettings, input: &InputState)
+    {
+        let boost_held = input.contains(Action::Boost);
+        if boost_held && !self.boost_was_held {
+            self.boost_toggled = !self.boost_toggled;
+        }
+        self.boost_was_held = boost_held;
+
+        if !settings.toggle_boost {
+            self.boost_toggled = false;
+        }
+    }
+
+    /// Applies the settings to the `input` of a step.
+    pub fn apply(&self, settings: &AccessibilitySettings, input: &mut InputState)
+    {
+        if settings.toggle_boost {
+            if self.boost_toggled {
+                input.insert(Action::Boost);
+            } else {
+                input.remove(Action::Boost);
+            }
+        }
+
+        // The gun refuses to fire while on cooldown, so it is enough to fire every frame.
+        if settings.auto_fire {
+            input.insert(Action::Fire);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn toggle_boost_sees_presses_in_frames_without_steps()
+    {
+        let mut settings = AccessibilitySettings::default();
+        settings.set_toggle_boost(true);
+
+        let mut boost = InputState::new();
+        boost.insert(Action::Boost);
+
+        // The key is pressed and released in frames that perform no step.
+        let mut assist = InputAssist::new();
+        assist.sample(&settings, &boost);
+        assist.sample(&settings, &InputState::new());
+
+        let mut input = InputState::new();
+        assist.apply(&settings, &mut input);
+        assert!(input.contains(Action::Boost));
+    }
+}
@@ -1,129 +1,127 @@
-use web_sys::{
-    WebGlBuffer,
-    WebGlProgram,
-    WebGlRenderingContext,
-};
+mod rings;
+mod starfield;
+
+use rings::Rings;
+use starfield::Starfield;
 
 use crate::{
     context::Context,
-    gl,
-    matrix::{
-        Matrix4x4,
-        Scale,
-        Translate,
-    },
+    gl::device::GraphicsDevice,
+    ship::Ship,
 };
 
-pub struct Background
+/// Width of the world seen in the foreground, which the tile of the starfield spans.
+const TILE_SIZE: f32 = 6.;
+
+/// Number of frames of movement at its current velocity the view is ahead of the ship.
+const LEAD_FRAMES: f32 = 30.;
+
+/// How fast the lead follows changes of velocity, from 0 to 1.
+const LEAD_SMOOTHING: f32 = 0.05;
+
+/// How the background is drawn, chosen by the theme.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BackgroundStyle
 {
-    pub position: [f32; 2],
-    program: WebGlProgram,
-    vertex_buffer: WebGlBuffer,
+    /// Layers of stars and a nebula scrolling with the ship.
+    Starfield,
+
+    /// Rings around a point moved by clicking the canvas.
+    Rings,
 }
 
-impl Background
+pub struct Background
 {
-    pub fn new(context: &WebGlRenderingContext) -> Result<Background, String>
-    {
-        let vertex_shader = gl::compile_vertex_shader(
-            &context,
-            r#"
-            attribute vec4 position;
-
-            uniform mat4 world_matrix;
-            uniform mat4 projection_matrix;
-            
-            varying vec4 vertex_position;
-    
-            void main()
-            {
-                vertex_position = projection_matrix * world_matrix * position;
-                gl_Position = position;
-            }
-            "#,
-        )?;
+    pub position: [f32; 2],
 
-        let fragment_shader = gl::compile_fragment_shader(
-            &context,
-            r#"
-            #define PI 3.14159265359
+    /// Renders a still background, without the rings, the twinkling of the stars or the parallax.
+    pub reduced_motion: bool,
 
-            precision mediump float;
+    /// Distance travelled by the ship in units of the world, as if it didn't wrap around.
+    scroll: [f32; 2],
 
-            varying vec4 vertex_position;
+    /// Offset of the view ahead of the ship, following its velocity.
+    lead: [f32; 2],
 
-            void main()
-            {
-                vec4 d0 = vertex_position * vertex_position;
-                float d1 = sqrt(d0.x + d0.y + d0.z);
-                float d2 = d1 * d1;
+    /// Position of the ship when last updated.
+    ship_position: Option<[f32; 2]>,
 
-                gl_FragColor = vec4(abs(sin(d2 * 17.0)) * 0.3, 
-                                    0.1, 
-                                    abs(cos(d2 * 29.0)) * 0.3,
-                                    1.0);
-            }
-            "#,
-        )?;
-
-        let program = gl::link_program(context, &vertex_shader, &fragment_shader)?;
-
-        let vertices: [f32; 18] = [
-            1., 1., 0., // 0
-            -1., 1., 0., // 1
-            -1., -1., 0., // 2
-            -1., -1., 0., // 3
-            1., -1., 0., // 4
-            1., 1., 0., // 5
-        ];
-
-        let vertex_buffer = context.create_buffer().ok_or("failed to create buffer")?;
-        context.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&vertex_buffer));
-
-        unsafe {
-            let vertex_array = js_sys::Float32Array::view(&vertices);
-
-            context.buffer_data_with_array_buffer_view(
-                WebGlRenderingContext::ARRAY_BUFFER,
-                &vertex_array,
-                WebGlRenderingContext::STATIC_DRAW,
-            );
-        }
+    /// Number of frames the stars have been twinkling for.
+    frame: u32,
+
+    rings: Rings,
+    starfield: Starfield,
+}
 
+impl Background
+{
+    /// Creates a background with a starfield generated from `seed`.
+    pub fn new(context: &dyn GraphicsDevice, seed: u64) -> Result<Background, String>
+    {
         Ok(Background {
             position: [0.0, 0.0],
-            program,
-            vertex_buffer,
+            reduced_motion: false,
+            scroll: [0., 0.],
+            lead: [0., 0.],
+            ship_position: None,
+            frame: 0,
+            rings: Rings::new(context)?,
+            starfield: Starfield::new(context, seed)?,
         })
     }
 
-    pub fn render(&self, context: &Context)
+    /// Follows the movement of `ship`, once per rendered frame.
+    pub fn update(&mut self, ship: &Ship)
     {
-        let gl = context.render_context();
-
-        gl.use_program(Some(&self.program));
-
-        gl.bind_buffer(
-            WebGlRenderingContext::ARRAY_BUFFER,
-            Some(&self.vertex_buffer),
-        );
-        gl.enable_vertex_attrib_array(0);
+        let position = *ship.position();
+        let last_position = self.ship_position.replace(position);
 
-        let matrix = Translate::id().vec2(&self.position).into_array();
-        let location = gl.get_uniform_location(&self.program, "world_matrix");
-
-        gl.uniform_matrix4fv_with_f32_array(location.as_ref(), false, &matrix);
+        if self.reduced_motion {
+            return;
+        }
 
-        let w = context.canvas_width().clone() as f32;
-        let h = context.canvas_height().clone() as f32;
-        let matrix = Scale::id().y(h / w).into_array();
+        // The ship wraps around the edges of the world, so it moved by the shortest distance.
+        if let Some(last_position) = last_position {
+            self.scroll[0] += wrap(position[0] - last_position[0], 4.);
+            self.scroll[1] += wrap(position[1] - last_position[1], 3.);
+        }
 
-        let location = gl.get_uniform_location(&self.program, "projection_matrix");
-        gl.uniform_matrix4fv_with_f32_array(location.as_ref(), false, &matrix);
+        let velocity = ship.velocity();
+        for (lead, velocity) in self.lead.iter_mut().zip(velocity) {
+            *lead += (velocity * LEAD_FRAMES - *lead) * LEAD_SMOOTHING;
+        }
 
-        gl.vertex_attrib_pointer_with_i32(0, 3, WebGlRenderingContext::FLOAT, false, 0, 0);
-        gl.draw_arrays(WebGlRenderingContext::TRIANGLES, 0, 6);
+        self.frame = self.frame.wrapping_add(1);
+    }
 
-        gl.use_program(None);
+    /// Renders the background in the style and colors of the theme of `context`.
+    pub fn render(&self, context: &Context)
+    {
+        let theme = &context.theme().background;
+
+        match theme.style {
+            BackgroundStyle::Starfield => {
+                // Clicking moves the stars towards `position` like the rings, a tile being two
+                // units wide in clip space.
+                let scroll = [
+                    (self.scroll[0] + self.lead[0]) / TILE_SIZE - self.position[0] / 2.,
+                    (self.scroll[1] + self.lead[1]) / TILE_SIZE - self.position[1] / 2.,
+                ];
+                let time = self.frame as f32 / 60.;
+
+                self.starfield
+                    .render(context, theme, scroll, time, self.reduced_motion);
+            }
+            BackgroundStyle::Rings => {
+                self.rings
+                    .render(context, theme, self.position, self.reduced_motion);
+            }
+        }
     }
 }
+
+/// `distance` moved back into `-size / 2` to `size / 2`.
+fn wrap(distance: f32, size: f32) -> f32
+{
+    distance - (distance / size).round() * size
+}
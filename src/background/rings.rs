@@ -0,0 +1,161 @@
+use web_sys::WebGlRenderingContext;
+
+use crate::{
+    context::Context,
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::GraphicsDevice,
+        program::{
+            Float,
+            Mat4,
+            Program,
+            Uniform,
+            Vec4,
+        },
+    },
+    matrix::{
+        Matrix4x4,
+        Scale,
+        Translate,
+    },
+    theme::BackgroundTheme,
+};
+
+/// A full-screen quad with rings around the center, alternating between the two colors of the
+/// theme added to its base color.
+pub struct Rings
+{
+    program: Program,
+    world_matrix: Uniform<Mat4>,
+    projection_matrix: Uniform<Mat4>,
+    ring_intensity: Uniform<Float>,
+    base_color: Uniform<Vec4>,
+    first_color: Uniform<Vec4>,
+    second_color: Uniform<Vec4>,
+    vertex_layout: VertexLayout,
+    vertex_buffer: ArrayBuffer<f32>,
+}
+
+impl Rings
+{
+    pub fn new(context: &dyn GraphicsDevice) -> Result<Rings, String>
+    {
+        let vertex_shader = gl::compile_vertex_shader(
+            context,
+            r#"
+            attribute vec4 position;
+
+            uniform mat4 world_matrix;
+            uniform mat4 projection_matrix;
+
+            varying vec4 vertex_position;
+
+            void main()
+            {
+                vertex_position = projection_matrix * world_matrix * position;
+                gl_Position = position;
+            }
+            "#,
+        )?;
+
+        let fragment_shader = gl::compile_fragment_shader(
+            context,
+            r#"
+            #define PI 3.14159265359
+
+            precision mediump float;
+
+            uniform float ring_intensity;
+            uniform vec4 base_color;
+            uniform vec4 first_color;
+            uniform vec4 second_color;
+
+            varying vec4 vertex_position;
+
+            void main()
+            {
+                vec4 d0 = vertex_position * vertex_position;
+                float d1 = sqrt(d0.x + d0.y + d0.z);
+                float d2 = d1 * d1;
+
+                vec3 rings = base_color.rgb
+                    + abs(sin(d2 * 17.0)) * first_color.rgb
+                    + abs(cos(d2 * 29.0)) * second_color.rgb;
+                vec3 still = base_color.rgb + (first_color.rgb + second_color.rgb) * 0.5;
+
+                gl_FragColor = vec4(mix(still, rings, ring_intensity), 1.0);
+            }
+            "#,
+        )?;
+
+        let vertex_layout =
+            VertexLayout::new().attribute("position", 3, VertexAttributeType::Float);
+
+        let program = Program::new(context, &vertex_shader, &fragment_shader, &[&vertex_layout])?;
+
+        let vertices: [f32; 18] = [
+            1., 1., 0., // 0
+            -1., 1., 0., // 1
+            -1., -1., 0., // 2
+            -1., -1., 0., // 3
+            1., -1., 0., // 4
+            1., 1., 0., // 5
+        ];
+
+        let vertex_buffer = ArrayBuffer::with_data(context, BufferUsage::StaticDraw, &vertices)?;
+
+        Ok(Rings {
+            world_matrix: program.uniform("world_matrix")?,
+            projection_matrix: program.uniform("projection_matrix")?,
+            ring_intensity: program.uniform("ring_intensity")?,
+            base_color: program.uniform("base_color")?,
+            first_color: program.uniform("first_color")?,
+            second_color: program.uniform("second_color")?,
+            program,
+            vertex_layout,
+            vertex_buffer,
+        })
+    }
+
+    /// Renders the rings centered on `position`, still if `reduced_motion` is set.
+    pub fn render(
+        &self,
+        context: &Context,
+        theme: &BackgroundTheme,
+        position: [f32; 2],
+        reduced_motion: bool,
+    )
+    {
+        let gl = context.render_context();
+
+        self.program.use_program(gl);
+        self.program
+            .enable_vertex_layout(gl, &self.vertex_layout, &self.vertex_buffer, 0);
+
+        let matrix = Translate::id().vec2(&position).into_array();
+        self.world_matrix.set(gl, &matrix);
+
+        let w = *context.canvas_width() as f32;
+        let h = *context.canvas_height() as f32;
+        let matrix = Scale::id().y(h / w).into_array();
+
+        self.projection_matrix.set(gl, &matrix);
+
+        self.ring_intensity
+            .set(gl, &if reduced_motion { 0. } else { 1. });
+        self.base_color.set(gl, &theme.base_color);
+        self.first_color.set(gl, &theme.colors[0]);
+        self.second_color.set(gl, &theme.colors[1]);
+
+        gl.draw_arrays(WebGlRenderingContext::TRIANGLES, 0, 6);
+
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    f32::consts::TAU,
+    ops::Range,
+};
+
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use web_sys::WebGlRenderingContext;
+
+use crate::{
+    context::Context,
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            ElementArrayBuffer,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        program::{
+            Float,
+            Program,
+            Uniform,
+            Vec2,
+            Vec4,
+        },
+    },
+    theme::BackgroundTheme,
+};
+
+/// A layer of stars moving at the same speed.
+struct StarLayer
+{
+    count: usize,
+
+    /// How much the layer moves relative to the foreground, from 0 for a layer at infinity to 1.
+    depth: f32,
+
+    /// Radius of the stars, in units of the half width of the tile.
+    size: Range<f32>,
+
+    brightness: Range<f32>,
+}
+
+/// The layers of stars, from the farthest to the nearest.
+const STAR_LAYERS: [StarLayer; 3] = [
+    StarLayer {
+        count: 220,
+        depth: 0.1,
+        size: 0.003..0.005,
+        brightness: 0.2..0.5,
+    },
+    StarLayer {
+        count: 110,
+        depth: 0.25,
+        size: 0.004..0.007,
+        brightness: 0.4..0.8,
+    },
+    StarLayer {
+        count: 45,
+        depth: 0.5,
+        size: 0.006..0.01,
+        brightness: 0.7..1.,
+    },
+];
+
+/// How much the nebula moves relative to the foreground, behind every star.
+const NEBULA_DEPTH: f32 = 0.05;
+
+/// How much farther than the edges of the screen the stars are spread, so that they go off screen
+/// before wrapping around.
+const MARGIN: f32 = 0.02;
+
+/// A procedural starfield over a nebula, repeating on a square tile covering the canvas.
+///
+/// Positions on the tile go from 0 to 1, the tile being moved by the scroll of each layer.
+pub struct Starfield
+{
+    nebula_program: Program,
+    nebula_scroll: Uniform<Vec2>,
+    nebula_scale: Uniform<Vec2>,
+    nebula_seed: Uniform<Float>,
+    base_color: Uniform<Vec4>,
+    cloud_color: Uniform<Vec4>,
+    dust_color: Uniform<Vec4>,
+    nebula_layout: VertexLayout,
+    nebula_buffer: ArrayBuffer<f32>,
+
+    /// Offset of the noise of the nebula, so that it differs from a seed to another.
+    seed: f32,
+
+    star_program: Program,
+    star_scroll: Uniform<Vec2>,
+    star_scale: Uniform<Vec2>,
+    time: Uniform<Float>,
+    twinkle_amount: Uniform<Float>,
+    star_brightness: Uniform<Float>,
+    star_layout: VertexLayout,
+    star_buffer: ArrayBuffer<f32>,
+    index_buffer: ElementArrayBuffer,
+}
+
+impl Starfield
+{
+    /// Creates the starfield generated from `seed`.
+    pub fn new(gl: &dyn GraphicsDevice, seed: u64) -> Result<Starfield, String>
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        //
+        // Nebula.
+        //
+        let nebula_layout =
+            VertexLayout::new().attribute("position", 2, VertexAttributeType::Float);
+
+        let nebula_program = Program::new(
+            gl,
+            &nebula_vertex_shader(gl)?,
+            &nebula_fragment_shader(gl)?,
+            &[&nebula_layout],
+        )?;
+
+        let vertices: [f32; 12] = [
+            1., 1., // 0
+            -1., 1., // 1
+            -1., -1., // 2
+            -1., -1., // 3
+            1., -1., // 4
+            1., 1., // 5
+        ];
+        let nebula_buffer = ArrayBuffer::with_data(gl, BufferUsage::StaticDraw, &vertices)?;
+
+        //
+        // Stars, drawn as quads around their center.
+        //
+        let star_layout = VertexLayout::new()
+            .attribute("star", 2, VertexAttributeType::Float)
+            .attribute("corner", 2, VertexAttributeType::Float)
+            .attribute("depth", 1, VertexAttributeType::Float)
+            .attribute("size", 1, VertexAttributeType::Float)
+            .attribute("color", 3, VertexAttributeType::Float)
+            .attribute("twinkle", 2, VertexAttributeType::Float);
+
+        let star_program = Program::new(
+            gl,
+            &star_vertex_shader(gl)?,
+            &star_fragment_shader(gl)?,
+            &[&star_layout],
+        )?;
+
+        let mut vertices = Vec::new();
+        let mut indices: Vec<u16> = Vec::new();
+
+        for layer in STAR_LAYERS.iter() {
+            for _ in 0..layer.count {
+                let star = [rng.gen::<f32>(), rng.gen::<f32>()];
+                let size = rng.gen_range(layer.size.clone());
+                let brightness = rng.gen_range(layer.brightness.clone());
+                let color = star_color(&mut rng, brightness);
+
+                // Phase and speed in radians per second.
+                let twinkle = [rng.gen_range(0. ..TAU), rng.gen_range(1. ..4.)];
+
+                let first = (vertices.len() / (*star_layout.stride() as usize / 4)) as u16;
+                for corner in [[-1., -1.], [1., -1.], [1., 1.], [-1., 1.]].iter() {
+                    vertices.extend_from_slice(&star);
+                    vertices.extend_from_slice(corner);
+                    vertices.push(layer.depth);
+                    vertices.push(size);
+                    vertices.extend_from_slice(&color);
+                    vertices.extend_from_slice(&twinkle);
+                }
+                indices.extend([0, 1, 2, 0, 2, 3].iter().map(|index| first + index));
+            }
+        }
+
+        let star_buffer = ArrayBuffer::with_data(gl, BufferUsage::StaticDraw, &vertices)?;
+
+        let mut index_buffer = ElementArrayBuffer::new(gl)?;
+        index_buffer.set_data(gl, BufferUsage::StaticDraw, &indices);
+
+        Ok(Starfield {
+            nebula_scroll: nebula_program.uniform("scroll")?,
+            nebula_scale: nebula_program.uniform("scale")?,
+            nebula_seed: nebula_program.uniform("seed")?,
+            base_color: nebula_program.uniform("base_color")?,
+            cloud_color: nebula_program.uniform("cloud_color")?,
+            dust_color: nebula_program.uniform("dust_color")?,
+            nebula_program,
+            nebula_layout,
+            nebula_buffer,
+            seed: rng.gen_range(0. ..100.),
+            star_scroll: star_program.uniform("scroll")?,
+            star_scale: star_program.uniform("scale")?,
+            time: star_program.uniform("time")?,
+            twinkle_amount: star_program.uniform("twinkle_amount")?,
+            star_brightness: star_program.uniform("star_brightness")?,
+            star_program,
+            star_layout,
+            star_buffer,
+            index_buffer,
+        })
+    }
+
+    /// Renders the starfield scrolled by `scroll` tiles at the depth of the foreground, with the
+    /// stars twinkling at `time` in seconds unless `reduced_motion` is set.
+    pub fn render(
+        &self,
+        context: &Context,
+        theme: &BackgroundTheme,
+        scroll: [f32; 2],
+        time: f32,
+        reduced_motion: bool,
+    )
+    {
+        let gl = context.render_context();
+
+        // The tile covers the canvas along its longest side and is square on screen.
+        let width = *context.canvas_width() as f32;
+        let height = *context.canvas_height() as f32;
+        let longest = width.max(height);
+        let scale = [longest / width, longest / height];
+
+        //
+        // Nebula.
+        //
+        self.nebula_program.use_program(gl);
+        self.nebula_program
+            .enable_vertex_layout(gl, &self.nebula_layout, &self.nebula_buffer, 0);
+
+        self.nebula_scroll
+            .set(gl, &[scroll[0] * NEBULA_DEPTH, scroll[1] * NEBULA_DEPTH]);
+        self.nebula_scale.set(gl, &scale);
+        self.nebula_seed.set(gl, &self.seed);
+        self.base_color.set(gl, &theme.base_color);
+        self.cloud_color.set(gl, &theme.colors[0]);
+        self.dust_color.set(gl, &theme.colors[1]);
+
+        gl.draw_arrays(WebGlRenderingContext::TRIANGLES, 0, 6);
+
+        self.nebula_program
+            .disable_vertex_layout(gl, &self.nebula_layout);
+        self.nebula_program.unuse_program(gl);
+
+        //
+        // Stars.
+        //
+        self.star_program.use_program(gl);
+        self.star_program
+            .enable_vertex_layout(gl, &self.star_layout, &self.star_buffer, 0);
+
+        self.star_scroll.set(gl, &scroll);
+        self.star_scale.set(gl, &scale);
+        self.time.set(gl, &time);
+        self.twinkle_amount
+            .set(gl, &if reduced_motion { 0. } else { 0.6 });
+        self.star_brightness.set(gl, &theme.star_brightness);
+
+        self.index_buffer.bind(gl);
+        gl.draw_elements(
+            WebGlRenderingContext::TRIANGLES,
+            self.index_buffer.len().unwrap() as i32,
+            WebGlRenderingContext::UNSIGNED_SHORT,
+            0,
+        );
+        self.index_buffer.unbind(gl);
+
+        self.star_program
+            .disable_vertex_layout(gl, &self.star_layout);
+        self.star_program.unuse_program(gl);
+    }
+}
+
+/// A color of a star of `brightness`, mostly white with a few bluish and yellowish stars.
+fn star_color(rng: &mut StdRng, brightness: f32) -> [f32; 3]
+{
+    let tint = match rng.gen_range(0..10) {
+        0 | 1 => [0.7, 0.8, 1.],
+        2 => [1., 0.9, 0.6],
+        _ => [1., 1., 1.],
+    };
+    [
+        tint[0] * brightness,
+        tint[1] * brightness,
+        tint[2] * brightness,
+    ]
+}
+
+fn nebula_vertex_shader(gl: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_vertex_shader(
+        gl,
+        r#"
+        attribute vec2 position;
+
+        uniform vec2 scroll;
+        uniform vec2 scale;
+
+        varying vec2 _uv;
+
+        void main()
+        {
+            _uv = (position / scale) * 0.5 + 0.5 + scroll;
+            gl_Position = vec4(position, 0.0, 1.0);
+        }
+        "#,
+    )
+}
+
+fn nebula_fragment_shader(gl: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_fragment_shader(
+        gl,
+        r#"
+        precision mediump float;
+
+        uniform float seed;
+        uniform vec4 base_color;
+        uniform vec4 cloud_color;
+        uniform vec4 dust_color;
+
+        varying vec2 _uv;
+
+        float hash(vec2 cell, float period)
+        {
+            cell = mod(cell, period);
+            return fract(sin(dot(cell, vec2(12.9898, 78.233)) + seed) * 43758.5453);
+        }
+
+        // Value noise repeating every `period` cells.
+        float noise(vec2 p, float period)
+        {
+            vec2 i = floor(p);
+            vec2 f = fract(p);
+            f = f * f * (3.0 - 2.0 * f);
+
+            float a = hash(i, period);
+            float b = hash(i + vec2(1.0, 0.0), period);
+            float c = hash(i + vec2(0.0, 1.0), period);
+            float d = hash(i + vec2(1.0, 1.0), period);
+
+            return mix(mix(a, b, f.x), mix(c, d, f.x), f.y);
+        }
+
+        // Noise repeating on the tile, with details from 4 to 32 cells per tile.
+        float fbm(vec2 uv)
+        {
+            float value = 0.0;
+            float amplitude = 0.5;
+            float period = 4.0;
+            for (int octave = 0; octave < 4; octave++) {
+                value += amplitude * noise(uv * period, period);
+                period *= 2.0;
+                amplitude *= 0.5;
+            }
+            return value;
+        }
+
+        void main()
+        {
+            vec2 uv = fract(_uv);
+
+            float clouds = smoothstep(0.45, 0.85, fbm(uv));
+            float dust = smoothstep(0.5, 0.9, fbm(uv.yx + vec2(0.5, 0.25)));
+
+            vec3 color = base_color.rgb + cloud_color.rgb * clouds + dust_color.rgb * dust;
+
+            gl_FragColor = vec4(color, 1.0);
+        }
+        "#,
+    )
+}
+
+fn star_vertex_shader(gl: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_vertex_shader(
+        gl,
+        &format!(
+            r#"
+            attribute vec2 star;
+            attribute vec2 corner;
+            attribute float depth;
+            attribute float size;
+            attribute vec3 color;
+            attribute vec2 twinkle;
+
+            uniform vec2 scroll;
+            uniform vec2 scale;
+            uniform float time;
+            uniform float twinkle_amount;
+            uniform float star_brightness;
+
+            varying vec2 _corner;
+            varying vec3 _color;
+
+            void main()
+            {{
+                vec2 center = (fract(star - scroll * depth) * 2.0 - 1.0) * (1.0 + {margin:.3});
+                float brightness =
+                    1.0 - twinkle_amount * (0.5 + 0.5 * sin(time * twinkle.y + twinkle.x));
+
+                _corner = corner;
+                _color = color * brightness * star_brightness;
+                gl_Position = vec4((center + corner * size) * scale, 0.0, 1.0);
+            }}
+            "#,
+            margin = MARGIN
+        ),
+    )
+}
+
+fn star_fragment_shader(gl: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_fragment_shader(
+        gl,
+        r#"
+        precision mediump float;
+
+        varying vec2 _corner;
+        varying vec3 _color;
+
+        void main()
+        {
+            float falloff = max(1.0 - dot(_corner, _corner), 0.0);
+            gl_FragColor = vec4(_color, falloff * falloff);
+        }
+        "#,
+    )
+}
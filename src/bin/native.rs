@@ -0,0 +1,43 @@
+use std::{
+    env,
+    path::PathBuf,
+    process,
+};
+
+use starrock::native::NativeOptions;
+
+const USAGE: &str = "usage: starrock-native [--mouse-aim] [--record <replay file>] [seed]";
+
+/// Runs starrock in a window, with the options described by `USAGE`.
+fn main()
+{
+    let options = match parse_options(env::args().skip(1)) {
+        Some(options) => options,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(error) = starrock::native::run(options) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+/// The options given by `arguments`, or `None` if they aren't valid.
+fn parse_options<I: Iterator<Item = String>>(mut arguments: I) -> Option<NativeOptions>
+{
+    let mut options = NativeOptions::default();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--mouse-aim" => options.mouse_aim = true,
+            "--record" => options.replay_path = Some(PathBuf::from(arguments.next()?)),
+            seed if options.seed.is_none() => options.seed = Some(seed.parse().ok()?),
+            _ => return None,
+        }
+    }
+
+    Some(options)
+}
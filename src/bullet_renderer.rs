@@ -1,26 +1,42 @@
-use web_sys::{
-    WebGlBuffer,
-    WebGlProgram,
-    WebGlRenderingContext,
-    WebGlShader,
-};
+use vecmath::vec2_normalized;
+use web_sys::WebGlRenderingContext;
 
 use crate::{
     bullet::Bullet,
     context::Context,
-    gl,
-    matrix::{
-        Matrix4x4,
-        Rotate,
-        Scale,
-        Translate,
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        instanced::InstanceBuffer,
+        program::{
+            Float,
+            Mat4,
+            Program,
+            Uniform,
+            Vec4,
+        },
     },
 };
 
+/// Renderer that renders all `Bullet`s of a world with one draw call.
 pub struct BulletRenderer
 {
-    program: WebGlProgram,
-    vertex_buffer: WebGlBuffer,
+    program: Program,
+    projection_matrix: Uniform<Mat4>,
+    color: Uniform<Vec4>,
+    shimmer: Uniform<Float>,
+    vertex_layout: VertexLayout,
+    vertex_buffer: ArrayBuffer<f32>,
+    instances: InstanceBuffer,
 }
 
 impl BulletRenderer
@@ -29,78 +45,98 @@ impl BulletRenderer
     {
         let gl = context.render_context();
 
-        let program = gl::link_program(gl, &vertex_shader(gl)?, &fragment_shader(gl)?)?;
+        let vertex_layout =
+            VertexLayout::new().attribute("position", 3, VertexAttributeType::Float);
+
+        // Every instance is positioned, scaled and rotated in the direction it travels.
+        let instance_layout = VertexLayout::new()
+            .attribute("instance_position", 2, VertexAttributeType::Float)
+            .attribute("instance_size", 2, VertexAttributeType::Float)
+            .attribute("instance_direction", 2, VertexAttributeType::Float);
+
+        let program = Program::new(
+            gl,
+            &vertex_shader(gl)?,
+            &fragment_shader(gl)?,
+            &[&vertex_layout, &instance_layout],
+        )?;
 
-        let vertex_buffer = gl::make_static_draw_array_buffer_f32(
+        let vertex_buffer = ArrayBuffer::with_data(
             gl,
-            vec![-1., -1., 0., -1., 1., 0., 1., -1., 0., 1., 1., 0.],
+            BufferUsage::StaticDraw,
+            &[-1., -1., 0., -1., 1., 0., 1., -1., 0., 1., 1., 0.],
         )?;
 
         Ok(BulletRenderer {
+            projection_matrix: program.uniform("projection_matrix")?,
+            color: program.uniform("color")?,
+            shimmer: program.uniform("shimmer")?,
             program,
+            vertex_layout,
             vertex_buffer,
+            instances: InstanceBuffer::new(context.render_context(), instance_layout)?,
         })
     }
 
-    pub fn render(&self, context: &Context, bullet: &Bullet)
+    pub fn render(&mut self, context: &Context, bullets: &[Bullet])
     {
         let gl = context.render_context();
 
-        gl.bind_buffer(
-            WebGlRenderingContext::ARRAY_BUFFER,
-            Some(&self.vertex_buffer),
-        );
-        gl.enable_vertex_attrib_array(0);
-
-        gl.use_program(Some(&self.program));
-        gl.vertex_attrib_pointer_with_i32(0, 3, WebGlRenderingContext::FLOAT, false, 0, 0);
-
         //
-        // Calculate world matrix and set the uniform.
+        // Upload the instances.
         //
-        let matrix = Scale::id()
-            .vec2(bullet.size())
-            .into_arr2()
-            .dot(&Rotate::id().vec2(*bullet.velocity()).into_arr2())
-            .dot(&Translate::id().vec2(bullet.position()).into_arr2());
-
-        let location = gl.get_uniform_location(&self.program, "world_matrix");
-
-        gl.uniform_matrix4fv_with_f32_array(
-            location.as_ref(),
-            false,
-            matrix.view().as_slice().unwrap(),
-        );
+        let mut data = Vec::with_capacity(bullets.len() * 6);
+        for bullet in bullets {
+            data.extend_from_slice(bullet.position());
+            data.extend_from_slice(bullet.size());
+            data.extend_from_slice(&vec2_normalized(*bullet.velocity()));
+        }
+        self.instances.set_data(gl, data);
 
         //
-        // Set the projection matrix uniform.
+        // Setup the program.
         //
-        let matrix = context.foreground_projection_matrix().clone().into_array();
-        let location = gl.get_uniform_location(&self.program, "projection_matrix");
+        self.program.use_program(gl);
 
-        gl.uniform_matrix4fv_with_f32_array(location.as_ref(), false, &matrix);
+        let matrix = context.foreground_matrix();
+        self.projection_matrix.set(gl, &matrix);
+
+        let colors = &context.theme().bullets;
+        self.color.set(gl, &colors.color);
+        self.shimmer.set(gl, &colors.shimmer);
+
+        self.program
+            .enable_vertex_layout(gl, &self.vertex_layout, &self.vertex_buffer, 0);
 
         //
-        // Draw ship.
+        // Draw bullets.
         //
-        gl.draw_arrays(WebGlRenderingContext::TRIANGLE_STRIP, 0, 4);
+        self.instances.draw(
+            gl,
+            &self.program,
+            WebGlRenderingContext::TRIANGLE_STRIP,
+            4,
+            0..bullets.len(),
+        );
 
         //
         // Clean-up
         //
-        gl.use_program(None);
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, None);
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
     }
 }
 
-fn vertex_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
+fn vertex_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
 {
     gl::compile_vertex_shader(
-        &context,
+        context,
         r#"
         attribute vec4 position;
+        attribute vec2 instance_position;
+        attribute vec2 instance_size;
+        attribute vec2 instance_direction;
 
-        uniform mat4 world_matrix;
         uniform mat4 projection_matrix;
 
         varying float relative_x;
@@ -113,7 +149,11 @@ fn vertex_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
 
             vec4 p = position;
             p.y *= (1.0 - x) * 0.5 + x;
-            p = projection_matrix * world_matrix * p;
+            p.xy *= instance_size;
+            p.xy = vec2(p.x * instance_direction.x - p.y * instance_direction.y,
+                        p.x * instance_direction.y + p.y * instance_direction.x);
+            p.xy += instance_position;
+            p = projection_matrix * p;
 
             relative_x = x;
             projected_position = p;
@@ -124,15 +164,18 @@ fn vertex_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
     )
 }
 
-fn fragment_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
+fn fragment_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
 {
     gl::compile_fragment_shader(
-        &context,
+        context,
         r#"
         #define PI 3.14159265359
 
         precision mediump float;
 
+        uniform vec4 color;
+        uniform float shimmer;
+
         varying float relative_x;
         varying vec4 projected_position;
 
@@ -145,11 +188,121 @@ fn fragment_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, Strin
             float x = relative_x;
             float a = (1.0 - x) * 0.1 + x * 1.0;
 
-            gl_FragColor = vec4(abs(sin(d2 * 17.0)) * 0.8,
-                                0.8,
-                                abs(cos(d2 * 29.0)) * 0.8,
-                                a);
+            vec3 shimmering = vec3(abs(sin(d2 * 17.0)), 1.0, abs(cos(d2 * 29.0)));
+
+            gl_FragColor = vec4(color.rgb * mix(vec3(1.0), shimmering, shimmer), color.a * a);
         }
         "#,
     )
 }
+
+#[cfg(test)]
+mod tests
+{
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::gl::{
+        device::Capabilities,
+        recording::{
+            Command,
+            RecordingDevice,
+        },
+    };
+
+    type GL = WebGlRenderingContext;
+
+    /// Renders three bullets, returning the renderer and the commands it issued to render them.
+    fn render(device: RecordingDevice) -> (BulletRenderer, Vec<Command>)
+    {
+        let device = Rc::new(device);
+        let context = Context::with_device(device.clone(), 800, 600);
+
+        let mut renderer = BulletRenderer::new(&context).unwrap();
+        let bullets: Vec<Bullet> = [[1., 0.], [0., 2.], [-3., 0.]]
+            .iter()
+            .map(|velocity| {
+                Bullet::builder()
+                    .position([1., 1.])
+                    .velocity(*velocity)
+                    .size([0.02, 0.01])
+                    .countdown(60)
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+
+        device.take_commands();
+        renderer.render(&context, &bullets);
+        (renderer, device.take_commands())
+    }
+
+    fn draws(commands: &[Command]) -> Vec<Command>
+    {
+        commands
+            .iter()
+            .filter(|command| {
+                matches!(
+                    command,
+                    Command::DrawArrays { .. } | Command::DrawArraysInstanced { .. }
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn bullets_are_drawn_with_one_instanced_strip()
+    {
+        let (renderer, commands) = render(RecordingDevice::new());
+
+        assert_eq!(
+            draws(&commands),
+            [Command::DrawArraysInstanced {
+                mode: GL::TRIANGLE_STRIP,
+                first: 0,
+                count: 4,
+                instances: 3,
+            }]
+        );
+
+        // The per-instance attributes advance once per instance during the draw only.
+        let location = renderer.program.attribute("instance_direction");
+        let divisors: Vec<u32> = commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::VertexAttribDivisor {
+                    location: l,
+                    divisor,
+                } if *l == location => Some(*divisor),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(divisors, [1, 0]);
+    }
+
+    #[test]
+    fn each_bullet_is_drawn_with_its_own_strip_without_instancing()
+    {
+        let (renderer, commands) = render(RecordingDevice::with_capabilities(Capabilities::none()));
+
+        let strip = Command::DrawArrays {
+            mode: GL::TRIANGLE_STRIP,
+            first: 0,
+            count: 4,
+        };
+        assert_eq!(draws(&commands), vec![strip; 3]);
+
+        let location = renderer.program.attribute("instance_direction");
+        let directions: Vec<Vec<f32>> = commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::VertexAttrib { location: l, value } if *l == location => {
+                    Some(value.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(directions, [[1., 0.], [0., 1.], [-1., 0.]]);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::world::WorldEvent;
+
+/// Trauma added by the ship bouncing off rocks, per unit of impulse.
+const IMPULSE_TRAUMA: f32 = 200.;
+
+/// Trauma added by a rock being destroyed, per unit of its width.
+const ROCK_DESTROYED_TRAUMA: f32 = 3.;
+
+/// Trauma lost every rendered frame.
+const TRAUMA_DECAY: f32 = 0.025;
+
+/// Greatest offset of the view, in units of the world.
+const MAX_OFFSET: f32 = 0.08;
+
+/// Greatest rotation of the view, in radians.
+const MAX_ANGLE: f32 = 0.03;
+
+/// Speed of the noise the view shakes with, in radians per rendered frame.
+const SHAKE_SPEED: f32 = 0.9;
+
+/// Number of frames the world is held still for when a rock is destroyed and when the ship loses
+/// a life.
+const ROCK_DESTROYED_HIT_STOP: u32 = 2;
+const SHIP_HIT_HIT_STOP: u32 = 6;
+
+/// An offset and a rotation of the view of the foreground, around its center.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CameraView
+{
+    /// Offset in units of the world.
+    pub offset: [f32; 2],
+
+    /// Angle in radians.
+    pub angle: f32,
+}
+
+/// Screen shake and hit-stop, giving feedback on collisions.
+///
+/// The shake grows with the trauma of the events of the world, which decays over time. It only
+/// moves the view of the foreground. The hit-stop holds the world still for a few frames by
+/// skipping their steps, as if the game was paused. Neither changes the simulation.
+#[derive(Debug)]
+pub struct Camera
+{
+    /// Disables the shake and the hit-stop.
+    pub reduced_motion: bool,
+
+    /// From 0 to 1, the shake being proportional to its square.
+    trauma: f32,
+
+    /// Number of frames with steps left to hold the world still for.
+    hit_stop: u32,
+
+    /// Number of frames rendered, the time of the noise of the shake.
+    frame: u32,
+}
+
+impl Camera
+{
+    pub fn new() -> Camera
+    {
+        Camera {
+            reduced_motion: false,
+            trauma: 0.,
+            hit_stop: 0,
+            frame: 0,
+        }
+    }
+
+    /// Adds trauma and hit-stop for the `events` of a step of the world.
+    pub fn step(&mut self, events: &[WorldEvent])
+    {
+        if self.reduced_motion {
+            return;
+        }
+
+        for event in events {
+            match event {
+                WorldEvent::ShipCollision { impulse, .. } => {
+                    self.add_trauma(impulse * IMPULSE_TRAUMA);
+                }
+                WorldEvent::RockDestroyed { size, .. } => {
+                    self.add_trauma(size[0] * ROCK_DESTROYED_TRAUMA);
+                    self.hit_stop = self.hit_stop.max(ROCK_DESTROYED_HIT_STOP);
+                }
+                WorldEvent::ShipHit { .. } => {
+                    self.hit_stop = self.hit_stop.max(SHIP_HIT_HIT_STOP);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The number of the `steps` asked for by the clock this frame to perform, none while a
+    /// hit-stop holds the world still. The steps asked for while the clock is `paused` are single
+    /// steps requested by the player, which are always performed.
+    pub fn hold(&mut self, steps: u32, paused: bool) -> u32
+    {
+        if paused || self.hit_stop == 0 || steps == 0 {
+            return steps;
+        }
+
+        self.hit_stop -= 1;
+        0
+    }
+
+    /// Advances the shake by a rendered frame and returns the view to render it with.
+    pub fn update(&mut self) -> CameraView
+    {
+        if self.reduced_motion {
+            self.trauma = 0.;
+            self.hit_stop = 0;
+            return CameraView::default();
+        }
+
+        self.frame = self.frame.wrapping_add(1);
+        self.trauma = (self.trauma - TRAUMA_DECAY).max(0.);
+
+        let shake = self.trauma * self.trauma;
+        let t = self.frame as f32 * SHAKE_SPEED;
+
+        CameraView {
+            offset: [
+                MAX_OFFSET * shake * noise(t, 0.),
+                MAX_OFFSET * shake * noise(t, 1.),
+            ],
+            angle: MAX_ANGLE * shake * noise(t, 2.),
+        }
+    }
+
+    fn add_trauma(&mut self, amount: f32)
+    {
+        self.trauma = (self.trauma + amount).min(1.);
+    }
+}
+
+/// Smooth noise from -1 to 1 at time `t`, different for each `seed`.
+fn noise(t: f32, seed: f32) -> f32
+{
+    0.6 * (t + seed * 1.3).sin()
+        + 0.3 * (t * 2.1 + seed * 2.9).sin()
+        + 0.1 * (t * 4.3 + seed * 4.7).sin()
+}
@@ -0,0 +1,105 @@
+/// The greatest allowed time scale.
+const TIME_SCALE_MAX: f32 = 4.;
+
+/// Time scale used when slow motion is toggled on.
+const SLOW_MOTION_TIME_SCALE: f32 = 0.25;
+
+/// Decides how many times the world should be stepped each rendered frame.
+///
+/// The world is always advanced in whole steps, so counters that are updated once per step, like
+/// the energy regeneration and the gun cooldown, behave the same regardless of the time scale. With
+/// a time scale below one some frames perform no step and with a time scale above one some frames
+/// perform several.
+#[derive(Debug)]
+pub struct SimulationClock
+{
+    paused: bool,
+    time_scale: f32,
+    accumulator: f32,
+    pending_steps: u32,
+}
+
+impl SimulationClock
+{
+    pub fn new() -> SimulationClock
+    {
+        SimulationClock {
+            paused: false,
+            time_scale: 1.,
+            accumulator: 0.,
+            pending_steps: 0,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool
+    {
+        self.paused
+    }
+
+    pub fn pause(&mut self)
+    {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self)
+    {
+        self.paused = false;
+        self.pending_steps = 0;
+    }
+
+    pub fn toggle_pause(&mut self)
+    {
+        if self.paused {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// Requests a single step while paused. Does nothing if the clock is running.
+    pub fn step_frame(&mut self)
+    {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    pub fn time_scale(&self) -> f32
+    {
+        self.time_scale
+    }
+
+    /// Sets the time scale, clamped to the range `[0, 4]`.
+    pub fn set_time_scale(&mut self, time_scale: f32)
+    {
+        self.time_scale = if time_scale.is_nan() {
+            1.
+        } else {
+            time_scale.clamp(0., TIME_SCALE_MAX)
+        };
+    }
+
+    /// Switches between slow motion and normal speed.
+    pub fn toggle_slow_motion(&mut self)
+    {
+        self.set_time_scale(if self.time_scale < 1. {
+            1.
+        } else {
+            SLOW_MOTION_TIME_SCALE
+        });
+    }
+
+    /// Advances the clock one rendered frame and returns the number of steps to perform.
+    pub fn advance(&mut self) -> u32
+    {
+        if self.paused {
+            return std::mem::take(&mut self.pending_steps);
+        }
+
+        self.accumulator += self.time_scale;
+        let steps = self.accumulator.floor();
+        self.accumulator -= steps;
+
+        steps as u32
+    }
+}
@@ -34,10 +34,13 @@ impl OtherCollisionObject
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Getters)]
 pub struct CircularHitbox
 {
+    #[get = "pub"]
     position: [f32; 2],
+
+    #[get = "pub"]
     radius: f32,
 }
 
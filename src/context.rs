@@ -1,34 +1,96 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+};
+
 use getset::{
     Getters,
     Setters,
 };
 use web_sys::WebGlRenderingContext;
 
-use crate::matrix::OrthographicProjection;
+use crate::{
+    camera::CameraView,
+    gl::{
+        device::{
+            FramebufferId,
+            GraphicsDevice,
+        },
+        framebuffer::Framebuffer,
+    },
+    matrix::{
+        Matrix4x4,
+        OrthographicProjection,
+        Rotate,
+        Translate,
+    },
+    theme::Theme,
+};
 
 #[derive(Builder)]
 pub struct ContextDescriptor
 {
     canvas_width: u32,
     canvas_height: u32,
-    render_context: WebGlRenderingContext,
+    device: Rc<dyn GraphicsDevice>,
     foreground_projection_matrix: OrthographicProjection,
+
+    #[builder(default = "Theme::default()")]
+    theme: Theme,
+}
+
+/// Where rendering goes: a framebuffer, the viewport into it and the color it is cleared with.
+#[derive(Clone, Debug)]
+pub struct RenderTarget
+{
+    /// The framebuffer, `None` for the canvas.
+    pub framebuffer: Option<FramebufferId>,
+
+    /// The viewport as `[x, y, width, height]` in pixels.
+    pub viewport: [i32; 4],
+
+    pub clear_color: [f32; 4],
+}
+
+impl RenderTarget
+{
+    /// A target covering the whole of `framebuffer`, cleared to transparent black.
+    pub fn framebuffer(framebuffer: &Framebuffer) -> RenderTarget
+    {
+        RenderTarget {
+            framebuffer: Some(*framebuffer.framebuffer()),
+            viewport: [0, 0, *framebuffer.width(), *framebuffer.height()],
+            clear_color: [0., 0., 0., 0.],
+        }
+    }
 }
 
 #[derive(Getters, Setters)]
 pub struct Context
 {
-    #[getset(get = "pub")]
+    /// Width of the drawing buffer in device pixels.
+    #[getset(get = "pub", set = "pub")]
     canvas_width: u32,
 
-    #[getset(get = "pub")]
+    /// Height of the drawing buffer in device pixels.
+    #[getset(get = "pub", set = "pub")]
     canvas_height: u32,
 
-    #[getset(get = "pub")]
-    render_context: WebGlRenderingContext,
+    device: Rc<dyn GraphicsDevice>,
 
     #[getset(get = "pub")]
     foreground_projection_matrix: OrthographicProjection,
+
+    /// Camera effects on top of the projection of the foreground.
+    #[getset(get = "pub", set = "pub")]
+    foreground_view: CameraView,
+
+    /// The colors every renderer draws with.
+    #[getset(get = "pub", set = "pub")]
+    theme: Theme,
+
+    /// The render targets pushed on top of the canvas, the last one is the current target.
+    render_targets: RefCell<Vec<RenderTarget>>,
 }
 
 impl Context
@@ -38,8 +100,137 @@ impl Context
         Context {
             canvas_width: descriptor.canvas_width,
             canvas_height: descriptor.canvas_height,
-            render_context: descriptor.render_context,
+            device: descriptor.device,
             foreground_projection_matrix: descriptor.foreground_projection_matrix,
+            foreground_view: CameraView::default(),
+            theme: descriptor.theme,
+            render_targets: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The graphics device every renderer draws with.
+    pub fn render_context(&self) -> &dyn GraphicsDevice
+    {
+        &*self.device
+    }
+
+    /// The matrix the foreground is rendered with, its projection with the view on top of it.
+    pub fn foreground_matrix(&self) -> [f32; 16]
+    {
+        let view = &self.foreground_view;
+        let projection = &self.foreground_projection_matrix;
+        let center = projection.unproject([0., 0.]);
+
+        // Rotated around the center of the projection, then moved.
+        let matrix = Translate::id()
+            .x(-center[0])
+            .y(-center[1])
+            .into_arr2()
+            .dot(&Rotate::id().radians(view.angle).into_arr2())
+            .dot(
+                &Translate::id()
+                    .x(center[0] + view.offset[0])
+                    .y(center[1] + view.offset[1])
+                    .into_arr2(),
+            )
+            .dot(&projection.clone().into_arr2());
+
+        let mut array = [0.; 16];
+        array.copy_from_slice(matrix.as_slice().unwrap());
+        array
+    }
+
+    /// Prepares the context for rendering again after the WebGL context has been restored.
+    pub fn restore(&mut self)
+    {
+        self.device.restore();
+        self.render_targets.borrow_mut().clear();
+    }
+
+    /// The canvas as a render target, which is the target when no other has been pushed.
+    pub fn canvas_render_target(&self) -> RenderTarget
+    {
+        RenderTarget {
+            framebuffer: None,
+            viewport: [0, 0, self.canvas_width as i32, self.canvas_height as i32],
+            clear_color: self.theme.clear_color,
         }
     }
+
+    /// The target currently rendered into.
+    pub fn render_target(&self) -> RenderTarget
+    {
+        self.render_targets
+            .borrow()
+            .last()
+            .cloned()
+            .unwrap_or_else(|| self.canvas_render_target())
+    }
+
+    /// Makes `target` the current render target until it is popped.
+    pub fn push_render_target(&self, target: RenderTarget)
+    {
+        self.apply_render_target(&target);
+        self.render_targets.borrow_mut().push(target);
+    }
+
+    /// Restores the render target that was current before the last push.
+    ///
+    /// Panics if no render target has been pushed.
+    pub fn pop_render_target(&self)
+    {
+        self.render_targets
+            .borrow_mut()
+            .pop()
+            .expect("no render target to pop");
+        self.apply_render_target(&self.render_target());
+    }
+
+    /// Performs `lambda` with `target` as render target, restoring the previous target
+    /// afterwards.
+    pub fn with_render_target<R, F: FnOnce() -> R>(&self, target: RenderTarget, lambda: F) -> R
+    {
+        self.push_render_target(target);
+        let result = lambda();
+        self.pop_render_target();
+        result
+    }
+
+    /// Clears the current render target with its clear color.
+    pub fn clear(&self)
+    {
+        let [r, g, b, a] = self.render_target().clear_color;
+
+        self.render_context().clear_color(r, g, b, a);
+        self.render_context()
+            .clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+    }
+
+    fn apply_render_target(&self, target: &RenderTarget)
+    {
+        let gl = self.render_context();
+        let [x, y, width, height] = target.viewport;
+
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, target.framebuffer);
+        gl.viewport(x, y, width, height);
+    }
+}
+
+#[cfg(test)]
+impl Context
+{
+    /// A context with the default theme drawing with `device` into a canvas of `width` by
+    /// `height` pixels.
+    pub fn with_device(device: Rc<dyn GraphicsDevice>, width: u32, height: u32) -> Context
+    {
+        Context::new(
+            ContextDescriptorBuilder::default()
+                .device(device)
+                .canvas_width(width)
+                .canvas_height(height)
+                .foreground_projection_matrix(crate::scene::foreground_projection_matrix())
+                .build()
+                .unwrap(),
+        )
+    }
 }
@@ -0,0 +1,281 @@
+use std::f32::consts::PI;
+
+use web_sys::WebGlRenderingContext;
+
+use crate::{
+    collision::CircularHitbox,
+    context::Context,
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        program::{
+            Mat4,
+            Program,
+            Uniform,
+        },
+        text::TextRenderer,
+    },
+    world::World,
+};
+
+const ROCK_COLOR: [f32; 4] = [1.0, 0.6, 0.2, 1.0];
+const SHIP_COLOR: [f32; 4] = [0.3, 1.0, 0.3, 1.0];
+const BULLET_COLOR: [f32; 4] = [1.0, 1.0, 0.3, 1.0];
+const VELOCITY_COLOR: [f32; 4] = [0.3, 0.7, 1.0, 1.0];
+const CONTACT_COLOR: [f32; 4] = [1.0, 0.2, 0.8, 1.0];
+const SEAM_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.3];
+const TEXT_COLOR: [f32; 4] = [0.3, 1.0, 0.3, 1.0];
+
+/// Number of line segments hitbox outlines are drawn with.
+const CIRCLE_SEGMENTS: usize = 24;
+
+/// Number of frames of movement the velocity arrows are drawn with, velocities are too short to
+/// see otherwise.
+const VELOCITY_SCALE: f32 = 30.;
+
+/// Half the size of the crosses marking contact points.
+const CONTACT_SIZE: f32 = 0.02;
+
+/// Draws the internals of the simulation on top of the game: hitbox outlines, velocity arrows,
+/// contact points and the seams where the foreground wraps around, with a panel of entity counts
+/// and the frame time.
+pub struct DebugOverlay
+{
+    program: Program,
+    projection_matrix: Uniform<Mat4>,
+    vertex_layout: VertexLayout,
+    vertex_buffer: ArrayBuffer<f32>,
+
+    /// Line vertices, two per line.
+    vertices: Vec<f32>,
+
+    text: TextRenderer,
+}
+
+impl DebugOverlay
+{
+    pub fn new(context: &Context) -> Result<DebugOverlay, String>
+    {
+        let gl = context.render_context();
+
+        let vertex_layout = VertexLayout::new()
+            .attribute("position", 2, VertexAttributeType::Float)
+            .attribute("color", 4, VertexAttributeType::Float);
+
+        let program = Program::new(
+            gl,
+            &vertex_shader(gl)?,
+            &fragment_shader(gl)?,
+            &[&vertex_layout],
+        )?;
+
+        Ok(DebugOverlay {
+            projection_matrix: program.uniform("projection_matrix")?,
+            program,
+            vertex_layout,
+            vertex_buffer: ArrayBuffer::new(gl)?,
+            vertices: Vec::new(),
+            text: TextRenderer::new(gl)?,
+        })
+    }
+
+    /// Draws the lines of `world`. Should be called with the foreground texture as render target,
+    /// so that lines crossing a seam wrap around like the objects they belong to.
+    pub fn render_world(&mut self, context: &Context, world: &World)
+    {
+        self.vertices.clear();
+
+        //
+        // Wrap seams.
+        //
+        self.line([0., -1.], [0., 4.], SEAM_COLOR);
+        self.line([4., -1.], [4., 4.], SEAM_COLOR);
+        self.line([-1., 0.], [5., 0.], SEAM_COLOR);
+        self.line([-1., 3.], [5., 3.], SEAM_COLOR);
+
+        //
+        // Hitboxes and velocities.
+        //
+        for rock in world.rocks() {
+            self.circle(&rock.hitbox(), ROCK_COLOR);
+            self.arrow(*rock.position(), *rock.velocity());
+        }
+
+        for bullet in world.bullets() {
+            self.circle(&bullet.hitbox(), BULLET_COLOR);
+        }
+
+        let ship = world.ship();
+        self.circle(&ship.hitbox(), SHIP_COLOR);
+        self.arrow(*ship.position(), *ship.velocity());
+        drop(ship);
+
+        //
+        // Contact points.
+        //
+        for contact in world.contacts() {
+            let [x, y] = *contact;
+            let d = CONTACT_SIZE;
+            self.line([x - d, y - d], [x + d, y + d], CONTACT_COLOR);
+            self.line([x - d, y + d], [x + d, y - d], CONTACT_COLOR);
+        }
+
+        //
+        // Draw.
+        //
+        let gl = context.render_context();
+
+        self.vertex_buffer
+            .update(gl, BufferUsage::DynamicDraw, &self.vertices);
+
+        self.program.use_program(gl);
+
+        let matrix = context.foreground_matrix();
+        self.projection_matrix.set(gl, &matrix);
+
+        self.program
+            .enable_vertex_layout(gl, &self.vertex_layout, &self.vertex_buffer, 0);
+
+        gl.draw_arrays(
+            WebGlRenderingContext::LINES,
+            0,
+            (self.vertices.len() / 6) as i32,
+        );
+
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
+    }
+
+    /// Draws the panel of entity counts and `frame_time`, in milliseconds, in the top middle of
+    /// the canvas.
+    pub fn render_panel(
+        &mut self,
+        context: &Context,
+        world: &World,
+        particle_count: usize,
+        frame_time: f64,
+    )
+    {
+        let width = *context.canvas_width() as f32;
+        let scale = (*context.canvas_height() as f32 / 360.).round().max(1.);
+        let line = 10. * scale;
+
+        let lines = [
+            format!("ROCKS     {:4}", world.rocks().len()),
+            format!("BULLETS   {:4}", world.bullets().len()),
+            format!("PARTICLES {:4}", particle_count),
+            format!("CONTACTS  {:4}", world.contacts().len()),
+            format!("FRAME {:5.1}MS", frame_time),
+            String::from(context.render_context().name()),
+        ];
+
+        let size = TextRenderer::measure(&lines[0], scale);
+        let x = (width - size[0]) / 2.;
+
+        for (i, text) in lines.iter().enumerate() {
+            self.text
+                .text(text, [x, 4. * scale + i as f32 * line], scale, TEXT_COLOR);
+        }
+
+        self.text.render(
+            context.render_context(),
+            *context.canvas_width(),
+            *context.canvas_height(),
+        );
+    }
+
+    fn line(&mut self, start: [f32; 2], end: [f32; 2], color: [f32; 4])
+    {
+        self.vertices.extend_from_slice(&start);
+        self.vertices.extend_from_slice(&color);
+        self.vertices.extend_from_slice(&end);
+        self.vertices.extend_from_slice(&color);
+    }
+
+    fn circle(&mut self, hitbox: &CircularHitbox, color: [f32; 4])
+    {
+        let [x, y] = *hitbox.position();
+        let radius = *hitbox.radius();
+        let point = |i: usize| {
+            let angle = 2. * PI * i as f32 / CIRCLE_SEGMENTS as f32;
+            [x + radius * angle.cos(), y + radius * angle.sin()]
+        };
+
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    /// An arrow from `position` showing `velocity`.
+    fn arrow(&mut self, position: [f32; 2], velocity: [f32; 2])
+    {
+        let end = [
+            position[0] + velocity[0] * VELOCITY_SCALE,
+            position[1] + velocity[1] * VELOCITY_SCALE,
+        ];
+        self.line(position, end, VELOCITY_COLOR);
+
+        let length = (velocity[0].powi(2) + velocity[1].powi(2)).sqrt() * VELOCITY_SCALE;
+        if length < f32::EPSILON {
+            return;
+        }
+
+        // The head is a quarter of the arrow long, pointing backwards at 30° from the shaft.
+        let angle = velocity[1].atan2(velocity[0]);
+        for side in [-1., 1.].iter() {
+            let angle = angle + PI + side * PI / 6.;
+            let head = [
+                end[0] + angle.cos() * length / 4.,
+                end[1] + angle.sin() * length / 4.,
+            ];
+            self.line(end, head, VELOCITY_COLOR);
+        }
+    }
+}
+
+fn vertex_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_vertex_shader(
+        context,
+        r#"
+        attribute vec2 position;
+        attribute vec4 color;
+
+        uniform mat4 projection_matrix;
+
+        varying vec4 _color;
+
+        void main()
+        {
+            _color = color;
+            gl_Position = projection_matrix * vec4(position, 0.0, 1.0);
+        }
+        "#,
+    )
+}
+
+fn fragment_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_fragment_shader(
+        context,
+        r#"
+        precision mediump float;
+
+        varying vec4 _color;
+
+        void main()
+        {
+            gl_FragColor = _color;
+        }
+        "#,
+    )
+}
@@ -4,7 +4,10 @@ use wasm_bindgen::{
 };
 use web_sys::{
     Document,
-    HtmlCanvasElement,
+    Element,
+    Event,
+    EventTarget,
+    ResizeObserver,
     Window,
 };
 
@@ -18,11 +21,82 @@ pub fn document() -> Document
     window().document().unwrap()
 }
 
-pub fn canvas() -> Result<HtmlCanvasElement, JsValue>
+/// An event listener that is removed from its target when dropped.
+pub struct EventListener
 {
-    let document = document();
-    Ok(document
-        .get_element_by_id("canvas")
-        .unwrap()
-        .dyn_into::<HtmlCanvasElement>()?)
+    target: EventTarget,
+    event_type: String,
+    closure: Closure<dyn FnMut(Event)>,
+}
+
+impl EventListener
+{
+    /// Adds `lambda` as a listener of events of type `event_type` on `target`.
+    pub fn new<E, F>(
+        target: &EventTarget,
+        event_type: &str,
+        mut lambda: F,
+    ) -> Result<EventListener, JsValue>
+    where
+        E: JsCast,
+        F: 'static + FnMut(E),
+    {
+        let closure = Closure::wrap(
+            Box::new(move |event: Event| lambda(event.unchecked_into())) as Box<dyn FnMut(_)>
+        );
+        target.add_event_listener_with_callback(event_type, closure.as_ref().unchecked_ref())?;
+
+        Ok(EventListener {
+            target: target.clone(),
+            event_type: String::from(event_type),
+            closure,
+        })
+    }
+}
+
+impl Drop for EventListener
+{
+    fn drop(&mut self)
+    {
+        self.target
+            .remove_event_listener_with_callback(
+                &self.event_type,
+                self.closure.as_ref().unchecked_ref(),
+            )
+            .ok();
+    }
+}
+
+/// Observes the size of an element. The element is no longer observed when dropped.
+pub struct ResizeListener
+{
+    observer: ResizeObserver,
+    _closure: Closure<dyn FnMut()>,
+}
+
+impl ResizeListener
+{
+    /// Calls `lambda` whenever `element` is resized.
+    pub fn new<F: 'static + FnMut()>(
+        element: &Element,
+        lambda: F,
+    ) -> Result<ResizeListener, JsValue>
+    {
+        let closure = Closure::wrap(Box::new(lambda) as Box<dyn FnMut()>);
+        let observer = ResizeObserver::new(closure.as_ref().unchecked_ref())?;
+        observer.observe(element);
+
+        Ok(ResizeListener {
+            observer,
+            _closure: closure,
+        })
+    }
+}
+
+impl Drop for ResizeListener
+{
+    fn drop(&mut self)
+    {
+        self.observer.disconnect();
+    }
 }
@@ -1,32 +1,40 @@
 use std::iter;
 
-use wasm_bindgen::JsValue;
-use web_sys::{
-    WebGlBuffer,
-    WebGlFramebuffer,
-    WebGlProgram,
-    WebGlRenderingContext,
-    WebGlShader,
-};
+use web_sys::WebGlRenderingContext;
 
 use crate::{
-    context::Context,
+    context::{
+        Context,
+        RenderTarget,
+    },
     gl::{
         self,
         buffer::{
+            ArrayBuffer,
             BufferUsage,
             ElementArrayBuffer,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        framebuffer::{
+            Framebuffer,
+            FramebufferDescriptorBuilder,
         },
-        texture::{
-            Texture2D,
-            TextureMagnificationFilter,
-            TextureMinificationFilter,
-            TextureWrappingFunction,
+        program::{
+            Mat4,
+            Program,
+            Sampler2D,
+            Uniform,
         },
     },
     matrix::{
         Matrix4x4,
         OrthographicProjection,
+        OrthographicProjectionBuilder,
         Scale,
     },
 };
@@ -34,102 +42,104 @@ use crate::{
 /// Renders the foreground.
 pub struct ForegroundRenderer
 {
-    texture: Texture2D,
-    framebuffer: WebGlFramebuffer,
-    program: WebGlProgram,
-    vertex_buffer: WebGlBuffer,
+    framebuffer: Framebuffer,
+    program: Program,
+    projection_matrix: Uniform<Mat4>,
+    view_matrix: Uniform<Mat4>,
+    sampler: Uniform<Sampler2D>,
+    vertex_layout: VertexLayout,
+    vertex_buffer: ArrayBuffer<f32>,
     index_buffer: ElementArrayBuffer,
 }
 
 impl ForegroundRenderer
 {
     /// Creates a new `ForegroundRenderer`.
-    pub fn new(context: &Context) -> Result<ForegroundRenderer, JsValue>
+    pub fn new(context: &Context) -> Result<ForegroundRenderer, String>
     {
         let gl = context.render_context();
 
         //
         // Create the program for rendering the foreground texture.
         //
-        let program = gl::link_program(gl, &vertex_shader(gl)?, &fragment_shader(gl)?)?;
+        let vertex_layout = VertexLayout::new()
+            .attribute("position", 3, VertexAttributeType::Float)
+            .attribute("texcoord", 2, VertexAttributeType::Float);
+
+        let program = Program::new(
+            gl,
+            &vertex_shader(gl)?,
+            &fragment_shader(gl)?,
+            &[&vertex_layout],
+        )?;
 
         //
-        // Create and setup texture.
+        // Create framebuffer rendering into the foreground texture.
         //
-        let mut texture = Texture2D::new(gl)?;
         let (width, height) = calculate_texture_size(context);
-
-        texture
-            .specification()
-            .level(0)
-            .internal_format(WebGlRenderingContext::RGBA as i32)
-            .width(width)
-            .height(height)
-            .border(0)
-            .format(WebGlRenderingContext::RGBA)
-            .type_(WebGlRenderingContext::UNSIGNED_BYTE)
-            .min_filter(TextureMinificationFilter::Linear)
-            .mag_filter(TextureMagnificationFilter::Linear)
-            .wrap_s(TextureWrappingFunction::ClampToEdge)
-            .wrap_t(TextureWrappingFunction::ClampToEdge)
-            .update(gl)?;
+        let framebuffer = Framebuffer::new(
+            gl,
+            FramebufferDescriptorBuilder::default()
+                .width(width)
+                .height(height)
+                .build()
+                .map_err(|error| format!("{}", error))?,
+        )?;
 
         //
         // Create vertex buffer and index buffer.
         //
-        let vertex_buffer = gl::make_static_draw_array_buffer_f32(
-            gl,
-            vec![
-                //  0,  1,  2,  3,
-                vertex([0., 0., 0.], [1. / 6., 1. / 5.]),
-                vertex([4., 0., 0.], [5. / 6., 1. / 5.]),
-                vertex([4., 3., 0.], [5. / 6., 4. / 5.]),
-                vertex([0., 3., 0.], [1. / 6., 4. / 5.]),
-                //  4,  5,  6,  7,
-                vertex([0., 0., 0.], [5. / 6., 1. / 5.]),
-                vertex([1., 0., 0.], [6. / 6., 1. / 5.]),
-                vertex([1., 3., 0.], [6. / 6., 4. / 5.]),
-                vertex([0., 3., 0.], [5. / 6., 4. / 5.]),
-                //  8,  9, 10, 11,
-                vertex([3., 0., 0.], [0. / 6., 1. / 5.]),
-                vertex([4., 0., 0.], [1. / 6., 1. / 5.]),
-                vertex([4., 3., 0.], [1. / 6., 4. / 5.]),
-                vertex([3., 3., 0.], [0. / 6., 4. / 5.]),
-                // 12, 13, 14, 15,
-                vertex([0., 0., 0.], [1. / 6., 4. / 5.]),
-                vertex([4., 0., 0.], [5. / 6., 4. / 5.]),
-                vertex([4., 1., 0.], [5. / 6., 5. / 5.]),
-                vertex([0., 1., 0.], [1. / 6., 5. / 5.]),
-                // 16, 17, 18, 19,
-                vertex([0., 2., 0.], [1. / 6., 0. / 5.]),
-                vertex([4., 2., 0.], [5. / 6., 0. / 5.]),
-                vertex([4., 3., 0.], [5. / 6., 1. / 5.]),
-                vertex([0., 3., 0.], [1. / 6., 1. / 5.]),
-                // 20, 21, 22, 23,
-                vertex([3., 2., 0.], [0. / 6., 0. / 5.]),
-                vertex([4., 2., 0.], [1. / 6., 0. / 5.]),
-                vertex([4., 3., 0.], [1. / 6., 1. / 5.]),
-                vertex([3., 3., 0.], [0. / 6., 1. / 5.]),
-                // 24, 25, 26, 27,
-                vertex([0., 2., 0.], [5. / 6., 0. / 5.]),
-                vertex([1., 2., 0.], [6. / 6., 0. / 5.]),
-                vertex([1., 3., 0.], [6. / 6., 1. / 5.]),
-                vertex([0., 3., 0.], [5. / 6., 1. / 5.]),
-                // 28, 29, 30, 31,
-                vertex([0., 0., 0.], [5. / 6., 4. / 5.]),
-                vertex([1., 0., 0.], [6. / 6., 4. / 5.]),
-                vertex([1., 1., 0.], [6. / 6., 5. / 5.]),
-                vertex([0., 1., 0.], [5. / 6., 5. / 5.]),
-                // 32, 33, 34, 35,
-                vertex([3., 0., 0.], [0. / 6., 4. / 5.]),
-                vertex([4., 0., 0.], [1. / 6., 4. / 5.]),
-                vertex([4., 1., 0.], [1. / 6., 5. / 5.]),
-                vertex([3., 1., 0.], [0. / 6., 5. / 5.]),
-            ]
-            .into_iter()
-            .flatten()
-            .collect(),
-        )?;
+        let vertices: Vec<f32> = vec![
+            //  0,  1,  2,  3,
+            vertex([0., 0., 0.], [1. / 6., 1. / 5.]),
+            vertex([4., 0., 0.], [5. / 6., 1. / 5.]),
+            vertex([4., 3., 0.], [5. / 6., 4. / 5.]),
+            vertex([0., 3., 0.], [1. / 6., 4. / 5.]),
+            //  4,  5,  6,  7,
+            vertex([0., 0., 0.], [5. / 6., 1. / 5.]),
+            vertex([1., 0., 0.], [6. / 6., 1. / 5.]),
+            vertex([1., 3., 0.], [6. / 6., 4. / 5.]),
+            vertex([0., 3., 0.], [5. / 6., 4. / 5.]),
+            //  8,  9, 10, 11,
+            vertex([3., 0., 0.], [0. / 6., 1. / 5.]),
+            vertex([4., 0., 0.], [1. / 6., 1. / 5.]),
+            vertex([4., 3., 0.], [1. / 6., 4. / 5.]),
+            vertex([3., 3., 0.], [0. / 6., 4. / 5.]),
+            // 12, 13, 14, 15,
+            vertex([0., 0., 0.], [1. / 6., 4. / 5.]),
+            vertex([4., 0., 0.], [5. / 6., 4. / 5.]),
+            vertex([4., 1., 0.], [5. / 6., 5. / 5.]),
+            vertex([0., 1., 0.], [1. / 6., 5. / 5.]),
+            // 16, 17, 18, 19,
+            vertex([0., 2., 0.], [1. / 6., 0. / 5.]),
+            vertex([4., 2., 0.], [5. / 6., 0. / 5.]),
+            vertex([4., 3., 0.], [5. / 6., 1. / 5.]),
+            vertex([0., 3., 0.], [1. / 6., 1. / 5.]),
+            // 20, 21, 22, 23,
+            vertex([3., 2., 0.], [0. / 6., 0. / 5.]),
+            vertex([4., 2., 0.], [1. / 6., 0. / 5.]),
+            vertex([4., 3., 0.], [1. / 6., 1. / 5.]),
+            vertex([3., 3., 0.], [0. / 6., 1. / 5.]),
+            // 24, 25, 26, 27,
+            vertex([0., 2., 0.], [5. / 6., 0. / 5.]),
+            vertex([1., 2., 0.], [6. / 6., 0. / 5.]),
+            vertex([1., 3., 0.], [6. / 6., 1. / 5.]),
+            vertex([0., 3., 0.], [5. / 6., 1. / 5.]),
+            // 28, 29, 30, 31,
+            vertex([0., 0., 0.], [5. / 6., 4. / 5.]),
+            vertex([1., 0., 0.], [6. / 6., 4. / 5.]),
+            vertex([1., 1., 0.], [6. / 6., 5. / 5.]),
+            vertex([0., 1., 0.], [5. / 6., 5. / 5.]),
+            // 32, 33, 34, 35,
+            vertex([3., 0., 0.], [0. / 6., 4. / 5.]),
+            vertex([4., 0., 0.], [1. / 6., 4. / 5.]),
+            vertex([4., 1., 0.], [1. / 6., 5. / 5.]),
+            vertex([3., 1., 0.], [0. / 6., 5. / 5.]),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let vertex_buffer = ArrayBuffer::with_data(gl, BufferUsage::StaticDraw, &vertices)?;
 
         let mut index_buffer = ElementArrayBuffer::new(gl)?;
         index_buffer.set_data(
@@ -142,114 +152,68 @@ impl ForegroundRenderer
             ],
         );
 
-        //
-        // Create framebuffer.
-        //
-        let framebuffer = gl
-            .create_framebuffer()
-            .ok_or("failed to create framebuffer")?;
-
         Ok(ForegroundRenderer {
-            texture,
             framebuffer,
+            projection_matrix: program.uniform("projection_matrix")?,
+            view_matrix: program.uniform("view_matrix")?,
+            sampler: program.uniform("texture")?,
             program,
+            vertex_layout,
             vertex_buffer,
             index_buffer,
         })
     }
 
-    /// Performs `lambda` with the foreground texture as render target.
-    pub fn with_render_target_foreground_texture<F: FnOnce()>(&self, context: &Context, lambda: F)
+    /// Reallocates the foreground texture to fit the current canvas size of `context`.
+    pub fn resize(&mut self, context: &Context) -> Result<(), String>
     {
-        let gl = context.render_context();
-
-        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, Some(&self.framebuffer));
-        gl.framebuffer_texture_2d(
-            WebGlRenderingContext::FRAMEBUFFER,
-            WebGlRenderingContext::COLOR_ATTACHMENT0,
-            WebGlRenderingContext::TEXTURE_2D,
-            Some(self.texture.texture()),
-            0,
-        );
-
         let (width, height) = calculate_texture_size(context);
-        gl.viewport(0, 0, width as i32, height as i32);
-
-        lambda();
-
-        //
-        // Cleanup.
-        //
-
-        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+        self.framebuffer
+            .resize(context.render_context(), width, height)
+    }
 
-        // Restore viewport
-        //
-        // TODO:
-        //   The context could probably keep track of a stack of viewport sizes so that it would be possible to draw
-        //   into several textures.
-        gl.viewport(
-            0,
-            0,
-            context.canvas_width().clone() as i32,
-            context.canvas_height().clone() as i32,
-        );
+    /// Performs `lambda` with the foreground texture as render target.
+    pub fn with_render_target_foreground_texture<F: FnOnce()>(&self, context: &Context, lambda: F)
+    {
+        context.with_render_target(RenderTarget::framebuffer(&self.framebuffer), lambda);
     }
 
     pub fn render(&self, context: &Context)
     {
         let gl = context.render_context();
 
-        gl.use_program(Some(&self.program));
+        self.program.use_program(gl);
 
         //
         // Projection matrix.
         //
-        let location = gl.get_uniform_location(&self.program, "projection_matrix");
-        let matrix = OrthographicProjection::default()
-            .abscissa(0. ..4.)
-            .ordinate(0. ..3.)
-            .into_array();
-        gl.uniform_matrix4fv_with_f32_array(location.as_ref(), false, &matrix);
+        let matrix = projection().into_array();
+        self.projection_matrix.set(gl, &matrix);
 
         //
         // View matrix.
         //
-        let canvas_width = context.canvas_width().clone() as f32;
-        let canvas_height = context.canvas_height().clone() as f32;
-
-        let matrix = if (4. / 3.) * canvas_height > canvas_width {
-            let (w, h) = (canvas_width * (3. / 4.), canvas_height);
-            Scale::id().y(w / h).into_array()
-        } else {
-            let (w, h) = (canvas_width, canvas_height * (4. / 3.));
-            Scale::id().x(h / w).into_array()
-        };
+        let matrix = Scale::id().vec2(&view_scale(context)).into_array();
+        self.view_matrix.set(gl, &matrix);
 
-        let location = gl.get_uniform_location(&self.program, "view_matrix");
-        gl.uniform_matrix4fv_with_f32_array(location.as_ref(), false, &matrix);
+        //
+        // Sample the foreground texture from the first texture unit.
+        //
+        self.sampler.set(gl, &0);
 
         //
         // Setup vertex buffer.
         //
-        gl.bind_buffer(
-            WebGlRenderingContext::ARRAY_BUFFER,
-            Some(&self.vertex_buffer),
-        );
-        gl.enable_vertex_attrib_array(0);
-        gl.vertex_attrib_pointer_with_i32(0, 3, WebGlRenderingContext::FLOAT, false, 20, 0);
-        gl.enable_vertex_attrib_array(1);
-        gl.vertex_attrib_pointer_with_i32(1, 2, WebGlRenderingContext::FLOAT, false, 20, 12);
-
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, None);
+        self.program
+            .enable_vertex_layout(gl, &self.vertex_layout, &self.vertex_buffer, 0);
 
         //
         // Draw.
         //
-        self.texture.bind(gl);
+        self.framebuffer.texture().bind(gl);
         self.index_buffer.bind(gl);
 
-        gl.draw_elements_with_i32(
+        gl.draw_elements(
             WebGlRenderingContext::TRIANGLES,
             self.index_buffer.len().unwrap() as i32,
             WebGlRenderingContext::UNSIGNED_SHORT,
@@ -260,12 +224,50 @@ impl ForegroundRenderer
         // Clean-up.
         //
         self.index_buffer.unbind(gl);
-        self.texture.unbind(gl);
-        gl.use_program(None);
+        self.framebuffer.texture().unbind(gl);
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
+    }
+}
+
+/// Maps a position in device pixels, relative to the top left corner of the canvas, to a position in
+/// the foreground.
+pub fn canvas_to_foreground(context: &Context, position: [f32; 2]) -> [f32; 2]
+{
+    let canvas_width = *context.canvas_width() as f32;
+    let canvas_height = *context.canvas_height() as f32;
+    let scale = view_scale(context);
+
+    let x = (position[0] / canvas_width * 2. - 1.) / scale[0];
+    let y = (1. - position[1] / canvas_height * 2.) / scale[1];
+
+    projection().build().unproject([x, y])
+}
+
+/// The projection of the foreground onto the canvas.
+fn projection() -> OrthographicProjectionBuilder
+{
+    OrthographicProjection::default()
+        .abscissa(0. ..4.)
+        .ordinate(0. ..3.)
+}
+
+/// Scaling that letterboxes the foreground so that it keeps its aspect ratio.
+fn view_scale(context: &Context) -> [f32; 2]
+{
+    let canvas_width = *context.canvas_width() as f32;
+    let canvas_height = *context.canvas_height() as f32;
+
+    if (4. / 3.) * canvas_height > canvas_width {
+        let (w, h) = (canvas_width * (3. / 4.), canvas_height);
+        [1., w / h]
+    } else {
+        let (w, h) = (canvas_width, canvas_height * (4. / 3.));
+        [h / w, 1.]
     }
 }
 
-fn vertex_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
+fn vertex_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
 {
     gl::compile_vertex_shader(
         context,
@@ -289,7 +291,7 @@ fn vertex_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
     )
 }
 
-fn fragment_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
+fn fragment_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
 {
     gl::compile_fragment_shader(
         context,
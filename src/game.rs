@@ -0,0 +1,632 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+};
+
+use js_sys::Reflect;
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    Event,
+    HtmlCanvasElement,
+    MouseEvent,
+};
+
+use crate::{
+    accessibility::AccessibilitySettings,
+    context::{
+        Context,
+        ContextDescriptorBuilder,
+    },
+    dom::{
+        self,
+        EventListener,
+        ResizeListener,
+    },
+    gl::{
+        backend::Backend,
+        webgl::WebGlDevice,
+    },
+    keyboard_event_bus::KeyboardEventBus,
+    mouse_event_bus::MouseEventBus,
+    replay::Replay,
+    run_loop::RunLoop,
+    scene::{
+        self,
+        FrameInput,
+        Scene,
+    },
+    theme::Theme,
+};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+}
+
+/// The largest integer a JavaScript number holds exactly, `Number.MAX_SAFE_INTEGER`.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.;
+
+/// Options a `Game` can be created with.
+///
+/// From JavaScript the options are passed as an object where every property is optional:
+///
+/// ```js
+/// { seed: 1234, mouseAim: true }
+/// ```
+#[derive(Clone, Debug, Default)]
+struct GameOptions
+{
+    /// Seed of every world, a random seed is used for each world if not set.
+    seed: Option<u64>,
+
+    /// Start with mouse aim enabled.
+    mouse_aim: bool,
+}
+
+impl GameOptions
+{
+    fn from_js(options: &JsValue) -> Result<GameOptions, JsValue>
+    {
+        let mut game_options = GameOptions::default();
+
+        if options.is_undefined() || options.is_null() {
+            return Ok(game_options);
+        }
+
+        let seed = Reflect::get(options, &"seed".into())?;
+        if !seed.is_undefined() {
+            // Larger numbers aren't integers exactly, so different seeds could make the same world.
+            let seed = seed
+                .as_f64()
+                .filter(|seed| seed.fract() == 0. && (0. ..=MAX_SAFE_INTEGER).contains(seed))
+                .ok_or("option 'seed' must be an integer from 0 to Number.MAX_SAFE_INTEGER")?;
+            game_options.seed = Some(seed as u64);
+        }
+
+        let mouse_aim = Reflect::get(options, &"mouseAim".into())?;
+        if !mouse_aim.is_undefined() {
+            game_options.mouse_aim = mouse_aim
+                .as_bool()
+                .ok_or("option 'mouseAim' must be a boolean")?;
+        }
+
+        Ok(game_options)
+    }
+}
+
+/// A game running in a canvas.
+///
+/// Several games can run on the same page, each in its own canvas. The keyboard controls the game
+/// whose canvas has focus.
+///
+/// The size of the canvas should be set with CSS. The resolution of the drawing buffer follows the
+/// size of the canvas and the device pixel ratio.
+#[wasm_bindgen]
+pub struct Game
+{
+    state: Rc<RefCell<GameState>>,
+    run_loop: RunLoop,
+    _listeners: Vec<EventListener>,
+    _resize_listener: ResizeListener,
+}
+
+#[wasm_bindgen]
+impl Game
+{
+    /// Creates a new game rendering into `canvas`. The game doesn't run until `start` is called.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: HtmlCanvasElement, options: JsValue) -> Result<Game, JsValue>
+    {
+        let options = GameOptions::from_js(&options)?;
+        let state = Rc::new(RefCell::new(GameState::new(&canvas, options)?));
+
+        let run_loop = RunLoop::new({
+            let state = Rc::downgrade(&state);
+            move || {
+                if let Some(state) = state.upgrade() {
+                    state.borrow_mut().frame();
+                }
+            }
+        });
+
+        // The canvas needs a tab index to be able to receive keyboard focus.
+        if !canvas.has_attribute("tabindex") {
+            canvas.set_attribute("tabindex", "0")?;
+        }
+
+        let listeners = vec![
+            //
+            // Pause the game when the page becomes hidden or the window loses focus.
+            //
+            EventListener::new(&dom::document(), "visibilitychange", {
+                let state = Rc::downgrade(&state);
+                move |_: Event| {
+                    if let Some(state) = state.upgrade() {
+                        if dom::document().hidden() {
+                            state.borrow_mut().pause();
+                        }
+                    }
+                }
+            })?,
+            //
+            // Stop stepping and rendering while the WebGL context is lost, and recreate every GPU
+            // resource when it is restored.
+            //
+            EventListener::new(&canvas, "webglcontextlost", {
+                let state = Rc::downgrade(&state);
+                move |event: Event| {
+                    // The context is only restored if the default action is prevented.
+                    event.prevent_default();
+
+                    if let Some(state) = state.upgrade() {
+                        state.borrow_mut().lose_context();
+                    }
+                }
+            })?,
+            EventListener::new(&canvas, "webglcontextrestored", {
+                let state = Rc::downgrade(&state);
+                move |_: Event| {
+                    if let Some(state) = state.upgrade() {
+                        if let Err(error) = state.borrow_mut().restore_context() {
+                            log(&format!("failed to restore the WebGL context: {:?}", error));
+                        }
+                    }
+                }
+            })?,
+            EventListener::new(&dom::window(), "blur", {
+                let state = Rc::downgrade(&state);
+                move |_: Event| {
+                    if let Some(state) = state.upgrade() {
+                        state.borrow_mut().pause();
+                    }
+                }
+            })?,
+            //
+            // Move the background when clicking the canvas.
+            //
+            EventListener::new(&canvas, "mousedown", {
+                let state = Rc::downgrade(&state);
+                let canvas = canvas.clone();
+                move |event: MouseEvent| {
+                    if let Some(state) = state.upgrade() {
+                        state.borrow_mut().move_background(&canvas, &event);
+                    }
+                }
+            })?,
+        ];
+
+        let resize_listener = ResizeListener::new(&canvas, {
+            let state = Rc::downgrade(&state);
+            move || {
+                if let Some(state) = state.upgrade() {
+                    state.borrow_mut().resize_pending = true;
+                }
+            }
+        })?;
+
+        Ok(Game {
+            state,
+            run_loop,
+            _listeners: listeners,
+            _resize_listener: resize_listener,
+        })
+    }
+
+    /// Starts running the game and gives its canvas keyboard focus.
+    pub fn start(&self) -> Result<(), JsValue>
+    {
+        self.state.borrow().canvas.focus()?;
+        self.run_loop.start()
+    }
+
+    /// Stops running the game. The game can be started again with `start`.
+    pub fn stop(&self)
+    {
+        self.run_loop.stop();
+    }
+
+    pub fn is_running(&self) -> bool
+    {
+        self.run_loop.is_running()
+    }
+
+    /// Replaces the world with a new one and starts a new recording.
+    pub fn restart(&self) -> Result<(), JsValue>
+    {
+        Ok(self.state.borrow_mut().scene.restart()?)
+    }
+
+    /// The replay of the current game encoded as bytes.
+    pub fn recorded_replay(&self) -> Vec<u8>
+    {
+        self.state.borrow().scene.recorded_replay().encode()
+    }
+
+    /// Stops the current game and plays the replay encoded in `data` instead.
+    pub fn play_replay(&self, data: &[u8]) -> Result<(), JsValue>
+    {
+        let replay = Replay::decode(data)?;
+        self.state.borrow_mut().scene.play_replay(&replay);
+        Ok(())
+    }
+
+    /// Enables or disables mouse aim; the ship turns towards the cursor and the left mouse button
+    /// fires.
+    pub fn set_mouse_aim(&self, enabled: bool)
+    {
+        self.state.borrow_mut().scene.mouse_aim = enabled;
+    }
+
+    /// Pauses the game. The game is also paused automatically when the page is hidden or loses
+    /// focus.
+    pub fn pause(&self)
+    {
+        self.state.borrow_mut().pause();
+    }
+
+    pub fn resume(&self)
+    {
+        self.state.borrow_mut().scene.clock.resume();
+    }
+
+    pub fn is_paused(&self) -> bool
+    {
+        self.state.borrow().scene.clock.is_paused()
+    }
+
+    /// Advances a paused game a single frame.
+    pub fn step_frame(&self)
+    {
+        self.state.borrow_mut().scene.clock.step_frame();
+    }
+
+    pub fn time_scale(&self) -> f32
+    {
+        self.state.borrow().scene.clock.time_scale()
+    }
+
+    /// Sets how fast the game runs, where 1 is normal speed. The scale is clamped to the range
+    /// `[0, 4]`.
+    pub fn set_time_scale(&self, time_scale: f32)
+    {
+        self.state
+            .borrow_mut()
+            .scene
+            .clock
+            .set_time_scale(time_scale);
+    }
+
+    /// Makes the boost key toggle boost instead of boosting while held down.
+    pub fn set_toggle_boost(&self, enabled: bool) -> Result<(), JsValue>
+    {
+        self.update_accessibility(|settings| {
+            settings.set_toggle_boost(enabled);
+        })
+    }
+
+    /// Makes the ship fire whenever the gun is ready.
+    pub fn set_auto_fire(&self, enabled: bool) -> Result<(), JsValue>
+    {
+        self.update_accessibility(|settings| {
+            settings.set_auto_fire(enabled);
+        })
+    }
+
+    /// Sets the speed the game runs at. Unlike `set_time_scale` the speed is remembered.
+    pub fn set_game_speed(&self, game_speed: f32) -> Result<(), JsValue>
+    {
+        self.set_time_scale(game_speed);
+
+        let time_scale = self.time_scale();
+        self.update_accessibility(|settings| {
+            settings.set_game_speed(time_scale);
+        })
+    }
+
+    /// Enables or disables the post-processing passes. The passes whose amount is 0 stay disabled.
+    pub fn set_post_processing(&self, enabled: bool)
+    {
+        self.state
+            .borrow_mut()
+            .scene
+            .post_process
+            .set_enabled(enabled);
+    }
+
+    /// Sets the intensity of the glow around bright objects, 0 disables it.
+    pub fn set_bloom(&self, intensity: f32)
+    {
+        self.state
+            .borrow_mut()
+            .scene
+            .post_process
+            .set_bloom(intensity.max(0.));
+    }
+
+    /// Sets the brightness, from 0 to 1, above which objects glow.
+    pub fn set_bloom_threshold(&self, threshold: f32)
+    {
+        self.state
+            .borrow_mut()
+            .scene
+            .post_process
+            .set_bloom_threshold(threshold.clamp(0., 1.));
+    }
+
+    /// Sets how dark the gaps between scanlines are, from 0 to 1. 0 disables the scanlines.
+    pub fn set_scanlines(&self, intensity: f32)
+    {
+        self.state
+            .borrow_mut()
+            .scene
+            .post_process
+            .set_scanlines(intensity.clamp(0., 1.));
+    }
+
+    /// Sets how much the screen bulges, 0 gives a flat screen.
+    pub fn set_curvature(&self, curvature: f32)
+    {
+        self.state
+            .borrow_mut()
+            .scene
+            .post_process
+            .set_curvature(curvature.max(0.));
+    }
+
+    /// Sets how far apart the color channels are at the edges of the screen, 0 disables it.
+    pub fn set_chromatic_aberration(&self, amount: f32)
+    {
+        self.state
+            .borrow_mut()
+            .scene
+            .post_process
+            .set_chromatic_aberration(amount.max(0.));
+    }
+
+    /// Sets how dark the corners of the screen are, from 0 to 1. 0 disables the vignette.
+    pub fn set_vignette(&self, amount: f32)
+    {
+        self.state
+            .borrow_mut()
+            .scene
+            .post_process
+            .set_vignette(amount.clamp(0., 1.));
+    }
+
+    /// Shows hitboxes, velocities, contact points and frame statistics on top of the game.
+    pub fn set_debug_overlay(&self, enabled: bool)
+    {
+        self.state.borrow_mut().scene.debug_overlay_enabled = enabled;
+    }
+
+    /// Switches to the colors of the theme called `name`, either "classic", "rings",
+    /// "high-contrast" or "vector".
+    pub fn set_theme(&self, name: &str) -> Result<(), JsValue>
+    {
+        self.state
+            .borrow_mut()
+            .context
+            .set_theme(Theme::named(name)?);
+        Ok(())
+    }
+
+    /// Disables the animated background and camera effects.
+    pub fn set_reduced_motion(&self, enabled: bool) -> Result<(), JsValue>
+    {
+        self.update_accessibility(|settings| {
+            settings.set_reduced_motion(enabled);
+        })
+    }
+}
+
+impl Game
+{
+    /// Updates the accessibility settings and saves them.
+    fn update_accessibility<F: FnOnce(&mut AccessibilitySettings)>(
+        &self,
+        lambda: F,
+    ) -> Result<(), JsValue>
+    {
+        let mut state = self.state.borrow_mut();
+        lambda(&mut state.scene.accessibility);
+        state.scene.accessibility.save()
+    }
+}
+
+/// Everything the run loop of a `Game` operates on.
+struct GameState
+{
+    canvas: HtmlCanvasElement,
+    context: Context,
+
+    /// Set when the canvas has been resized and the drawing buffer should be resized to match.
+    resize_pending: bool,
+    device_pixel_ratio: f64,
+
+    /// Set while the WebGL context is lost, during which nothing is stepped or rendered.
+    context_lost: bool,
+
+    /// Set when the clock was paused because the context was lost, so that it is resumed when
+    /// the context is restored.
+    paused_by_context_loss: bool,
+
+    keyboard_event_bus: KeyboardEventBus,
+    mouse_event_bus: MouseEventBus,
+    scene: Scene,
+}
+
+impl GameState
+{
+    fn new(canvas: &HtmlCanvasElement, options: GameOptions) -> Result<GameState, JsValue>
+    {
+        // -----------------------------------------------------------------------------------------
+        // Create context.
+        // -----------------------------------------------------------------------------------------
+
+        let device_pixel_ratio = dom::window().device_pixel_ratio();
+        let (width, height) = drawing_buffer_size(canvas, device_pixel_ratio);
+        canvas.set_width(width);
+        canvas.set_height(height);
+
+        let context = Context::new(
+            ContextDescriptorBuilder::default()
+                .device(Rc::new(WebGlDevice::new(Backend::new(canvas)?)))
+                .canvas_width(width)
+                .canvas_height(height)
+                .foreground_projection_matrix(scene::foreground_projection_matrix())
+                .build()
+                .map_err(|error| format!("{}", error))?,
+        );
+
+        // -----------------------------------------------------------------------------------------
+        // Initialize scene.
+        // -----------------------------------------------------------------------------------------
+
+        let mut scene = Scene::new(&context, options.seed, AccessibilitySettings::load())?;
+        scene.mouse_aim = options.mouse_aim;
+
+        // -----------------------------------------------------------------------------------------
+        // Initialize input.
+        // -----------------------------------------------------------------------------------------
+
+        let keyboard_event_bus = KeyboardEventBus::new(canvas)?;
+        let mouse_event_bus = MouseEventBus::new(canvas)?;
+
+        Ok(GameState {
+            canvas: canvas.clone(),
+            context,
+            resize_pending: false,
+            device_pixel_ratio,
+            context_lost: false,
+            paused_by_context_loss: false,
+            keyboard_event_bus,
+            mouse_event_bus,
+            scene,
+        })
+    }
+
+    /// Resizes the drawing buffer, and everything depending on its size, to fit the canvas.
+    fn resize(&mut self) -> Result<(), JsValue>
+    {
+        self.resize_pending = false;
+        self.device_pixel_ratio = dom::window().device_pixel_ratio();
+
+        let (width, height) = drawing_buffer_size(&self.canvas, self.device_pixel_ratio);
+        if (width, height) == (*self.context.canvas_width(), *self.context.canvas_height()) {
+            return Ok(());
+        }
+
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+
+        self.context.set_canvas_width(width);
+        self.context.set_canvas_height(height);
+        self.context
+            .render_context()
+            .viewport(0, 0, width as i32, height as i32);
+
+        self.scene.resize(&self.context)?;
+        Ok(())
+    }
+
+    /// Pauses the simulation until it is resumed, even if the context is lost and restored in the
+    /// meantime.
+    fn pause(&mut self)
+    {
+        self.scene.clock.pause();
+        self.paused_by_context_loss = false;
+    }
+
+    /// Pauses the simulation until the context is restored.
+    fn lose_context(&mut self)
+    {
+        self.context_lost = true;
+
+        if !self.scene.clock.is_paused() {
+            self.scene.clock.pause();
+            self.paused_by_context_loss = true;
+        }
+    }
+
+    /// Recreates every GPU resource and resumes the simulation if it was paused by the context
+    /// loss, unless it was paused again since, like when the page was hidden.
+    fn restore_context(&mut self) -> Result<(), JsValue>
+    {
+        self.context.restore();
+        self.scene.restore(&self.context)?;
+        self.context_lost = false;
+
+        // The canvas might have been resized while the context was lost.
+        self.resize_pending = true;
+
+        if self.paused_by_context_loss {
+            self.scene.clock.resume();
+            self.paused_by_context_loss = false;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the background to where the canvas was clicked.
+    fn move_background(&mut self, canvas: &HtmlCanvasElement, event: &MouseEvent)
+    {
+        // The mouse is used to control the ship in mouse aim mode.
+        if self.scene.mouse_aim || *self.scene.accessibility.reduced_motion() {
+            return;
+        }
+
+        let background = &mut self.scene.renderers.background;
+
+        let width = canvas.client_width() as f32;
+        let mut offset = event.offset_x() as f32 - width / 2.;
+        offset /= width;
+        offset *= -2.;
+        background.position[0] = offset;
+
+        let height = canvas.client_height() as f32;
+        let mut offset = event.offset_y() as f32 - height / 2.;
+        offset /= height;
+        offset *= 2.;
+        background.position[1] = offset;
+    }
+
+    /// Steps and renders the scene with the input collected since last frame.
+    fn frame(&mut self)
+    {
+        if self.context_lost {
+            return;
+        }
+
+        // The device pixel ratio changes when zooming or moving the window to another screen,
+        // without the canvas being resized.
+        if self.resize_pending || self.device_pixel_ratio != dom::window().device_pixel_ratio() {
+            if let Err(error) = self.resize() {
+                log(&format!("failed to resize: {:?}", error));
+            }
+        }
+
+        let input = FrameInput {
+            time: dom::window()
+                .performance()
+                .map(|performance| performance.now()),
+            keys_pressed: self.keyboard_event_bus.take_keys_pressed().collect(),
+            keys_held_down: self.keyboard_event_bus.keys_held_down().collect(),
+            mouse_position: self.mouse_event_bus.position(),
+            left_button_down: self.mouse_event_bus.left_button_is_down(),
+        };
+        self.scene.frame(&mut self.context, &input);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Helper functions.
+// -------------------------------------------------------------------------------------------------
+
+/// The size in device pixels of the drawing buffer of `canvas`.
+fn drawing_buffer_size(canvas: &HtmlCanvasElement, device_pixel_ratio: f64) -> (u32, u32)
+{
+    let width = (canvas.client_width() as f64 * device_pixel_ratio).round();
+    let height = (canvas.client_height() as f64 * device_pixel_ratio).round();
+    (width.max(1.) as u32, height.max(1.) as u32)
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use super::{
+    device::GraphicsDevice,
+    image::Image,
+    texture::{
+        Texture2D,
+        TextureMagnificationFilter,
+        TextureMinificationFilter,
+    },
+};
+
+/// A named rectangle of a `SpriteAtlas`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasRegion
+{
+    /// Size in pixels.
+    pub width: u32,
+    pub height: u32,
+
+    /// Texture coordinates of the left and right sides.
+    pub s: [f32; 2],
+
+    /// Texture coordinates of the top and bottom sides.
+    pub t: [f32; 2],
+}
+
+impl AtlasRegion
+{
+    /// The texture coordinates of the top left and bottom right corners.
+    pub fn texcoords(&self) -> ([f32; 2], [f32; 2])
+    {
+        let ([left, right], [top, bottom]) = (self.s, self.t);
+        ([left, top], [right, bottom])
+    }
+}
+
+/// A texture holding several sprites, looked up by name.
+///
+/// The regions are described with one line per sprite of its name followed by the left, top,
+/// width and height of its rectangle in pixels of the image, like `ship 0 0 32 32`. Empty lines
+/// and lines starting with `#` are ignored.
+pub struct SpriteAtlas
+{
+    texture: Texture2D,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl SpriteAtlas
+{
+    /// An atlas of the sprites in `image` described by `regions`.
+    pub fn new(
+        gl: &dyn GraphicsDevice,
+        image: &Image,
+        regions: &str,
+        min_filter: TextureMinificationFilter,
+        mag_filter: TextureMagnificationFilter,
+    ) -> Result<SpriteAtlas, String>
+    {
+        let regions = parse_regions(regions, image.width(), image.height())?;
+        let texture = Texture2D::with_image(gl, image, min_filter, mag_filter)?;
+
+        Ok(SpriteAtlas { texture, regions })
+    }
+
+    /// An atlas of the sprites in the PNG image `bytes`, from `include_bytes!` or a fetch.
+    pub fn from_png(
+        gl: &dyn GraphicsDevice,
+        bytes: &[u8],
+        regions: &str,
+        min_filter: TextureMinificationFilter,
+        mag_filter: TextureMagnificationFilter,
+    ) -> Result<SpriteAtlas, String>
+    {
+        SpriteAtlas::new(
+            gl,
+            &Image::decode_png(bytes)?,
+            regions,
+            min_filter,
+            mag_filter,
+        )
+    }
+
+    pub fn texture(&self) -> &Texture2D
+    {
+        &self.texture
+    }
+
+    /// The region of the sprite `name`.
+    pub fn region(&self, name: &str) -> Result<AtlasRegion, String>
+    {
+        self.regions.get(name).copied().ok_or_else(|| {
+            let mut names: Vec<&str> = self.regions.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            format!(
+                "no sprite '{}' in atlas, sprites are: {}",
+                name,
+                names.join(", ")
+            )
+        })
+    }
+}
+
+fn parse_regions(
+    source: &str,
+    image_width: u32,
+    image_height: u32,
+) -> Result<HashMap<String, AtlasRegion>, String>
+{
+    let mut regions = HashMap::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: &str| format!("line {} of atlas regions: {}", i + 1, message);
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (name, rectangle) = match words.as_slice() {
+            [name, rectangle @ ..] if rectangle.len() == 4 => (*name, rectangle),
+            _ => return Err(error("expected a name, left, top, width and height")),
+        };
+        let rectangle: Vec<u32> = rectangle
+            .iter()
+            .map(|word| word.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|parse_error| error(&format!("{}", parse_error)))?;
+        let (left, top, width, height) = (rectangle[0], rectangle[1], rectangle[2], rectangle[3]);
+
+        let fits = |start: u32, size: u32, end: u32| {
+            start.checked_add(size).is_some_and(|last| last <= end)
+        };
+        if !fits(left, width, image_width) || !fits(top, height, image_height) {
+            return Err(error(&format!(
+                "'{}' is outside of the {}x{} image",
+                name, image_width, image_height
+            )));
+        }
+
+        let region = AtlasRegion {
+            width,
+            height,
+            s: [
+                left as f32 / image_width as f32,
+                (left + width) as f32 / image_width as f32,
+            ],
+            t: [
+                top as f32 / image_height as f32,
+                (top + height) as f32 / image_height as f32,
+            ],
+        };
+        if regions.insert(name.to_string(), region).is_some() {
+            return Err(error(&format!("'{}' is defined twice", name)));
+        }
+    }
+
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn regions_are_parsed_into_texture_coordinates()
+    {
+        let regions = parse_regions(
+            "# Sprites.\n\nship 0 0 32 16\n  rock 32 16 32 48  \n",
+            64,
+            64,
+        )
+        .unwrap();
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(
+            regions["ship"],
+            AtlasRegion {
+                width: 32,
+                height: 16,
+                s: [0., 0.5],
+                t: [0., 0.25],
+            }
+        );
+        assert_eq!(regions["rock"].s, [0.5, 1.]);
+        assert_eq!(regions["rock"].t, [0.25, 1.]);
+    }
+
+    #[test]
+    fn errors_name_the_line()
+    {
+        let error = |source: &str| parse_regions(source, 64, 64).unwrap_err();
+
+        assert!(error("ship 0 0 32").starts_with("line 1 "));
+        assert!(error("ship 0 0 32 x").starts_with("line 1 "));
+        assert!(error("# Sprites.\nship 0 0 65 1").starts_with("line 2 "));
+        assert!(error("ship 0 0 1 1\nship 1 1 1 1").starts_with("line 2 "));
+        assert!(error("\nship 4294967295 0 1 1").starts_with("line 2 "));
+        assert!(error("ship 0 4294967295 0 1").starts_with("line 1 "));
+    }
+}
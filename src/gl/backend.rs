@@ -0,0 +1,213 @@
+use wasm_bindgen::{
+    JsCast,
+    JsValue,
+};
+use web_sys::{
+    AngleInstancedArrays,
+    HtmlCanvasElement,
+    OesVertexArrayObject,
+    WebGl2RenderingContext,
+    WebGlRenderingContext,
+    WebGlVertexArrayObject,
+};
+
+use super::device::Capabilities;
+
+type GL = WebGlRenderingContext;
+
+/// `HALF_FLOAT_OES` from `OES_texture_half_float`, which differs from `HALF_FLOAT` of WebGL2.
+const HALF_FLOAT_OES: u32 = 0x8D61;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Version
+{
+    WebGl1,
+    WebGl2,
+}
+
+/// The WebGL1 extensions standing in for WebGL2 features.
+#[derive(Clone, Default)]
+struct Extensions
+{
+    instanced_arrays: Option<AngleInstancedArrays>,
+    vertex_array_object: Option<OesVertexArrayObject>,
+}
+
+/// A WebGL2 context, or a WebGL1 context where WebGL2 isn't supported.
+///
+/// The renderers are written against the WebGL1 API returned by `gl`, which a WebGL2 context
+/// implements too, and use the methods of the backend for the features whose API differs between
+/// the two versions. Each of those methods uses the native WebGL2 function or the equivalent
+/// WebGL1 extension, and `capabilities` tells which are available.
+#[derive(Clone)]
+pub struct Backend
+{
+    gl: GL,
+    webgl2: Option<WebGl2RenderingContext>,
+    extensions: Extensions,
+    capabilities: Capabilities,
+}
+
+impl Backend
+{
+    /// Creates a WebGL2 context for `canvas`, falling back to WebGL1.
+    pub fn new(canvas: &HtmlCanvasElement) -> Result<Backend, JsValue>
+    {
+        let mut backend = match canvas.get_context("webgl2")? {
+            Some(context) => {
+                let webgl2 = context.dyn_into::<WebGl2RenderingContext>()?;
+                Backend {
+                    gl: webgl2.clone().unchecked_into::<GL>(),
+                    webgl2: Some(webgl2),
+                    extensions: Extensions::default(),
+                    capabilities: Capabilities::none(),
+                }
+            }
+            None => Backend {
+                gl: canvas
+                    .get_context("webgl")?
+                    .ok_or("WebGL is not supported")?
+                    .dyn_into::<GL>()?,
+                webgl2: None,
+                extensions: Extensions::default(),
+                capabilities: Capabilities::none(),
+            },
+        };
+
+        backend.enable_extensions();
+        Ok(backend)
+    }
+
+    /// Enables the extensions again, which is needed after the context has been restored.
+    pub fn restore(&mut self)
+    {
+        self.enable_extensions();
+    }
+
+    /// The WebGL1 API, implemented by the context of either version.
+    pub fn gl(&self) -> &GL
+    {
+        &self.gl
+    }
+
+    pub fn version(&self) -> Version
+    {
+        match self.webgl2 {
+            Some(_) => Version::WebGl2,
+            None => Version::WebGl1,
+        }
+    }
+
+    pub fn capabilities(&self) -> &Capabilities
+    {
+        &self.capabilities
+    }
+
+    //
+    // Vertex arrays.
+    //
+
+    pub fn create_vertex_array(&self) -> Option<WebGlVertexArrayObject>
+    {
+        match (&self.webgl2, &self.extensions.vertex_array_object) {
+            (Some(gl), _) => gl.create_vertex_array(),
+            (None, Some(extension)) => extension.create_vertex_array_oes(),
+            (None, None) => None,
+        }
+    }
+
+    pub fn bind_vertex_array(&self, vertex_array: Option<&WebGlVertexArrayObject>)
+    {
+        match (&self.webgl2, &self.extensions.vertex_array_object) {
+            (Some(gl), _) => gl.bind_vertex_array(vertex_array),
+            (None, Some(extension)) => extension.bind_vertex_array_oes(vertex_array),
+            (None, None) => {}
+        }
+    }
+
+    //
+    // Instancing.
+    //
+
+    /// Sets the divisor of the vertex attribute at `location`. Does nothing without instancing.
+    pub fn vertex_attrib_divisor(&self, location: u32, divisor: u32)
+    {
+        match (&self.webgl2, &self.extensions.instanced_arrays) {
+            (Some(gl), _) => gl.vertex_attrib_divisor(location, divisor),
+            (None, Some(extension)) => extension.vertex_attrib_divisor_angle(location, divisor),
+            (None, None) => {}
+        }
+    }
+
+    /// Draws `instances` instances of `count` vertices. Does nothing without instancing.
+    pub fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32)
+    {
+        match (&self.webgl2, &self.extensions.instanced_arrays) {
+            (Some(gl), _) => gl.draw_arrays_instanced(mode, first, count, instances),
+            (None, Some(extension)) => {
+                extension.draw_arrays_instanced_angle(mode, first, count, instances)
+            }
+            (None, None) => {}
+        }
+    }
+
+    //
+    // Float textures.
+    //
+
+    /// The internal format, format and type of half float RGBA textures, if they can be rendered
+    /// into.
+    pub fn float_texture_format(&self) -> Option<(i32, u32, u32)>
+    {
+        if !self.capabilities.float_render_targets {
+            return None;
+        }
+
+        Some(match self.version() {
+            Version::WebGl2 => (
+                WebGl2RenderingContext::RGBA16F as i32,
+                GL::RGBA,
+                WebGl2RenderingContext::HALF_FLOAT,
+            ),
+            Version::WebGl1 => (GL::RGBA as i32, GL::RGBA, HALF_FLOAT_OES),
+        })
+    }
+
+    fn enable_extensions(&mut self)
+    {
+        let gl = self.gl.clone();
+        let extension = |name: &str| gl.get_extension(name).ok().flatten();
+
+        self.capabilities = match self.webgl2 {
+            Some(_) => {
+                self.extensions = Extensions::default();
+
+                Capabilities {
+                    vertex_arrays: true,
+                    instancing: true,
+                    float_render_targets: extension("EXT_color_buffer_float").is_some(),
+                    npot_mipmaps: true,
+                }
+            }
+            None => {
+                self.extensions = Extensions {
+                    instanced_arrays: extension("ANGLE_instanced_arrays")
+                        .map(JsCast::unchecked_into),
+                    vertex_array_object: extension("OES_vertex_array_object")
+                        .map(JsCast::unchecked_into),
+                };
+
+                let float_render_targets = extension("OES_texture_half_float").is_some()
+                    && extension("OES_texture_half_float_linear").is_some()
+                    && extension("EXT_color_buffer_half_float").is_some();
+
+                Capabilities {
+                    vertex_arrays: self.extensions.vertex_array_object.is_some(),
+                    instancing: self.extensions.instanced_arrays.is_some(),
+                    float_render_targets,
+                    npot_mipmaps: false,
+                }
+            }
+        };
+    }
+}
@@ -1,10 +1,18 @@
+use std::{
+    marker::PhantomData,
+    mem,
+    slice,
+};
+
 use getset::Getters;
-use js_sys::Uint16Array;
-use web_sys::{
-    WebGlBuffer,
-    WebGlRenderingContext,
+use web_sys::WebGlRenderingContext;
+
+use super::device::{
+    BufferId,
+    GraphicsDevice,
 };
 
+#[derive(Clone, Copy)]
 #[allow(dead_code)]
 pub enum BufferUsage
 {
@@ -13,27 +21,31 @@ pub enum BufferUsage
     StaticDraw = WebGlRenderingContext::STATIC_DRAW as isize,
 }
 
-pub trait ElementArrayBufferType: Sized
-{
-    type View: Into<js_sys::Object>;
-
-    unsafe fn view(data: &Vec<Self>) -> Self::View;
-}
-
-impl ElementArrayBufferType for u16
+/// A type of plain numbers whose values are uploaded to buffers as their bytes in memory.
+///
+/// # Safety
+///
+/// The type must have no padding and every bit pattern of its size must be a valid value.
+pub unsafe trait BufferElement: Copy
 {
-    type View = Uint16Array;
-
-    unsafe fn view(data: &Vec<Self>) -> Self::View
+    fn as_bytes(data: &[Self]) -> &[u8]
     {
-        Self::View::view(data)
+        unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data)) }
     }
 }
 
+unsafe impl BufferElement for u8 {}
+unsafe impl BufferElement for u16 {}
+unsafe impl BufferElement for f32 {}
+
+pub trait ElementArrayBufferType: BufferElement {}
+
+impl ElementArrayBufferType for u16 {}
+
 #[derive(Debug, Getters)]
 pub struct ElementArrayBuffer
 {
-    buffer: WebGlBuffer,
+    buffer: BufferId,
 
     #[getset(get = "pub")]
     len: Option<usize>,
@@ -41,44 +53,225 @@ pub struct ElementArrayBuffer
 
 impl ElementArrayBuffer
 {
-    pub fn new(gl: &WebGlRenderingContext) -> Result<ElementArrayBuffer, String>
+    pub fn new(gl: &dyn GraphicsDevice) -> Result<ElementArrayBuffer, String>
     {
         gl.create_buffer()
-            .ok_or(String::from("failed to create ElementArrayBuffer"))
+            .map_err(|_| String::from("failed to create ElementArrayBuffer"))
             .map(|buffer| ElementArrayBuffer { buffer, len: None })
     }
 
-    pub fn bind(&self, gl: &WebGlRenderingContext)
+    pub fn bind(&self, gl: &dyn GraphicsDevice)
     {
         gl.bind_buffer(
             WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
-            Some(&self.buffer),
+            Some(self.buffer),
         );
     }
 
-    pub fn unbind(&self, gl: &WebGlRenderingContext)
+    pub fn unbind(&self, gl: &dyn GraphicsDevice)
     {
         gl.bind_buffer(WebGlRenderingContext::ELEMENT_ARRAY_BUFFER, None);
     }
 
     pub fn set_data<T: ElementArrayBufferType>(
         &mut self,
-        gl: &WebGlRenderingContext,
+        gl: &dyn GraphicsDevice,
         usage: BufferUsage,
         data: &Vec<T>,
     )
     {
         self.bind(gl);
 
-        unsafe {
-            gl.buffer_data_with_array_buffer_view(
-                WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
-                &T::view(&data).into(),
-                usage as u32,
-            )
-        }
+        gl.buffer_data(
+            WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+            T::as_bytes(data),
+            usage as u32,
+        );
         self.len = Some(data.len());
 
         self.unbind(gl);
     }
 }
+
+pub trait ArrayBufferType: BufferElement {}
+
+impl ArrayBufferType for f32 {}
+
+impl ArrayBufferType for u8 {}
+
+/// A buffer of vertex data of type `T`.
+#[derive(Debug, Getters)]
+pub struct ArrayBuffer<T>
+{
+    #[getset(get = "pub")]
+    buffer: BufferId,
+
+    /// Number of elements of type `T` the buffer has storage for.
+    capacity: usize,
+
+    _type: PhantomData<T>,
+}
+
+impl<T: ArrayBufferType> ArrayBuffer<T>
+{
+    pub fn new(gl: &dyn GraphicsDevice) -> Result<ArrayBuffer<T>, String>
+    {
+        gl.create_buffer()
+            .map_err(|_| String::from("failed to create ArrayBuffer"))
+            .map(|buffer| ArrayBuffer {
+                buffer,
+                capacity: 0,
+                _type: PhantomData,
+            })
+    }
+
+    /// Creates a buffer initialized with `data`.
+    pub fn with_data(
+        gl: &dyn GraphicsDevice,
+        usage: BufferUsage,
+        data: &[T],
+    ) -> Result<ArrayBuffer<T>, String>
+    {
+        let mut buffer = ArrayBuffer::new(gl)?;
+        buffer.set_data(gl, usage, data);
+        Ok(buffer)
+    }
+
+    pub fn bind(&self, gl: &dyn GraphicsDevice)
+    {
+        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(self.buffer));
+    }
+
+    pub fn unbind(&self, gl: &dyn GraphicsDevice)
+    {
+        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, None);
+    }
+
+    /// Replaces the storage of the buffer with `data`.
+    pub fn set_data(&mut self, gl: &dyn GraphicsDevice, usage: BufferUsage, data: &[T])
+    {
+        self.bind(gl);
+
+        gl.buffer_data(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            T::as_bytes(data),
+            usage as u32,
+        );
+        self.capacity = data.len();
+
+        self.unbind(gl);
+    }
+
+    /// Overwrites the buffer with `data`, keeping the storage of the buffer if it is large enough
+    /// and reallocating it with `usage` otherwise.
+    pub fn update(&mut self, gl: &dyn GraphicsDevice, usage: BufferUsage, data: &[T])
+    {
+        if data.len() > self.capacity {
+            return self.set_data(gl, usage, data);
+        }
+
+        self.bind(gl);
+
+        gl.buffer_sub_data(WebGlRenderingContext::ARRAY_BUFFER, 0, T::as_bytes(data));
+
+        self.unbind(gl);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[allow(dead_code)]
+pub enum VertexAttributeType
+{
+    Float,
+
+    /// An unsigned byte normalized to the range 0 to 1.
+    UnsignedByte,
+}
+
+impl VertexAttributeType
+{
+    pub fn value(&self) -> u32
+    {
+        use VertexAttributeType::*;
+
+        match self {
+            Float => WebGlRenderingContext::FLOAT,
+            UnsignedByte => WebGlRenderingContext::UNSIGNED_BYTE,
+        }
+    }
+
+    fn size(&self) -> i32
+    {
+        use VertexAttributeType::*;
+
+        match self {
+            Float => 4,
+            UnsignedByte => 1,
+        }
+    }
+
+    pub fn normalized(&self) -> bool
+    {
+        matches!(self, VertexAttributeType::UnsignedByte)
+    }
+}
+
+/// An attribute of the vertices in an `ArrayBuffer`.
+#[derive(Clone, Debug, Getters)]
+pub struct VertexAttribute
+{
+    /// Name of the attribute in the shader.
+    #[getset(get = "pub")]
+    name: &'static str,
+
+    /// Number of components, between 1 and 4.
+    #[getset(get = "pub")]
+    components: i32,
+
+    #[getset(get = "pub")]
+    type_: VertexAttributeType,
+
+    /// Offset in bytes from the start of a vertex.
+    #[getset(get = "pub")]
+    offset: i32,
+}
+
+/// How the attributes of a vertex are laid out in an `ArrayBuffer`.
+///
+/// Attributes are interleaved in the order they are added, each one right after the previous one.
+#[derive(Clone, Debug, Default, Getters)]
+pub struct VertexLayout
+{
+    #[getset(get = "pub")]
+    attributes: Vec<VertexAttribute>,
+
+    /// Size in bytes of a vertex.
+    #[getset(get = "pub")]
+    stride: i32,
+}
+
+impl VertexLayout
+{
+    pub fn new() -> VertexLayout
+    {
+        VertexLayout::default()
+    }
+
+    /// Adds an attribute after the attributes added so far.
+    pub fn attribute(
+        mut self,
+        name: &'static str,
+        components: i32,
+        type_: VertexAttributeType,
+    ) -> VertexLayout
+    {
+        self.attributes.push(VertexAttribute {
+            name,
+            components,
+            type_,
+            offset: self.stride,
+        });
+        self.stride += components * type_.size();
+        self
+    }
+}
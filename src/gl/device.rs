@@ -0,0 +1,241 @@
+/// A compiled shader.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ShaderId(pub u32);
+
+/// A linked program.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ProgramId(pub u32);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BufferId(pub u32);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TextureId(pub u32);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FramebufferId(pub u32);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RenderbufferId(pub u32);
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct VertexArrayId(pub u32);
+
+/// The location of a uniform of a program.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct UniformLocationId(pub u32);
+
+/// The features a device has on top of WebGL1.
+#[derive(Clone, Copy, Debug)]
+pub struct Capabilities
+{
+    pub vertex_arrays: bool,
+    pub instancing: bool,
+
+    /// Half float textures can be rendered into and sampled with linear filtering.
+    pub float_render_targets: bool,
+
+    /// Textures whose sides aren't powers of two can have mipmaps.
+    pub npot_mipmaps: bool,
+}
+
+impl Capabilities
+{
+    /// The capabilities of plain WebGL1.
+    pub fn none() -> Capabilities
+    {
+        Capabilities {
+            vertex_arrays: false,
+            instancing: false,
+            float_render_targets: false,
+            npot_mipmaps: false,
+        }
+    }
+}
+
+/// A uniform that is used by a linked program.
+#[derive(Clone, Debug)]
+pub struct ActiveUniform
+{
+    /// The name of the uniform, with the `[0]` suffix of arrays removed.
+    pub name: String,
+
+    /// The type as reported by `getActiveUniform`.
+    pub type_: u32,
+
+    pub location: UniformLocationId,
+}
+
+/// The parameters of `texImage2D`, apart from the pixels.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TexImage2D
+{
+    pub target: u32,
+    pub level: i32,
+    pub internal_format: i32,
+    pub width: i32,
+    pub height: i32,
+    pub border: i32,
+    pub format: u32,
+    pub type_: u32,
+}
+
+/// The graphics operations the renderers are written against.
+///
+/// Objects created by a device are referred to by opaque handles, whose values are only
+/// meaningful to the device that created them. Enumerations like primitive modes, buffer targets
+/// and texture formats are passed as their WebGL values.
+pub trait GraphicsDevice
+{
+    /// A short name of the device shown in debug output.
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// The internal format, format and type of half float RGBA textures, if they can be rendered
+    /// into.
+    fn float_texture_format(&self) -> Option<(i32, u32, u32)>;
+
+    /// Prepares the device for use again after its context has been lost and restored. The handles
+    /// of the objects created before are invalid afterwards.
+    fn restore(&self) {}
+
+    //
+    // Shaders and programs.
+    //
+
+    fn compile_shader(&self, type_: u32, source: &str) -> Result<ShaderId, String>;
+
+    /// Links a program with each attribute in `attributes` bound to its index as location.
+    fn link_program(
+        &self,
+        vertex_shader: ShaderId,
+        fragment_shader: ShaderId,
+        attributes: &[&str],
+    ) -> Result<ProgramId, String>;
+
+    /// The location of the attribute `name`, or -1 if it isn't active.
+    fn attribute_location(&self, program: ProgramId, name: &str) -> i32;
+
+    fn active_uniforms(&self, program: ProgramId) -> Vec<ActiveUniform>;
+
+    fn use_program(&self, program: Option<ProgramId>);
+
+    fn uniform1f(&self, location: UniformLocationId, value: f32);
+    fn uniform1i(&self, location: UniformLocationId, value: i32);
+    fn uniform2fv(&self, location: UniformLocationId, value: &[f32]);
+    fn uniform4fv(&self, location: UniformLocationId, value: &[f32]);
+    fn uniform_matrix4fv(&self, location: UniformLocationId, value: &[f32]);
+
+    //
+    // Buffers.
+    //
+
+    fn create_buffer(&self) -> Result<BufferId, String>;
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferId>);
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32);
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8]);
+
+    //
+    // Vertex arrays.
+    //
+
+    /// Fails without the vertex arrays capability.
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String>;
+
+    /// Binds `vertex_array`, or the default vertex array if `None`.
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayId>);
+
+    //
+    // Vertex attributes.
+    //
+
+    fn enable_vertex_attrib_array(&self, location: u32);
+    fn disable_vertex_attrib_array(&self, location: u32);
+
+    /// Points the attribute at `location` into the bound array buffer, `stride` and `offset` are
+    /// in bytes.
+    fn vertex_attrib_pointer(
+        &self,
+        location: u32,
+        components: i32,
+        type_: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    );
+
+    /// Sets the constant value of the disabled attribute at `location`, with 1 to 4 components.
+    fn vertex_attrib(&self, location: u32, value: &[f32]);
+
+    /// Does nothing without the instancing capability.
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32);
+
+    //
+    // Textures.
+    //
+
+    fn create_texture(&self) -> Result<TextureId, String>;
+    fn active_texture(&self, unit: u32);
+    fn bind_texture(&self, target: u32, texture: Option<TextureId>);
+
+    /// Allocates the storage of the bound texture, initialized with `pixels` if given.
+    fn tex_image_2d(&self, image: &TexImage2D, pixels: Option<&[u8]>) -> Result<(), String>;
+
+    fn tex_parameteri(&self, target: u32, name: u32, value: i32);
+
+    /// Generates the mipmaps of the bound texture from its base level.
+    fn generate_mipmap(&self, target: u32);
+
+    //
+    // Framebuffers.
+    //
+
+    fn create_framebuffer(&self) -> Result<FramebufferId, String>;
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferId>);
+    fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<TextureId>,
+        level: i32,
+    );
+
+    /// The completeness status of the framebuffer bound to `target`.
+    fn check_framebuffer_status(&self, target: u32) -> u32;
+
+    fn create_renderbuffer(&self) -> Result<RenderbufferId, String>;
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<RenderbufferId>);
+
+    /// Allocates the storage of the bound renderbuffer.
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32);
+
+    fn framebuffer_renderbuffer(
+        &self,
+        target: u32,
+        attachment: u32,
+        renderbuffer_target: u32,
+        renderbuffer: Option<RenderbufferId>,
+    );
+
+    //
+    // State and drawing.
+    //
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32);
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32);
+    fn clear(&self, mask: u32);
+    fn enable(&self, capability: u32);
+    fn disable(&self, capability: u32);
+    fn blend_func(&self, source: u32, destination: u32);
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32);
+
+    /// Draws `count` indices of `type_` from the bound element array buffer, starting `offset`
+    /// bytes into it.
+    fn draw_elements(&self, mode: u32, count: i32, type_: u32, offset: i32);
+
+    /// Does nothing without the instancing capability.
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32);
+}
@@ -0,0 +1,205 @@
+use getset::Getters;
+use web_sys::WebGlRenderingContext;
+
+use super::{
+    device::{
+        FramebufferId,
+        GraphicsDevice,
+        RenderbufferId,
+    },
+    texture::{
+        Texture2D,
+        TextureMagnificationFilter,
+        TextureMinificationFilter,
+        TextureWrappingFunction,
+    },
+};
+
+type GL = WebGlRenderingContext;
+
+/// The internal format, format and type of a texture.
+pub type TextureFormat = (i32, u32, u32);
+
+pub const RGBA8: TextureFormat = (GL::RGBA as i32, GL::RGBA, GL::UNSIGNED_BYTE);
+
+/// The buffers a `Framebuffer` has besides its color attachment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[allow(dead_code)]
+pub enum DepthStencil
+{
+    None,
+    Depth,
+    DepthAndStencil,
+}
+
+impl DepthStencil
+{
+    /// The attachment and the internal format of the renderbuffer, if there is one.
+    fn renderbuffer(&self) -> Option<(u32, u32)>
+    {
+        match self {
+            DepthStencil::None => None,
+            DepthStencil::Depth => Some((GL::DEPTH_ATTACHMENT, GL::DEPTH_COMPONENT16)),
+            DepthStencil::DepthAndStencil => {
+                Some((GL::DEPTH_STENCIL_ATTACHMENT, GL::DEPTH_STENCIL))
+            }
+        }
+    }
+}
+
+#[derive(Builder)]
+pub struct FramebufferDescriptor
+{
+    width: i32,
+    height: i32,
+
+    /// The format of the color attachment.
+    #[builder(default = "RGBA8")]
+    format: TextureFormat,
+
+    #[builder(default = "DepthStencil::None")]
+    depth_stencil: DepthStencil,
+}
+
+/// A framebuffer rendering into a texture it owns, which is sampled with linear filtering and
+/// clamped to its edges. Depth and stencil buffers, if any, are renderbuffers of the same size.
+#[derive(Debug, Getters)]
+pub struct Framebuffer
+{
+    #[getset(get = "pub")]
+    framebuffer: FramebufferId,
+
+    /// The color attachment.
+    #[getset(get = "pub")]
+    texture: Texture2D,
+
+    renderbuffer: Option<RenderbufferId>,
+    format: TextureFormat,
+    depth_stencil: DepthStencil,
+
+    #[getset(get = "pub")]
+    width: i32,
+
+    #[getset(get = "pub")]
+    height: i32,
+}
+
+impl Framebuffer
+{
+    pub fn new(
+        gl: &dyn GraphicsDevice,
+        descriptor: FramebufferDescriptor,
+    ) -> Result<Framebuffer, String>
+    {
+        let renderbuffer = match descriptor.depth_stencil.renderbuffer() {
+            Some(_) => Some(gl.create_renderbuffer()?),
+            None => None,
+        };
+
+        let mut framebuffer = Framebuffer {
+            framebuffer: gl.create_framebuffer()?,
+            texture: Texture2D::new(gl)?,
+            renderbuffer,
+            format: descriptor.format,
+            depth_stencil: descriptor.depth_stencil,
+            width: descriptor.width,
+            height: descriptor.height,
+        };
+        framebuffer.allocate(gl)?;
+
+        //
+        // The attachments stay valid when their storage is reallocated.
+        //
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(framebuffer.framebuffer));
+        gl.framebuffer_texture_2d(
+            GL::FRAMEBUFFER,
+            GL::COLOR_ATTACHMENT0,
+            GL::TEXTURE_2D,
+            Some(*framebuffer.texture.texture()),
+            0,
+        );
+        if let Some((attachment, _)) = framebuffer.depth_stencil.renderbuffer() {
+            gl.framebuffer_renderbuffer(
+                GL::FRAMEBUFFER,
+                attachment,
+                GL::RENDERBUFFER,
+                framebuffer.renderbuffer,
+            );
+        }
+
+        framebuffer.check_status(gl)?;
+        Ok(framebuffer)
+    }
+
+    /// Reallocates the attachments with a size of `width` by `height`, discarding their content.
+    pub fn resize(&mut self, gl: &dyn GraphicsDevice, width: i32, height: i32)
+        -> Result<(), String>
+    {
+        self.width = width;
+        self.height = height;
+        self.allocate(gl)?;
+
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(self.framebuffer));
+        self.check_status(gl)
+    }
+
+    /// Allocates the storage of the attachments.
+    fn allocate(&mut self, gl: &dyn GraphicsDevice) -> Result<(), String>
+    {
+        let (internal_format, format, type_) = self.format;
+        let (width, height) = (self.width, self.height);
+
+        self.texture
+            .specification()
+            .level(0)
+            .internal_format(internal_format)
+            .width(width)
+            .height(height)
+            .border(0)
+            .format(format)
+            .type_(type_)
+            .min_filter(TextureMinificationFilter::Linear)
+            .mag_filter(TextureMagnificationFilter::Linear)
+            .wrap_s(TextureWrappingFunction::ClampToEdge)
+            .wrap_t(TextureWrappingFunction::ClampToEdge)
+            .update(gl)?;
+
+        if let Some((_, internal_format)) = self.depth_stencil.renderbuffer() {
+            gl.bind_renderbuffer(GL::RENDERBUFFER, self.renderbuffer);
+            gl.renderbuffer_storage(GL::RENDERBUFFER, internal_format, width, height);
+            gl.bind_renderbuffer(GL::RENDERBUFFER, None);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the bound framebuffer, which is this one, is complete and unbinds it.
+    fn check_status(&self, gl: &dyn GraphicsDevice) -> Result<(), String>
+    {
+        let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        if status == GL::FRAMEBUFFER_COMPLETE {
+            return Ok(());
+        }
+
+        let reason = match status {
+            GL::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "an attachment can't be rendered into",
+            GL::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "it has no attachment",
+            GL::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => "its attachments have different sizes",
+            GL::FRAMEBUFFER_UNSUPPORTED => "the combination of formats is unsupported",
+            _ => "of an unknown reason",
+        };
+        Err(format!(
+            "{}x{} framebuffer with format {:?} and {:?} on {} is incomplete because {} \
+             (status 0x{:x})",
+            self.width,
+            self.height,
+            self.format,
+            self.depth_stencil,
+            gl.name(),
+            reason,
+            status
+        ))
+    }
+}
@@ -0,0 +1,538 @@
+use std::num::NonZeroU32;
+
+use glow::{
+    Context,
+    HasContext,
+    NativeBuffer,
+    NativeFramebuffer,
+    NativeProgram,
+    NativeRenderbuffer,
+    NativeShader,
+    NativeTexture,
+    NativeUniformLocation,
+    NativeVertexArray,
+    PixelUnpackData,
+};
+
+use super::device::{
+    ActiveUniform,
+    BufferId,
+    Capabilities,
+    FramebufferId,
+    GraphicsDevice,
+    ProgramId,
+    RenderbufferId,
+    ShaderId,
+    TexImage2D,
+    TextureId,
+    UniformLocationId,
+    VertexArrayId,
+};
+
+/// The `GraphicsDevice` of an OpenGL ES 3.0 context, used by the native front end.
+///
+/// The shaders of the renderers are written in GLSL ES 1.00 for WebGL1, which OpenGL ES 3.0
+/// compiles as is. The handles are the names of the OpenGL objects.
+pub struct GlesDevice
+{
+    gl: Context,
+    capabilities: Capabilities,
+}
+
+impl GlesDevice
+{
+    pub fn new(gl: Context) -> GlesDevice
+    {
+        let extensions = gl.supported_extensions();
+        let float_render_targets = extensions.contains("GL_EXT_color_buffer_half_float")
+            || extensions.contains("GL_EXT_color_buffer_float");
+
+        let capabilities = Capabilities {
+            vertex_arrays: true,
+            instancing: true,
+            float_render_targets,
+            npot_mipmaps: true,
+        };
+
+        GlesDevice { gl, capabilities }
+    }
+}
+
+fn handle(id: u32) -> NonZeroU32
+{
+    NonZeroU32::new(id).expect("OpenGL object names are never 0")
+}
+
+impl GraphicsDevice for GlesDevice
+{
+    fn name(&self) -> &'static str
+    {
+        "GLES3"
+    }
+
+    fn capabilities(&self) -> Capabilities
+    {
+        self.capabilities
+    }
+
+    fn float_texture_format(&self) -> Option<(i32, u32, u32)>
+    {
+        match self.capabilities.float_render_targets {
+            true => Some((glow::RGBA16F as i32, glow::RGBA, glow::HALF_FLOAT)),
+            false => None,
+        }
+    }
+
+    //
+    // Shaders and programs.
+    //
+
+    fn compile_shader(&self, type_: u32, source: &str) -> Result<ShaderId, String>
+    {
+        unsafe {
+            let shader = self.gl.create_shader(type_)?;
+            self.gl.shader_source(shader, source);
+            self.gl.compile_shader(shader);
+
+            if self.gl.get_shader_compile_status(shader) {
+                Ok(ShaderId(shader.0.get()))
+            } else {
+                Err(self.gl.get_shader_info_log(shader))
+            }
+        }
+    }
+
+    fn link_program(
+        &self,
+        vertex_shader: ShaderId,
+        fragment_shader: ShaderId,
+        attributes: &[&str],
+    ) -> Result<ProgramId, String>
+    {
+        unsafe {
+            let program = self.gl.create_program()?;
+            self.gl
+                .attach_shader(program, NativeShader(handle(vertex_shader.0)));
+            self.gl
+                .attach_shader(program, NativeShader(handle(fragment_shader.0)));
+
+            for (location, attribute) in attributes.iter().enumerate() {
+                self.gl
+                    .bind_attrib_location(program, location as u32, attribute);
+            }
+
+            self.gl.link_program(program);
+
+            if self.gl.get_program_link_status(program) {
+                Ok(ProgramId(program.0.get()))
+            } else {
+                Err(self.gl.get_program_info_log(program))
+            }
+        }
+    }
+
+    fn attribute_location(&self, program: ProgramId, name: &str) -> i32
+    {
+        unsafe {
+            self.gl
+                .get_attrib_location(NativeProgram(handle(program.0)), name)
+                .map_or(-1, |location| location as i32)
+        }
+    }
+
+    fn active_uniforms(&self, program: ProgramId) -> Vec<ActiveUniform>
+    {
+        let program = NativeProgram(handle(program.0));
+
+        unsafe {
+            (0..self.gl.get_active_uniforms(program))
+                .filter_map(|i| self.gl.get_active_uniform(program, i))
+                .filter_map(|info| {
+                    // Arrays are reported with the name of their first element.
+                    let name = info.name.trim_end_matches("[0]").to_string();
+
+                    self.gl
+                        .get_uniform_location(program, &name)
+                        .map(|location| ActiveUniform {
+                            name,
+                            type_: info.utype,
+                            location: UniformLocationId(location.0),
+                        })
+                })
+                .collect()
+        }
+    }
+
+    fn use_program(&self, program: Option<ProgramId>)
+    {
+        unsafe {
+            self.gl
+                .use_program(program.map(|program| NativeProgram(handle(program.0))));
+        }
+    }
+
+    fn uniform1f(&self, location: UniformLocationId, value: f32)
+    {
+        unsafe {
+            self.gl
+                .uniform_1_f32(Some(&NativeUniformLocation(location.0)), value);
+        }
+    }
+
+    fn uniform1i(&self, location: UniformLocationId, value: i32)
+    {
+        unsafe {
+            self.gl
+                .uniform_1_i32(Some(&NativeUniformLocation(location.0)), value);
+        }
+    }
+
+    fn uniform2fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        unsafe {
+            self.gl
+                .uniform_2_f32_slice(Some(&NativeUniformLocation(location.0)), value);
+        }
+    }
+
+    fn uniform4fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        unsafe {
+            self.gl
+                .uniform_4_f32_slice(Some(&NativeUniformLocation(location.0)), value);
+        }
+    }
+
+    fn uniform_matrix4fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        unsafe {
+            self.gl.uniform_matrix_4_f32_slice(
+                Some(&NativeUniformLocation(location.0)),
+                false,
+                value,
+            );
+        }
+    }
+
+    //
+    // Buffers.
+    //
+
+    fn create_buffer(&self) -> Result<BufferId, String>
+    {
+        unsafe { self.gl.create_buffer() }.map(|buffer| BufferId(buffer.0.get()))
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferId>)
+    {
+        unsafe {
+            self.gl
+                .bind_buffer(target, buffer.map(|buffer| NativeBuffer(handle(buffer.0))));
+        }
+    }
+
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32)
+    {
+        unsafe {
+            self.gl.buffer_data_u8_slice(target, data, usage);
+        }
+    }
+
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8])
+    {
+        unsafe {
+            self.gl.buffer_sub_data_u8_slice(target, offset, data);
+        }
+    }
+
+    //
+    // Vertex arrays.
+    //
+
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String>
+    {
+        unsafe { self.gl.create_vertex_array() }
+            .map(|vertex_array| VertexArrayId(vertex_array.0.get()))
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayId>)
+    {
+        unsafe {
+            self.gl.bind_vertex_array(
+                vertex_array.map(|vertex_array| NativeVertexArray(handle(vertex_array.0))),
+            );
+        }
+    }
+
+    //
+    // Vertex attributes.
+    //
+
+    fn enable_vertex_attrib_array(&self, location: u32)
+    {
+        unsafe {
+            self.gl.enable_vertex_attrib_array(location);
+        }
+    }
+
+    fn disable_vertex_attrib_array(&self, location: u32)
+    {
+        unsafe {
+            self.gl.disable_vertex_attrib_array(location);
+        }
+    }
+
+    fn vertex_attrib_pointer(
+        &self,
+        location: u32,
+        components: i32,
+        type_: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    )
+    {
+        unsafe {
+            self.gl
+                .vertex_attrib_pointer_f32(location, components, type_, normalized, stride, offset);
+        }
+    }
+
+    fn vertex_attrib(&self, location: u32, value: &[f32])
+    {
+        unsafe {
+            match value.len() {
+                1 => self.gl.vertex_attrib_1_f32_slice(location, value),
+                2 => self.gl.vertex_attrib_2_f32_slice(location, value),
+                3 => self.gl.vertex_attrib_3_f32_slice(location, value),
+                4 => self.gl.vertex_attrib_4_f32_slice(location, value),
+                _ => panic!("vertex attributes have between 1 and 4 components"),
+            }
+        }
+    }
+
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32)
+    {
+        unsafe {
+            self.gl.vertex_attrib_divisor(location, divisor);
+        }
+    }
+
+    //
+    // Textures.
+    //
+
+    fn create_texture(&self) -> Result<TextureId, String>
+    {
+        unsafe { self.gl.create_texture() }.map(|texture| TextureId(texture.0.get()))
+    }
+
+    fn active_texture(&self, unit: u32)
+    {
+        unsafe {
+            self.gl.active_texture(unit);
+        }
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<TextureId>)
+    {
+        unsafe {
+            self.gl.bind_texture(
+                target,
+                texture.map(|texture| NativeTexture(handle(texture.0))),
+            );
+        }
+    }
+
+    fn tex_image_2d(&self, image: &TexImage2D, pixels: Option<&[u8]>) -> Result<(), String>
+    {
+        unsafe {
+            self.gl.tex_image_2d(
+                image.target,
+                image.level,
+                image.internal_format,
+                image.width,
+                image.height,
+                image.border,
+                image.format,
+                image.type_,
+                PixelUnpackData::Slice(pixels),
+            );
+        }
+        Ok(())
+    }
+
+    fn tex_parameteri(&self, target: u32, name: u32, value: i32)
+    {
+        unsafe {
+            self.gl.tex_parameter_i32(target, name, value);
+        }
+    }
+
+    fn generate_mipmap(&self, target: u32)
+    {
+        unsafe {
+            self.gl.generate_mipmap(target);
+        }
+    }
+
+    //
+    // Framebuffers.
+    //
+
+    fn create_framebuffer(&self) -> Result<FramebufferId, String>
+    {
+        unsafe { self.gl.create_framebuffer() }
+            .map(|framebuffer| FramebufferId(framebuffer.0.get()))
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferId>)
+    {
+        unsafe {
+            self.gl.bind_framebuffer(
+                target,
+                framebuffer.map(|framebuffer| NativeFramebuffer(handle(framebuffer.0))),
+            );
+        }
+    }
+
+    fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<TextureId>,
+        level: i32,
+    )
+    {
+        unsafe {
+            self.gl.framebuffer_texture_2d(
+                target,
+                attachment,
+                texture_target,
+                texture.map(|texture| NativeTexture(handle(texture.0))),
+                level,
+            );
+        }
+    }
+
+    fn check_framebuffer_status(&self, target: u32) -> u32
+    {
+        unsafe { self.gl.check_framebuffer_status(target) }
+    }
+
+    fn create_renderbuffer(&self) -> Result<RenderbufferId, String>
+    {
+        unsafe { self.gl.create_renderbuffer() }
+            .map(|renderbuffer| RenderbufferId(renderbuffer.0.get()))
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<RenderbufferId>)
+    {
+        unsafe {
+            self.gl.bind_renderbuffer(
+                target,
+                renderbuffer.map(|renderbuffer| NativeRenderbuffer(handle(renderbuffer.0))),
+            );
+        }
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32)
+    {
+        // The unsized depth and stencil format of WebGL has to be sized in OpenGL ES 3.0.
+        let internal_format = match internal_format {
+            glow::DEPTH_STENCIL => glow::DEPTH24_STENCIL8,
+            internal_format => internal_format,
+        };
+
+        unsafe {
+            self.gl
+                .renderbuffer_storage(target, internal_format, width, height);
+        }
+    }
+
+    fn framebuffer_renderbuffer(
+        &self,
+        target: u32,
+        attachment: u32,
+        renderbuffer_target: u32,
+        renderbuffer: Option<RenderbufferId>,
+    )
+    {
+        unsafe {
+            self.gl.framebuffer_renderbuffer(
+                target,
+                attachment,
+                renderbuffer_target,
+                renderbuffer.map(|renderbuffer| NativeRenderbuffer(handle(renderbuffer.0))),
+            );
+        }
+    }
+
+    //
+    // State and drawing.
+    //
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32)
+    {
+        unsafe {
+            self.gl.viewport(x, y, width, height);
+        }
+    }
+
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32)
+    {
+        unsafe {
+            self.gl.clear_color(r, g, b, a);
+        }
+    }
+
+    fn clear(&self, mask: u32)
+    {
+        unsafe {
+            self.gl.clear(mask);
+        }
+    }
+
+    fn enable(&self, capability: u32)
+    {
+        unsafe {
+            self.gl.enable(capability);
+        }
+    }
+
+    fn disable(&self, capability: u32)
+    {
+        unsafe {
+            self.gl.disable(capability);
+        }
+    }
+
+    fn blend_func(&self, source: u32, destination: u32)
+    {
+        unsafe {
+            self.gl.blend_func(source, destination);
+        }
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32)
+    {
+        unsafe {
+            self.gl.draw_arrays(mode, first, count);
+        }
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, type_: u32, offset: i32)
+    {
+        unsafe {
+            self.gl.draw_elements(mode, count, type_, offset);
+        }
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32)
+    {
+        unsafe {
+            self.gl.draw_arrays_instanced(mode, first, count, instances);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+#[cfg(test)]
+use png::{
+    BitDepth,
+    Encoder,
+};
+use png::{
+    ColorType,
+    Decoder,
+    Transformations,
+};
+
+/// An RGBA image with 8 bits per channel, with rows from top to bottom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image
+{
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+/// How much two images may differ and still be considered the same.
+#[cfg(test)]
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance
+{
+    /// Largest difference of a channel of a pixel that isn't counted as a mismatch.
+    pub channel: u8,
+
+    /// Number of mismatching pixels allowed, for rounding differences along the edges of shapes.
+    pub pixels: usize,
+}
+
+impl Image
+{
+    /// An image of `width` by `height` pixels, with 4 bytes per pixel in `pixels`.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Image, String>
+    {
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|count| count.checked_mul(4))
+            .ok_or_else(|| format!("{}x{} image is too large", width, height))?;
+
+        if pixels.len() != size {
+            return Err(format!(
+                "{} bytes of pixels for a {}x{} image, expected {}",
+                pixels.len(),
+                width,
+                height,
+                size
+            ));
+        }
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Decodes a PNG image of any color type, converted to 8 bit RGBA.
+    pub fn decode_png(bytes: &[u8]) -> Result<Image, String>
+    {
+        let mut decoder = Decoder::new(bytes);
+        decoder.set_transformations(Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info().map_err(|error| format!("{}", error))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .map_err(|error| format!("{}", error))?;
+        let data = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            ColorType::Rgba => data.to_vec(),
+            ColorType::Rgb => data
+                .chunks(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            ColorType::GrayscaleAlpha => data
+                .chunks(2)
+                .flat_map(|la| [la[0], la[0], la[0], la[1]])
+                .collect(),
+            ColorType::Grayscale => data.iter().flat_map(|&l| [l, l, l, 255]).collect(),
+            ColorType::Indexed => return Err("indexed PNG images weren't expanded".to_string()),
+        };
+
+        Image::new(info.width, info.height, pixels)
+    }
+
+    pub fn width(&self) -> u32
+    {
+        self.width
+    }
+
+    pub fn height(&self) -> u32
+    {
+        self.height
+    }
+
+    /// The bytes of the pixels, row by row from the top.
+    pub fn pixels(&self) -> &[u8]
+    {
+        &self.pixels
+    }
+}
+
+#[cfg(test)]
+impl Image
+{
+    pub fn encode_png(&self) -> Result<Vec<u8>, String>
+    {
+        let mut bytes = Vec::new();
+
+        let mut encoder = Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|error| format!("{}", error))?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|error| format!("{}", error))?;
+        writer.finish().map_err(|error| format!("{}", error))?;
+
+        Ok(bytes)
+    }
+
+    /// The pixel at column `x` of row `y`, counted from the top left corner.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4]
+    {
+        let i = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    /// Checks that the image is the same as `expected` within `tolerance`, with an error
+    /// describing how they differ otherwise.
+    pub fn compare(&self, expected: &Image, tolerance: Tolerance) -> Result<(), String>
+    {
+        if (self.width, self.height) != (expected.width, expected.height) {
+            return Err(format!(
+                "image is {}x{}, expected {}x{}",
+                self.width, self.height, expected.width, expected.height
+            ));
+        }
+
+        let mut mismatches = 0;
+        let mut largest = (0, 0, 0);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (a, b) = (self.pixel(x, y), expected.pixel(x, y));
+                let difference = (0..4)
+                    .map(|i| (a[i] as i32 - b[i] as i32).unsigned_abs() as u8)
+                    .max()
+                    .unwrap();
+
+                if difference > tolerance.channel {
+                    mismatches += 1;
+                }
+                if difference > largest.0 {
+                    largest = (difference, x, y);
+                }
+            }
+        }
+
+        if mismatches > tolerance.pixels {
+            let (difference, x, y) = largest;
+            return Err(format!(
+                "{} pixels differ by more than {}, the largest difference is {} at ({}, {}) where \
+                 the pixel is {:?} instead of {:?}",
+                mismatches,
+                tolerance.channel,
+                difference,
+                x,
+                y,
+                self.pixel(x, y),
+                expected.pixel(x, y)
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    /// Encodes a PNG image with pixels of `color` and `depth` in `data`, with an optional
+    /// palette and transparency chunk.
+    fn encode(
+        (width, height): (u32, u32),
+        color: ColorType,
+        depth: BitDepth,
+        data: &[u8],
+        palette: Option<(&[u8], &[u8])>,
+    ) -> Vec<u8>
+    {
+        let mut bytes = Vec::new();
+
+        let mut encoder = Encoder::new(&mut bytes, width, height);
+        encoder.set_color(color);
+        encoder.set_depth(depth);
+        if let Some((palette, trns)) = palette {
+            encoder.set_palette(palette.to_vec());
+            encoder.set_trns(trns.to_vec());
+        }
+
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        writer.finish().unwrap();
+
+        bytes
+    }
+
+    #[test]
+    fn rgba_is_decoded_unchanged()
+    {
+        let pixels = [255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0, 9, 8, 7, 6];
+        let bytes = encode((2, 2), ColorType::Rgba, BitDepth::Eight, &pixels, None);
+
+        assert_eq!(
+            Image::decode_png(&bytes).unwrap(),
+            Image::new(2, 2, pixels.to_vec()).unwrap()
+        );
+    }
+
+    #[test]
+    fn rgb_is_decoded_opaque()
+    {
+        let bytes = encode(
+            (2, 1),
+            ColorType::Rgb,
+            BitDepth::Eight,
+            &[255, 0, 0, 1, 2, 3],
+            None,
+        );
+
+        let image = Image::decode_png(&bytes).unwrap();
+        assert_eq!(image.pixels(), [255, 0, 0, 255, 1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn grayscale_is_decoded_into_every_color_channel()
+    {
+        let bytes = encode(
+            (1, 2),
+            ColorType::Grayscale,
+            BitDepth::Eight,
+            &[0, 200],
+            None,
+        );
+        let image = Image::decode_png(&bytes).unwrap();
+        assert_eq!((image.width(), image.height()), (1, 2));
+        assert_eq!(image.pixels(), [0, 0, 0, 255, 200, 200, 200, 255]);
+
+        let bytes = encode(
+            (2, 1),
+            ColorType::GrayscaleAlpha,
+            BitDepth::Eight,
+            &[50, 100, 150, 200],
+            None,
+        );
+        let image = Image::decode_png(&bytes).unwrap();
+        assert_eq!(image.pixels(), [50, 50, 50, 100, 150, 150, 150, 200]);
+
+        // Channels of 16 bits are reduced to their most significant byte.
+        let bytes = encode(
+            (1, 1),
+            ColorType::Grayscale,
+            BitDepth::Sixteen,
+            &[0x12, 0x34],
+            None,
+        );
+        let image = Image::decode_png(&bytes).unwrap();
+        assert_eq!(image.pixels(), [0x12, 0x12, 0x12, 255]);
+    }
+
+    #[test]
+    fn indexed_is_decoded_through_its_palette()
+    {
+        let palette: (&[u8], &[u8]) = (&[10, 20, 30, 40, 50, 60], &[128]);
+
+        let bytes = encode(
+            (2, 1),
+            ColorType::Indexed,
+            BitDepth::Eight,
+            &[1, 0],
+            Some(palette),
+        );
+        let image = Image::decode_png(&bytes).unwrap();
+        assert_eq!(image.pixels(), [40, 50, 60, 255, 10, 20, 30, 128]);
+
+        // Indices of a bit are packed eight to a byte, from the most significant bit.
+        let bytes = encode(
+            (3, 1),
+            ColorType::Indexed,
+            BitDepth::One,
+            &[0b0110_0000],
+            Some(palette),
+        );
+        let image = Image::decode_png(&bytes).unwrap();
+        assert_eq!(
+            image.pixels(),
+            [10, 20, 30, 128, 40, 50, 60, 255, 40, 50, 60, 255]
+        );
+    }
+
+    #[test]
+    fn invalid_data_is_an_error()
+    {
+        assert!(Image::decode_png(b"not a png").is_err());
+
+        let bytes = encode((1, 1), ColorType::Rgb, BitDepth::Eight, &[1, 2, 3], None);
+        assert!(Image::decode_png(&bytes[..bytes.len() - 20]).is_err());
+    }
+
+    #[test]
+    fn size_is_checked_without_overflowing()
+    {
+        assert!(Image::new(2, 3, vec![0; 24]).is_ok());
+        assert!(Image::new(2, 3, vec![0; 23]).is_err());
+        assert!(Image::new(u32::MAX, u32::MAX, Vec::new()).is_err());
+    }
+}
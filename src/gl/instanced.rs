@@ -0,0 +1,121 @@
+use std::ops::Range;
+
+use super::{
+    buffer::{
+        ArrayBuffer,
+        BufferUsage,
+        VertexAttributeType,
+        VertexLayout,
+    },
+    device::GraphicsDevice,
+    program::Program,
+};
+
+/// Draws many instances of the same geometry with per-instance attributes stored in one dynamic
+/// buffer.
+///
+/// Draws all instances with a single draw call when the device supports instancing, natively on
+/// WebGL2 or with `ANGLE_instanced_arrays` on WebGL1. Otherwise every instance is drawn with its
+/// own draw call, with the per-instance attributes set as constant vertex attributes.
+pub struct InstanceBuffer
+{
+    buffer: ArrayBuffer<f32>,
+
+    /// Layout of the per-instance attributes, which must all be floats.
+    layout: VertexLayout,
+
+    /// Number of floats per instance.
+    stride: usize,
+
+    /// The per-instance attributes last uploaded, kept for drawing without the extension.
+    data: Vec<f32>,
+}
+
+impl InstanceBuffer
+{
+    /// Creates a buffer of instances with attributes laid out as in `layout`.
+    pub fn new(gl: &dyn GraphicsDevice, layout: VertexLayout) -> Result<InstanceBuffer, String>
+    {
+        debug_assert!(layout
+            .attributes()
+            .iter()
+            .all(|attribute| matches!(attribute.type_(), VertexAttributeType::Float)));
+
+        let buffer = ArrayBuffer::new(gl)?;
+        let stride = *layout.stride() as usize / 4;
+
+        Ok(InstanceBuffer {
+            buffer,
+            layout,
+            stride,
+            data: Vec::new(),
+        })
+    }
+
+    /// Replaces the instances with those in `data`, which holds the attributes of every instance
+    /// one after the other.
+    pub fn set_data(&mut self, gl: &dyn GraphicsDevice, data: Vec<f32>)
+    {
+        debug_assert_eq!(data.len() % self.stride, 0);
+
+        if gl.capabilities().instancing {
+            self.buffer.update(gl, BufferUsage::DynamicDraw, &data);
+        }
+
+        self.data = data;
+    }
+
+    /// Draws the instances in `instances` with `count` vertices each, using `program` which must
+    /// have been created with the layout of the instances.
+    pub fn draw(
+        &self,
+        gl: &dyn GraphicsDevice,
+        program: &Program,
+        mode: u32,
+        count: i32,
+        instances: Range<usize>,
+    )
+    {
+        if instances.is_empty() {
+            return;
+        }
+
+        match gl.capabilities().instancing {
+            true => {
+                let offset = (instances.start * self.stride * 4) as i32;
+                program.enable_vertex_layout(gl, &self.layout, &self.buffer, offset);
+
+                for attribute in self.layout.attributes() {
+                    gl.vertex_attrib_divisor(program.attribute(attribute.name()), 1);
+                }
+
+                gl.draw_arrays_instanced(mode, 0, count, instances.len() as i32);
+
+                for attribute in self.layout.attributes() {
+                    gl.vertex_attrib_divisor(program.attribute(attribute.name()), 0);
+                }
+
+                program.disable_vertex_layout(gl, &self.layout);
+            }
+            false => {
+                for instance in self
+                    .data
+                    .chunks(self.stride)
+                    .skip(instances.start)
+                    .take(instances.len())
+                {
+                    for attribute in self.layout.attributes() {
+                        let start = *attribute.offset() as usize / 4;
+                        let end = start + *attribute.components() as usize;
+                        gl.vertex_attrib(
+                            program.attribute(attribute.name()),
+                            &instance[start..end],
+                        );
+                    }
+
+                    gl.draw_arrays(mode, 0, count);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    marker::PhantomData,
+};
+
+use web_sys::WebGlRenderingContext;
+
+use super::{
+    buffer::{
+        ArrayBuffer,
+        ArrayBufferType,
+        VertexLayout,
+    },
+    device::{
+        ActiveUniform,
+        BufferId,
+        GraphicsDevice,
+        ProgramId,
+        ShaderId,
+        UniformLocationId,
+        VertexArrayId,
+    },
+};
+
+type GL = WebGlRenderingContext;
+
+/// The type of a uniform, with the value it is set with.
+pub trait UniformType
+{
+    type Value: ?Sized;
+
+    /// Name of the type in GLSL, used in error messages.
+    const NAME: &'static str;
+
+    /// The type as reported by `getActiveUniform`.
+    const GL_TYPE: u32;
+
+    fn set(gl: &dyn GraphicsDevice, location: UniformLocationId, value: &Self::Value);
+}
+
+pub struct Float;
+
+impl UniformType for Float
+{
+    type Value = f32;
+
+    const GL_TYPE: u32 = GL::FLOAT;
+    const NAME: &'static str = "float";
+
+    fn set(gl: &dyn GraphicsDevice, location: UniformLocationId, value: &f32)
+    {
+        gl.uniform1f(location, *value);
+    }
+}
+
+pub struct Vec2;
+
+impl UniformType for Vec2
+{
+    type Value = [f32; 2];
+
+    const GL_TYPE: u32 = GL::FLOAT_VEC2;
+    const NAME: &'static str = "vec2";
+
+    fn set(gl: &dyn GraphicsDevice, location: UniformLocationId, value: &[f32; 2])
+    {
+        gl.uniform2fv(location, value);
+    }
+}
+
+pub struct Vec4;
+
+impl UniformType for Vec4
+{
+    type Value = [f32; 4];
+
+    const GL_TYPE: u32 = GL::FLOAT_VEC4;
+    const NAME: &'static str = "vec4";
+
+    fn set(gl: &dyn GraphicsDevice, location: UniformLocationId, value: &[f32; 4])
+    {
+        gl.uniform4fv(location, value);
+    }
+}
+
+pub struct Mat4;
+
+impl UniformType for Mat4
+{
+    /// The matrix in the layout of `Matrix4x4::into_array`.
+    type Value = [f32];
+
+    const GL_TYPE: u32 = GL::FLOAT_MAT4;
+    const NAME: &'static str = "mat4";
+
+    fn set(gl: &dyn GraphicsDevice, location: UniformLocationId, value: &[f32])
+    {
+        debug_assert_eq!(value.len(), 16);
+        gl.uniform_matrix4fv(location, value);
+    }
+}
+
+/// A `sampler2D`, set with the texture unit to sample from.
+pub struct Sampler2D;
+
+impl UniformType for Sampler2D
+{
+    type Value = i32;
+
+    const GL_TYPE: u32 = GL::SAMPLER_2D;
+    const NAME: &'static str = "sampler2D";
+
+    fn set(gl: &dyn GraphicsDevice, location: UniformLocationId, value: &i32)
+    {
+        gl.uniform1i(location, *value);
+    }
+}
+
+/// A uniform of a `Program` whose type has been checked.
+pub struct Uniform<T: UniformType>
+{
+    location: UniformLocationId,
+    _type: PhantomData<T>,
+}
+
+impl<T: UniformType> Uniform<T>
+{
+    /// Sets the uniform. The program it belongs to must be in use.
+    pub fn set(&self, gl: &dyn GraphicsDevice, value: &T::Value)
+    {
+        T::set(gl, self.location, value);
+    }
+}
+
+/// A linked shader program with its attribute and uniform locations resolved once.
+///
+/// The attributes of the vertex layouts the program is created with are bound to explicit
+/// locations when linking, in the order they are given.
+///
+/// With the vertex arrays capability the program has its own vertex array, bound while the
+/// program is in use, which keeps the attribute pointers between draws. Enabling a layout then
+/// only changes the pointers when it is enabled with another buffer or offset than before.
+pub struct Program
+{
+    program: ProgramId,
+    attributes: Vec<String>,
+    uniforms: HashMap<String, ActiveUniform>,
+    vertex_array: Option<VertexArrayId>,
+
+    /// The buffer and offset each attribute location of the vertex array points into.
+    pointers: RefCell<HashMap<u32, (BufferId, i32)>>,
+}
+
+impl Program
+{
+    pub fn new(
+        gl: &dyn GraphicsDevice,
+        vertex_shader: &ShaderId,
+        fragment_shader: &ShaderId,
+        layouts: &[&VertexLayout],
+    ) -> Result<Program, String>
+    {
+        let attributes: Vec<&str> = layouts
+            .iter()
+            .flat_map(|layout| layout.attributes())
+            .map(|attribute| *attribute.name())
+            .collect();
+
+        let program = gl.link_program(*vertex_shader, *fragment_shader, &attributes)?;
+
+        // Unused attributes are removed when linking, which is most likely a misspelled name.
+        for name in &attributes {
+            if gl.attribute_location(program, name) < 0 {
+                return Err(format!("no active attribute '{}'", name));
+            }
+        }
+
+        let uniforms = gl
+            .active_uniforms(program)
+            .into_iter()
+            .map(|uniform| (uniform.name.clone(), uniform))
+            .collect();
+
+        let vertex_array = match gl.capabilities().vertex_arrays {
+            true => Some(gl.create_vertex_array()?),
+            false => None,
+        };
+
+        Ok(Program {
+            program,
+            attributes: attributes.iter().map(|name| name.to_string()).collect(),
+            uniforms,
+            vertex_array,
+            pointers: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// The location `name` was bound to when linking.
+    ///
+    /// Panics if `name` is not an attribute of the vertex layouts the program was created with.
+    pub fn attribute(&self, name: &str) -> u32
+    {
+        self.attributes
+            .iter()
+            .position(|attribute| attribute == name)
+            .unwrap_or_else(|| panic!("attribute '{}' was not bound when linking", name))
+            as u32
+    }
+
+    /// Points the attributes of `layout` at the vertices in `buffer`, starting `offset` bytes
+    /// into it. The program must be in use.
+    pub fn enable_vertex_layout<T: ArrayBufferType>(
+        &self,
+        gl: &dyn GraphicsDevice,
+        layout: &VertexLayout,
+        buffer: &ArrayBuffer<T>,
+        offset: i32,
+    )
+    {
+        let pointer = (*buffer.buffer(), offset);
+
+        if self.vertex_array.is_some() {
+            let mut pointers = self.pointers.borrow_mut();
+            let recorded = layout
+                .attributes()
+                .iter()
+                .all(|attribute| pointers.get(&self.attribute(attribute.name())) == Some(&pointer));
+
+            if recorded {
+                return;
+            }
+
+            for attribute in layout.attributes() {
+                pointers.insert(self.attribute(attribute.name()), pointer);
+            }
+        }
+
+        buffer.bind(gl);
+
+        for attribute in layout.attributes() {
+            let location = self.attribute(attribute.name());
+            gl.enable_vertex_attrib_array(location);
+            gl.vertex_attrib_pointer(
+                location,
+                *attribute.components(),
+                attribute.type_().value(),
+                attribute.type_().normalized(),
+                *layout.stride(),
+                offset + attribute.offset(),
+            );
+        }
+
+        buffer.unbind(gl);
+    }
+
+    /// Disables the attributes of `layout`, unless they are kept enabled in the vertex array of
+    /// the program.
+    pub fn disable_vertex_layout(&self, gl: &dyn GraphicsDevice, layout: &VertexLayout)
+    {
+        if self.vertex_array.is_some() {
+            return;
+        }
+
+        for attribute in layout.attributes() {
+            gl.disable_vertex_attrib_array(self.attribute(attribute.name()));
+        }
+    }
+
+    /// The uniform `name`, which must be active and of type `T`.
+    pub fn uniform<T: UniformType>(&self, name: &str) -> Result<Uniform<T>, String>
+    {
+        let uniform = self.uniforms.get(name).ok_or_else(|| {
+            let mut names: Vec<&str> = self.uniforms.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            format!(
+                "no active uniform '{}', active uniforms are: {}",
+                name,
+                names.join(", ")
+            )
+        })?;
+
+        if uniform.type_ != T::GL_TYPE {
+            return Err(format!("uniform '{}' is not a {}", name, T::NAME));
+        }
+
+        Ok(Uniform {
+            location: uniform.location,
+            _type: PhantomData,
+        })
+    }
+
+    pub fn use_program(&self, gl: &dyn GraphicsDevice)
+    {
+        gl.use_program(Some(self.program));
+
+        if self.vertex_array.is_some() {
+            gl.bind_vertex_array(self.vertex_array);
+        }
+    }
+
+    pub fn unuse_program(&self, gl: &dyn GraphicsDevice)
+    {
+        if self.vertex_array.is_some() {
+            gl.bind_vertex_array(None);
+        }
+
+        gl.use_program(None);
+    }
+}
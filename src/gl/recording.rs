@@ -0,0 +1,796 @@
+use std::cell::{
+    Cell,
+    RefCell,
+};
+
+use web_sys::{
+    WebGl2RenderingContext,
+    WebGlRenderingContext,
+};
+
+use super::device::{
+    ActiveUniform,
+    BufferId,
+    Capabilities,
+    FramebufferId,
+    GraphicsDevice,
+    ProgramId,
+    RenderbufferId,
+    ShaderId,
+    TexImage2D,
+    TextureId,
+    UniformLocationId,
+    VertexArrayId,
+};
+
+type GL = WebGlRenderingContext;
+
+/// An operation performed on a `RecordingDevice`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command
+{
+    CompileShader
+    {
+        shader: ShaderId,
+        type_: u32,
+    },
+    LinkProgram
+    {
+        program: ProgramId,
+        vertex_shader: ShaderId,
+        fragment_shader: ShaderId,
+    },
+    UseProgram(Option<ProgramId>),
+    Uniform1f
+    {
+        location: UniformLocationId,
+        value: f32,
+    },
+    Uniform1i
+    {
+        location: UniformLocationId,
+        value: i32,
+    },
+    Uniform2fv
+    {
+        location: UniformLocationId,
+        value: Vec<f32>,
+    },
+    Uniform4fv
+    {
+        location: UniformLocationId,
+        value: Vec<f32>,
+    },
+    UniformMatrix4fv
+    {
+        location: UniformLocationId,
+        value: Vec<f32>,
+    },
+
+    CreateBuffer(BufferId),
+    BindBuffer
+    {
+        target: u32,
+        buffer: Option<BufferId>,
+    },
+    /// `len` is the size of the data in bytes.
+    BufferData
+    {
+        target: u32,
+        len: usize,
+        usage: u32,
+    },
+    BufferSubData
+    {
+        target: u32,
+        offset: i32,
+        len: usize,
+    },
+
+    CreateVertexArray(VertexArrayId),
+    BindVertexArray(Option<VertexArrayId>),
+
+    EnableVertexAttribArray(u32),
+    DisableVertexAttribArray(u32),
+    VertexAttribPointer
+    {
+        location: u32,
+        components: i32,
+        type_: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    },
+    VertexAttrib
+    {
+        location: u32,
+        value: Vec<f32>,
+    },
+    VertexAttribDivisor
+    {
+        location: u32,
+        divisor: u32,
+    },
+
+    CreateTexture(TextureId),
+    ActiveTexture(u32),
+    BindTexture
+    {
+        target: u32,
+        texture: Option<TextureId>,
+    },
+    TexImage2D
+    {
+        image: TexImage2D,
+        with_pixels: bool,
+    },
+    TexParameteri
+    {
+        target: u32,
+        name: u32,
+        value: i32,
+    },
+    GenerateMipmap
+    {
+        target: u32,
+    },
+
+    CreateFramebuffer(FramebufferId),
+    BindFramebuffer
+    {
+        target: u32,
+        framebuffer: Option<FramebufferId>,
+    },
+    FramebufferTexture2D
+    {
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<TextureId>,
+        level: i32,
+    },
+    CheckFramebufferStatus(u32),
+    CreateRenderbuffer(RenderbufferId),
+    BindRenderbuffer
+    {
+        target: u32,
+        renderbuffer: Option<RenderbufferId>,
+    },
+    RenderbufferStorage
+    {
+        target: u32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+    },
+    FramebufferRenderbuffer
+    {
+        target: u32,
+        attachment: u32,
+        renderbuffer_target: u32,
+        renderbuffer: Option<RenderbufferId>,
+    },
+
+    Viewport([i32; 4]),
+    ClearColor([f32; 4]),
+    Clear(u32),
+    Enable(u32),
+    Disable(u32),
+    BlendFunc
+    {
+        source: u32,
+        destination: u32,
+    },
+    DrawArrays
+    {
+        mode: u32,
+        first: i32,
+        count: i32,
+    },
+    DrawElements
+    {
+        mode: u32,
+        count: i32,
+        type_: u32,
+        offset: i32,
+    },
+    DrawArraysInstanced
+    {
+        mode: u32,
+        first: i32,
+        count: i32,
+        instances: i32,
+    },
+}
+
+struct Program
+{
+    /// Attributes in the order of their declaration in the vertex shader.
+    declared_attributes: Vec<String>,
+
+    /// Attributes bound to their index as location when linking.
+    bound_attributes: Vec<String>,
+
+    /// Names and types of the uniforms declared in both shaders.
+    uniforms: Vec<(String, u32)>,
+}
+
+/// A `GraphicsDevice` that draws nothing and keeps a log of the commands issued to it, for testing
+/// renderers without a browser.
+///
+/// Shader sources aren't compiled, the attributes and uniforms of a program are those declared
+/// with one `attribute` or `uniform` declaration per line in its shaders.
+pub struct RecordingDevice
+{
+    capabilities: Capabilities,
+    commands: RefCell<Vec<Command>>,
+
+    /// Sources of the compiled shaders.
+    shaders: RefCell<Vec<String>>,
+
+    programs: RefCell<Vec<Program>>,
+
+    /// Names of the uniforms whose location has been queried.
+    uniform_names: RefCell<Vec<String>>,
+
+    /// The next id of buffers, vertex arrays, textures, framebuffers and renderbuffers.
+    next_id: Cell<u32>,
+}
+
+impl RecordingDevice
+{
+    /// A device with the capabilities of WebGL2 apart from float render targets.
+    pub fn new() -> RecordingDevice
+    {
+        RecordingDevice::with_capabilities(Capabilities {
+            vertex_arrays: true,
+            instancing: true,
+            float_render_targets: false,
+            npot_mipmaps: true,
+        })
+    }
+
+    pub fn with_capabilities(capabilities: Capabilities) -> RecordingDevice
+    {
+        RecordingDevice {
+            capabilities,
+            commands: RefCell::new(Vec::new()),
+            shaders: RefCell::new(Vec::new()),
+            programs: RefCell::new(Vec::new()),
+            uniform_names: RefCell::new(Vec::new()),
+            next_id: Cell::new(0),
+        }
+    }
+
+    /// The commands issued since the last call, clearing the log.
+    pub fn take_commands(&self) -> Vec<Command>
+    {
+        self.commands.replace(Vec::new())
+    }
+
+    /// The name of the uniform at `location`.
+    pub fn uniform_name(&self, location: UniformLocationId) -> String
+    {
+        self.uniform_names.borrow()[location.0 as usize].clone()
+    }
+
+    fn record(&self, command: Command)
+    {
+        self.commands.borrow_mut().push(command);
+    }
+
+    fn next_id(&self) -> u32
+    {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+}
+
+impl Default for RecordingDevice
+{
+    fn default() -> RecordingDevice
+    {
+        RecordingDevice::new()
+    }
+}
+
+impl GraphicsDevice for RecordingDevice
+{
+    fn name(&self) -> &'static str
+    {
+        "RECORDING"
+    }
+
+    fn capabilities(&self) -> Capabilities
+    {
+        self.capabilities
+    }
+
+    fn float_texture_format(&self) -> Option<(i32, u32, u32)>
+    {
+        match self.capabilities.float_render_targets {
+            true => Some((
+                WebGl2RenderingContext::RGBA16F as i32,
+                GL::RGBA,
+                WebGl2RenderingContext::HALF_FLOAT,
+            )),
+            false => None,
+        }
+    }
+
+    //
+    // Shaders and programs.
+    //
+
+    fn compile_shader(&self, type_: u32, source: &str) -> Result<ShaderId, String>
+    {
+        let mut shaders = self.shaders.borrow_mut();
+        let shader = ShaderId(shaders.len() as u32);
+        shaders.push(source.to_string());
+
+        self.record(Command::CompileShader { shader, type_ });
+        Ok(shader)
+    }
+
+    fn link_program(
+        &self,
+        vertex_shader: ShaderId,
+        fragment_shader: ShaderId,
+        attributes: &[&str],
+    ) -> Result<ProgramId, String>
+    {
+        let shaders = self.shaders.borrow();
+        let vertex_source = &shaders[vertex_shader.0 as usize];
+        let fragment_source = &shaders[fragment_shader.0 as usize];
+
+        let declared_attributes = declarations(vertex_source, "attribute")
+            .map(|(_, name)| name)
+            .collect();
+
+        let mut uniforms: Vec<(String, u32)> = Vec::new();
+        for (type_, name) in
+            declarations(vertex_source, "uniform").chain(declarations(fragment_source, "uniform"))
+        {
+            if !uniforms.iter().any(|(other, _)| *other == name) {
+                uniforms.push((name, uniform_type(&type_)));
+            }
+        }
+
+        let mut programs = self.programs.borrow_mut();
+        let program = ProgramId(programs.len() as u32);
+        programs.push(Program {
+            declared_attributes,
+            bound_attributes: attributes.iter().map(|name| name.to_string()).collect(),
+            uniforms,
+        });
+
+        self.record(Command::LinkProgram {
+            program,
+            vertex_shader,
+            fragment_shader,
+        });
+        Ok(program)
+    }
+
+    fn attribute_location(&self, program: ProgramId, name: &str) -> i32
+    {
+        let programs = self.programs.borrow();
+        let program = &programs[program.0 as usize];
+
+        if !program
+            .declared_attributes
+            .iter()
+            .any(|other| other == name)
+        {
+            return -1;
+        }
+
+        // Attributes that weren't bound are placed after the bound ones.
+        program
+            .bound_attributes
+            .iter()
+            .position(|other| other == name)
+            .unwrap_or_else(|| {
+                program.bound_attributes.len()
+                    + program
+                        .declared_attributes
+                        .iter()
+                        .filter(|other| !program.bound_attributes.contains(other))
+                        .position(|other| other == name)
+                        .unwrap_or(0)
+            }) as i32
+    }
+
+    fn active_uniforms(&self, program: ProgramId) -> Vec<ActiveUniform>
+    {
+        let programs = self.programs.borrow();
+        let mut uniform_names = self.uniform_names.borrow_mut();
+
+        programs[program.0 as usize]
+            .uniforms
+            .iter()
+            .map(|(name, type_)| {
+                uniform_names.push(name.clone());
+                ActiveUniform {
+                    name: name.clone(),
+                    type_: *type_,
+                    location: UniformLocationId(uniform_names.len() as u32 - 1),
+                }
+            })
+            .collect()
+    }
+
+    fn use_program(&self, program: Option<ProgramId>)
+    {
+        self.record(Command::UseProgram(program));
+    }
+
+    fn uniform1f(&self, location: UniformLocationId, value: f32)
+    {
+        self.record(Command::Uniform1f { location, value });
+    }
+
+    fn uniform1i(&self, location: UniformLocationId, value: i32)
+    {
+        self.record(Command::Uniform1i { location, value });
+    }
+
+    fn uniform2fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.record(Command::Uniform2fv {
+            location,
+            value: value.to_vec(),
+        });
+    }
+
+    fn uniform4fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.record(Command::Uniform4fv {
+            location,
+            value: value.to_vec(),
+        });
+    }
+
+    fn uniform_matrix4fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.record(Command::UniformMatrix4fv {
+            location,
+            value: value.to_vec(),
+        });
+    }
+
+    //
+    // Buffers.
+    //
+
+    fn create_buffer(&self) -> Result<BufferId, String>
+    {
+        let buffer = BufferId(self.next_id());
+        self.record(Command::CreateBuffer(buffer));
+        Ok(buffer)
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferId>)
+    {
+        self.record(Command::BindBuffer { target, buffer });
+    }
+
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32)
+    {
+        self.record(Command::BufferData {
+            target,
+            len: data.len(),
+            usage,
+        });
+    }
+
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8])
+    {
+        self.record(Command::BufferSubData {
+            target,
+            offset,
+            len: data.len(),
+        });
+    }
+
+    //
+    // Vertex arrays.
+    //
+
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String>
+    {
+        if !self.capabilities.vertex_arrays {
+            return Err(String::from("vertex arrays are not supported"));
+        }
+
+        let vertex_array = VertexArrayId(self.next_id());
+        self.record(Command::CreateVertexArray(vertex_array));
+        Ok(vertex_array)
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayId>)
+    {
+        self.record(Command::BindVertexArray(vertex_array));
+    }
+
+    //
+    // Vertex attributes.
+    //
+
+    fn enable_vertex_attrib_array(&self, location: u32)
+    {
+        self.record(Command::EnableVertexAttribArray(location));
+    }
+
+    fn disable_vertex_attrib_array(&self, location: u32)
+    {
+        self.record(Command::DisableVertexAttribArray(location));
+    }
+
+    fn vertex_attrib_pointer(
+        &self,
+        location: u32,
+        components: i32,
+        type_: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    )
+    {
+        self.record(Command::VertexAttribPointer {
+            location,
+            components,
+            type_,
+            normalized,
+            stride,
+            offset,
+        });
+    }
+
+    fn vertex_attrib(&self, location: u32, value: &[f32])
+    {
+        self.record(Command::VertexAttrib {
+            location,
+            value: value.to_vec(),
+        });
+    }
+
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32)
+    {
+        if self.capabilities.instancing {
+            self.record(Command::VertexAttribDivisor { location, divisor });
+        }
+    }
+
+    //
+    // Textures.
+    //
+
+    fn create_texture(&self) -> Result<TextureId, String>
+    {
+        let texture = TextureId(self.next_id());
+        self.record(Command::CreateTexture(texture));
+        Ok(texture)
+    }
+
+    fn active_texture(&self, unit: u32)
+    {
+        self.record(Command::ActiveTexture(unit));
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<TextureId>)
+    {
+        self.record(Command::BindTexture { target, texture });
+    }
+
+    fn tex_image_2d(&self, image: &TexImage2D, pixels: Option<&[u8]>) -> Result<(), String>
+    {
+        self.record(Command::TexImage2D {
+            image: *image,
+            with_pixels: pixels.is_some(),
+        });
+        Ok(())
+    }
+
+    fn tex_parameteri(&self, target: u32, name: u32, value: i32)
+    {
+        self.record(Command::TexParameteri {
+            target,
+            name,
+            value,
+        });
+    }
+
+    fn generate_mipmap(&self, target: u32)
+    {
+        self.record(Command::GenerateMipmap { target });
+    }
+
+    //
+    // Framebuffers.
+    //
+
+    fn create_framebuffer(&self) -> Result<FramebufferId, String>
+    {
+        let framebuffer = FramebufferId(self.next_id());
+        self.record(Command::CreateFramebuffer(framebuffer));
+        Ok(framebuffer)
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferId>)
+    {
+        self.record(Command::BindFramebuffer {
+            target,
+            framebuffer,
+        });
+    }
+
+    fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<TextureId>,
+        level: i32,
+    )
+    {
+        self.record(Command::FramebufferTexture2D {
+            target,
+            attachment,
+            texture_target,
+            texture,
+            level,
+        });
+    }
+
+    /// Framebuffers are always complete.
+    fn check_framebuffer_status(&self, target: u32) -> u32
+    {
+        self.record(Command::CheckFramebufferStatus(target));
+        GL::FRAMEBUFFER_COMPLETE
+    }
+
+    fn create_renderbuffer(&self) -> Result<RenderbufferId, String>
+    {
+        let renderbuffer = RenderbufferId(self.next_id());
+        self.record(Command::CreateRenderbuffer(renderbuffer));
+        Ok(renderbuffer)
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<RenderbufferId>)
+    {
+        self.record(Command::BindRenderbuffer {
+            target,
+            renderbuffer,
+        });
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32)
+    {
+        self.record(Command::RenderbufferStorage {
+            target,
+            internal_format,
+            width,
+            height,
+        });
+    }
+
+    fn framebuffer_renderbuffer(
+        &self,
+        target: u32,
+        attachment: u32,
+        renderbuffer_target: u32,
+        renderbuffer: Option<RenderbufferId>,
+    )
+    {
+        self.record(Command::FramebufferRenderbuffer {
+            target,
+            attachment,
+            renderbuffer_target,
+            renderbuffer,
+        });
+    }
+
+    //
+    // State and drawing.
+    //
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32)
+    {
+        self.record(Command::Viewport([x, y, width, height]));
+    }
+
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32)
+    {
+        self.record(Command::ClearColor([r, g, b, a]));
+    }
+
+    fn clear(&self, mask: u32)
+    {
+        self.record(Command::Clear(mask));
+    }
+
+    fn enable(&self, capability: u32)
+    {
+        self.record(Command::Enable(capability));
+    }
+
+    fn disable(&self, capability: u32)
+    {
+        self.record(Command::Disable(capability));
+    }
+
+    fn blend_func(&self, source: u32, destination: u32)
+    {
+        self.record(Command::BlendFunc {
+            source,
+            destination,
+        });
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32)
+    {
+        self.record(Command::DrawArrays { mode, first, count });
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, type_: u32, offset: i32)
+    {
+        self.record(Command::DrawElements {
+            mode,
+            count,
+            type_,
+            offset,
+        });
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32)
+    {
+        if self.capabilities.instancing {
+            self.record(Command::DrawArraysInstanced {
+                mode,
+                first,
+                count,
+                instances,
+            });
+        }
+    }
+}
+
+/// The types and names declared with `qualifier` in `source`, with the size of arrays removed.
+fn declarations<'a>(
+    source: &'a str,
+    qualifier: &'a str,
+) -> impl Iterator<Item = (String, String)> + 'a
+{
+    source.lines().filter_map(move |line| {
+        let words: Vec<&str> = line
+            .trim()
+            .trim_end_matches(';')
+            .split_whitespace()
+            .collect();
+
+        match words.as_slice() {
+            [first, .., type_, name] if *first == qualifier => {
+                let name = name.split('[').next().unwrap_or(name);
+                Some((type_.to_string(), name.to_string()))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// The value `getActiveUniform` reports for the GLSL type `name`.
+fn uniform_type(name: &str) -> u32
+{
+    match name {
+        "int" => GL::INT,
+        "float" => GL::FLOAT,
+        "vec2" => GL::FLOAT_VEC2,
+        "vec3" => GL::FLOAT_VEC3,
+        "vec4" => GL::FLOAT_VEC4,
+        "mat4" => GL::FLOAT_MAT4,
+        "sampler2D" => GL::SAMPLER_2D,
+        _ => 0,
+    }
+}
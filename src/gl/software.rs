@@ -0,0 +1,1442 @@
+use std::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    convert::TryInto,
+    mem,
+};
+
+use web_sys::WebGlRenderingContext;
+
+use super::{
+    device::{
+        ActiveUniform,
+        BufferId,
+        Capabilities,
+        FramebufferId,
+        GraphicsDevice,
+        ProgramId,
+        RenderbufferId,
+        ShaderId,
+        TexImage2D,
+        TextureId,
+        UniformLocationId,
+        VertexArrayId,
+    },
+    image::Image,
+};
+
+type GL = WebGlRenderingContext;
+
+/// Number of vertex attribute locations and texture units of the device.
+const MAX_VERTEX_ATTRIBS: usize = 16;
+const MAX_TEXTURE_UNITS: usize = 8;
+
+/// A `GraphicsDevice` rasterizing on the CPU, for comparing rendered frames to golden images
+/// without a GPU.
+///
+/// Shaders aren't compiled. Instead, programs written in the subset of GLSL the flat-colored and
+/// textured renderers use are recognized and run natively:
+///
+/// - the vertex shader multiplies `position` by the `mat4` uniforms named in its `main`, in the
+///   order they are named, after scaling it by `instance_size` and moving it by
+///   `instance_position` if it declares them,
+/// - the fragment shader sets `gl_FragColor` to a product of `vec4` constants and uniforms,
+///   `texture2D` samples and varyings, where the varying `_name` is the attribute `name` passed through
+///   unchanged by the vertex shader.
+///
+/// Draws with any other program, or with a primitive mode other than triangles, are skipped and
+/// counted by `skipped_draws`. Triangles are filled by pixel centers without antialiasing, and
+/// varyings are interpolated linearly in screen space, which is exact for the orthographic
+/// projections the renderers use.
+pub struct SoftwareDevice
+{
+    canvas: RefCell<Surface>,
+    shaders: RefCell<Vec<String>>,
+    programs: RefCell<Vec<Program>>,
+
+    /// Values of the uniforms by location.
+    uniforms: RefCell<Vec<Vec<f32>>>,
+
+    buffers: RefCell<Vec<Vec<u8>>>,
+    textures: RefCell<Vec<Texture>>,
+
+    /// The texture attached to each framebuffer.
+    framebuffers: RefCell<Vec<Option<TextureId>>>,
+
+    /// Number of renderbuffers created.
+    renderbuffers: Cell<u32>,
+
+    state: RefCell<State>,
+    skipped_draws: Cell<usize>,
+}
+
+impl SoftwareDevice
+{
+    /// A device drawing into a `width` by `height` canvas.
+    pub fn new(width: u32, height: u32) -> SoftwareDevice
+    {
+        SoftwareDevice {
+            canvas: RefCell::new(Surface::new(width as usize, height as usize, true)),
+            shaders: RefCell::new(Vec::new()),
+            programs: RefCell::new(Vec::new()),
+            uniforms: RefCell::new(Vec::new()),
+            buffers: RefCell::new(Vec::new()),
+            textures: RefCell::new(Vec::new()),
+            framebuffers: RefCell::new(Vec::new()),
+            renderbuffers: Cell::new(0),
+            state: RefCell::new(State::new(width as i32, height as i32)),
+            skipped_draws: Cell::new(0),
+        }
+    }
+
+    /// What has been drawn into the canvas.
+    pub fn image(&self) -> Image
+    {
+        self.canvas.borrow().image()
+    }
+
+    /// Number of draws skipped because their program or primitive mode isn't supported.
+    pub fn skipped_draws(&self) -> usize
+    {
+        self.skipped_draws.get()
+    }
+
+    fn skip_draw(&self)
+    {
+        self.skipped_draws.set(self.skipped_draws.get() + 1);
+    }
+
+    /// Draws the vertices `indices` of each instance in `instances` as primitives of `mode`.
+    fn draw(&self, mode: u32, indices: &[u32], instances: u32)
+    {
+        let state = self.state.borrow();
+
+        let programs = self.programs.borrow();
+        let program = match state.program {
+            Some(program) => &programs[program.0 as usize],
+            None => return,
+        };
+        let shading = match (&program.shading, triangles(mode, indices.len())) {
+            (Some(shading), Some(_)) => shading,
+            _ => {
+                self.skip_draw();
+                return;
+            }
+        };
+
+        //
+        // Resolve the inputs of the program.
+        //
+        let uniforms = self.uniforms.borrow();
+        let uniform = |name: &str| -> Option<&[f32]> {
+            program
+                .uniforms
+                .iter()
+                .find(|(other, _, _)| other == name)
+                .map(|(_, _, location)| uniforms[location.0 as usize].as_slice())
+        };
+
+        let matrices: Vec<&[f32]> = shading
+            .matrices
+            .iter()
+            .filter_map(|name| uniform(name).filter(|matrix| matrix.len() == 16))
+            .collect();
+        if matrices.len() != shading.matrices.len() {
+            self.skip_draw();
+            return;
+        }
+
+        let colors: Vec<[f32; 4]> = shading
+            .colors
+            .iter()
+            .filter_map(|name| uniform(name).and_then(|value| value.try_into().ok()))
+            .collect();
+        if colors.len() != shading.colors.len() {
+            self.skip_draw();
+            return;
+        }
+
+        let position = program.attribute_location("position");
+        let instance = if shading.instanced {
+            Some((
+                program.attribute_location("instance_position"),
+                program.attribute_location("instance_size"),
+            ))
+        } else {
+            None
+        };
+        let varyings: Vec<usize> = shading
+            .varyings
+            .iter()
+            .map(|name| program.attribute_location(name))
+            .collect();
+
+        // The render target is taken out before the textures are borrowed to be sampled.
+        let mut target = self.take_render_target(&state);
+        let (vx, vy, vw, vh) = state.viewport;
+
+        let textures = self.textures.borrow();
+        let samplers: Vec<Option<&Texture>> = shading
+            .samplers
+            .iter()
+            .map(|name| {
+                let unit = uniform(name).and_then(|value| value.first()).copied();
+                let unit = unit.unwrap_or(0.) as usize;
+                state
+                    .textures
+                    .get(unit)
+                    .copied()
+                    .flatten()
+                    .map(|texture| &textures[texture.0 as usize])
+            })
+            .collect();
+
+        let buffers = self.buffers.borrow();
+        let fetch = |location: usize, vertex: u32, instance: u32| {
+            state.attributes[location].fetch(&buffers, vertex, instance)
+        };
+
+        //
+        // Run the vertex shader on each vertex.
+        //
+        for instance_index in 0..instances {
+            let vertices: Vec<Vertex> = indices
+                .iter()
+                .map(|&index| {
+                    let mut p = fetch(position, index, instance_index);
+                    if let Some((offset, size)) = instance {
+                        let offset = fetch(offset, index, instance_index);
+                        let size = fetch(size, index, instance_index);
+                        p[0] = p[0] * size[0] + offset[0];
+                        p[1] = p[1] * size[1] + offset[1];
+                    }
+                    for matrix in matrices.iter().rev() {
+                        p = transform(matrix, p);
+                    }
+
+                    Vertex {
+                        x: vx as f32 + (p[0] / p[3] + 1.) / 2. * vw as f32,
+                        y: vy as f32 + (p[1] / p[3] + 1.) / 2. * vh as f32,
+                        w: p[3],
+                        varyings: varyings
+                            .iter()
+                            .map(|&location| fetch(location, index, instance_index))
+                            .collect(),
+                    }
+                })
+                .collect();
+
+            //
+            // Rasterize the triangles and shade their fragments.
+            //
+            for [a, b, c] in triangles(mode, vertices.len()).unwrap() {
+                let triangle = [&vertices[a], &vertices[b], &vertices[c]];
+                if triangle.iter().any(|vertex| vertex.w <= 0.) {
+                    continue;
+                }
+
+                let bounds = (
+                    vx.max(0),
+                    vy.max(0),
+                    (vx + vw).min(target.width as i32),
+                    (vy + vh).min(target.height as i32),
+                );
+                rasterize(triangle, bounds, |x, y, weights| {
+                    let varying = |i: usize| {
+                        let mut value = [0.; 4];
+                        for (vertex, weight) in triangle.iter().zip(weights.iter()) {
+                            for (component, v) in value.iter_mut().zip(vertex.varyings[i].iter()) {
+                                *component += weight * v;
+                            }
+                        }
+                        value
+                    };
+
+                    let mut color = [1.; 4];
+                    for factor in shading.color.iter() {
+                        let value = match factor {
+                            Factor::Constant(value) => *value,
+                            Factor::Uniform(i) => colors[*i],
+                            Factor::Varying(i) => varying(*i),
+                            Factor::Texture { sampler, texcoord } => match samplers[*sampler] {
+                                Some(texture) => {
+                                    let uv = varying(*texcoord);
+                                    texture.sample(uv[0], uv[1])
+                                }
+                                None => [0., 0., 0., 1.],
+                            },
+                        };
+                        for (component, v) in color.iter_mut().zip(value.iter()) {
+                            *component *= v;
+                        }
+                    }
+
+                    target.blend(x, y, color, state.blend);
+                });
+            }
+        }
+
+        drop(textures);
+        self.put_back_render_target(&state, target);
+    }
+
+    /// Takes the surface drawn into out of its texture, or the canvas, so that other textures can
+    /// be sampled while it is drawn into.
+    fn take_render_target(&self, state: &State) -> Surface
+    {
+        match self.render_texture(state) {
+            Some(texture) => mem::take(&mut self.textures.borrow_mut()[texture.0 as usize].surface),
+            None => mem::take(&mut *self.canvas.borrow_mut()),
+        }
+    }
+
+    fn put_back_render_target(&self, state: &State, surface: Surface)
+    {
+        match self.render_texture(state) {
+            Some(texture) => self.textures.borrow_mut()[texture.0 as usize].surface = surface,
+            None => *self.canvas.borrow_mut() = surface,
+        }
+    }
+
+    /// The texture attached to the bound framebuffer, if any.
+    fn render_texture(&self, state: &State) -> Option<TextureId>
+    {
+        state
+            .framebuffer
+            .and_then(|framebuffer| self.framebuffers.borrow()[framebuffer.0 as usize])
+    }
+
+    /// The texture bound to the active unit.
+    fn bound_texture(&self) -> Option<TextureId>
+    {
+        let state = self.state.borrow();
+        state.textures[state.texture_unit]
+    }
+
+    fn set_uniform(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.uniforms.borrow_mut()[location.0 as usize] = value.to_vec();
+    }
+}
+
+impl GraphicsDevice for SoftwareDevice
+{
+    fn name(&self) -> &'static str
+    {
+        "SOFTWARE"
+    }
+
+    fn capabilities(&self) -> Capabilities
+    {
+        Capabilities {
+            instancing: true,
+            npot_mipmaps: true,
+            ..Capabilities::none()
+        }
+    }
+
+    fn float_texture_format(&self) -> Option<(i32, u32, u32)>
+    {
+        None
+    }
+
+    //
+    // Shaders and programs.
+    //
+
+    fn compile_shader(&self, _: u32, source: &str) -> Result<ShaderId, String>
+    {
+        let mut shaders = self.shaders.borrow_mut();
+        shaders.push(source.to_string());
+        Ok(ShaderId(shaders.len() as u32 - 1))
+    }
+
+    fn link_program(
+        &self,
+        vertex_shader: ShaderId,
+        fragment_shader: ShaderId,
+        attributes: &[&str],
+    ) -> Result<ProgramId, String>
+    {
+        let shaders = self.shaders.borrow();
+        let vertex_source = &shaders[vertex_shader.0 as usize];
+        let fragment_source = &shaders[fragment_shader.0 as usize];
+
+        let mut uniforms = self.uniforms.borrow_mut();
+        let mut program_uniforms: Vec<(String, u32, UniformLocationId)> = Vec::new();
+        for (type_, name) in
+            declarations(vertex_source, "uniform").chain(declarations(fragment_source, "uniform"))
+        {
+            if !program_uniforms.iter().any(|(other, _, _)| *other == name) {
+                uniforms.push(Vec::new());
+                let location = UniformLocationId(uniforms.len() as u32 - 1);
+                program_uniforms.push((name, uniform_type(&type_), location));
+            }
+        }
+
+        // Attributes that aren't bound are placed after the bound ones.
+        let mut program_attributes: Vec<String> =
+            attributes.iter().map(|name| name.to_string()).collect();
+        for (_, name) in declarations(vertex_source, "attribute") {
+            if !program_attributes.contains(&name) {
+                program_attributes.push(name);
+            }
+        }
+        if program_attributes.len() > MAX_VERTEX_ATTRIBS {
+            return Err(format!(
+                "{} attributes, at most {} are supported",
+                program_attributes.len(),
+                MAX_VERTEX_ATTRIBS
+            ));
+        }
+
+        let mut programs = self.programs.borrow_mut();
+        programs.push(Program {
+            shading: Shading::new(vertex_source, fragment_source),
+            attributes: program_attributes,
+            uniforms: program_uniforms,
+        });
+        Ok(ProgramId(programs.len() as u32 - 1))
+    }
+
+    fn attribute_location(&self, program: ProgramId, name: &str) -> i32
+    {
+        let programs = self.programs.borrow();
+        programs[program.0 as usize]
+            .attributes
+            .iter()
+            .position(|other| other == name)
+            .map_or(-1, |location| location as i32)
+    }
+
+    fn active_uniforms(&self, program: ProgramId) -> Vec<ActiveUniform>
+    {
+        self.programs.borrow()[program.0 as usize]
+            .uniforms
+            .iter()
+            .map(|(name, type_, location)| ActiveUniform {
+                name: name.clone(),
+                type_: *type_,
+                location: *location,
+            })
+            .collect()
+    }
+
+    fn use_program(&self, program: Option<ProgramId>)
+    {
+        self.state.borrow_mut().program = program;
+    }
+
+    fn uniform1f(&self, location: UniformLocationId, value: f32)
+    {
+        self.set_uniform(location, &[value]);
+    }
+
+    fn uniform1i(&self, location: UniformLocationId, value: i32)
+    {
+        self.set_uniform(location, &[value as f32]);
+    }
+
+    fn uniform2fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.set_uniform(location, value);
+    }
+
+    fn uniform4fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.set_uniform(location, value);
+    }
+
+    fn uniform_matrix4fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.set_uniform(location, value);
+    }
+
+    //
+    // Buffers.
+    //
+
+    fn create_buffer(&self) -> Result<BufferId, String>
+    {
+        let mut buffers = self.buffers.borrow_mut();
+        buffers.push(Vec::new());
+        Ok(BufferId(buffers.len() as u32 - 1))
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferId>)
+    {
+        let mut state = self.state.borrow_mut();
+        match target {
+            GL::ARRAY_BUFFER => state.array_buffer = buffer,
+            GL::ELEMENT_ARRAY_BUFFER => state.element_array_buffer = buffer,
+            _ => {}
+        }
+    }
+
+    fn buffer_data(&self, target: u32, data: &[u8], _: u32)
+    {
+        if let Some(buffer) = self.state.borrow().buffer(target) {
+            self.buffers.borrow_mut()[buffer.0 as usize] = data.to_vec();
+        }
+    }
+
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8])
+    {
+        if let Some(buffer) = self.state.borrow().buffer(target) {
+            let mut buffers = self.buffers.borrow_mut();
+            let buffer = &mut buffers[buffer.0 as usize];
+
+            let offset = offset as usize;
+            if offset + data.len() <= buffer.len() {
+                buffer[offset..offset + data.len()].copy_from_slice(data);
+            }
+        }
+    }
+
+    //
+    // Vertex arrays.
+    //
+
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String>
+    {
+        Err(String::from("vertex arrays are not supported"))
+    }
+
+    fn bind_vertex_array(&self, _vertex_array: Option<VertexArrayId>) {}
+
+    //
+    // Vertex attributes.
+    //
+
+    fn enable_vertex_attrib_array(&self, location: u32)
+    {
+        self.state.borrow_mut().attributes[location as usize].enabled = true;
+    }
+
+    fn disable_vertex_attrib_array(&self, location: u32)
+    {
+        self.state.borrow_mut().attributes[location as usize].enabled = false;
+    }
+
+    fn vertex_attrib_pointer(
+        &self,
+        location: u32,
+        components: i32,
+        type_: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    )
+    {
+        let mut state = self.state.borrow_mut();
+        let buffer = state.array_buffer;
+        let attribute = &mut state.attributes[location as usize];
+
+        attribute.buffer = buffer;
+        attribute.components = components as usize;
+        attribute.type_ = type_;
+        attribute.normalized = normalized;
+        attribute.offset = offset as usize;
+        attribute.stride = match stride {
+            0 => components as usize * type_size(type_),
+            stride => stride as usize,
+        };
+    }
+
+    fn vertex_attrib(&self, location: u32, value: &[f32])
+    {
+        let mut constant = [0., 0., 0., 1.];
+        constant[..value.len()].copy_from_slice(value);
+        self.state.borrow_mut().attributes[location as usize].constant = constant;
+    }
+
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32)
+    {
+        self.state.borrow_mut().attributes[location as usize].divisor = divisor;
+    }
+
+    //
+    // Textures.
+    //
+
+    fn create_texture(&self) -> Result<TextureId, String>
+    {
+        let mut textures = self.textures.borrow_mut();
+        textures.push(Texture::default());
+        Ok(TextureId(textures.len() as u32 - 1))
+    }
+
+    fn active_texture(&self, unit: u32)
+    {
+        self.state.borrow_mut().texture_unit = (unit - GL::TEXTURE0) as usize;
+    }
+
+    fn bind_texture(&self, _: u32, texture: Option<TextureId>)
+    {
+        let mut state = self.state.borrow_mut();
+        let unit = state.texture_unit;
+        state.textures[unit] = texture;
+    }
+
+    fn tex_image_2d(&self, image: &TexImage2D, pixels: Option<&[u8]>) -> Result<(), String>
+    {
+        // Only the base level is sampled.
+        if image.level != 0 {
+            return Ok(());
+        }
+
+        let texture = self.bound_texture().ok_or("no texture bound")?;
+        let (width, height) = (image.width as usize, image.height as usize);
+        let mut surface = Surface::new(width, height, image.type_ == GL::UNSIGNED_BYTE);
+
+        if let Some(pixels) = pixels {
+            if (image.format, image.type_) != (GL::RGBA, GL::UNSIGNED_BYTE) {
+                return Err("only RGBA pixels with a byte per channel can be uploaded".to_string());
+            }
+            if pixels.len() < width * height * 4 {
+                return Err(format!(
+                    "{} bytes of pixels for a {}x{} texture",
+                    pixels.len(),
+                    width,
+                    height
+                ));
+            }
+
+            for (pixel, bytes) in surface.pixels.iter_mut().zip(pixels.chunks(4)) {
+                for (component, byte) in pixel.iter_mut().zip(bytes.iter()) {
+                    *component = *byte as f32 / 255.;
+                }
+            }
+        }
+
+        self.textures.borrow_mut()[texture.0 as usize].surface = surface;
+        Ok(())
+    }
+
+    fn tex_parameteri(&self, _: u32, name: u32, value: i32)
+    {
+        if let Some(texture) = self.bound_texture() {
+            if name == GL::TEXTURE_MAG_FILTER {
+                self.textures.borrow_mut()[texture.0 as usize].linear = value as u32 == GL::LINEAR;
+            }
+        }
+    }
+
+    fn generate_mipmap(&self, _: u32)
+    {
+        // Only the base level is sampled.
+    }
+
+    //
+    // Framebuffers.
+    //
+
+    fn create_framebuffer(&self) -> Result<FramebufferId, String>
+    {
+        let mut framebuffers = self.framebuffers.borrow_mut();
+        framebuffers.push(None);
+        Ok(FramebufferId(framebuffers.len() as u32 - 1))
+    }
+
+    fn bind_framebuffer(&self, _: u32, framebuffer: Option<FramebufferId>)
+    {
+        self.state.borrow_mut().framebuffer = framebuffer;
+    }
+
+    fn framebuffer_texture_2d(&self, _: u32, _: u32, _: u32, texture: Option<TextureId>, _: i32)
+    {
+        if let Some(framebuffer) = self.state.borrow().framebuffer {
+            self.framebuffers.borrow_mut()[framebuffer.0 as usize] = texture;
+        }
+    }
+
+    fn check_framebuffer_status(&self, _: u32) -> u32
+    {
+        let state = self.state.borrow();
+        let framebuffer = match state.framebuffer {
+            Some(framebuffer) => framebuffer,
+            None => return GL::FRAMEBUFFER_COMPLETE,
+        };
+
+        match self.framebuffers.borrow()[framebuffer.0 as usize] {
+            Some(texture) => match self.textures.borrow()[texture.0 as usize]
+                .surface
+                .pixels
+                .len()
+            {
+                0 => GL::FRAMEBUFFER_INCOMPLETE_ATTACHMENT,
+                _ => GL::FRAMEBUFFER_COMPLETE,
+            },
+            None => GL::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT,
+        }
+    }
+
+    /// Renderbuffers have no storage, there is no depth or stencil testing.
+    fn create_renderbuffer(&self) -> Result<RenderbufferId, String>
+    {
+        let renderbuffer = self.renderbuffers.get();
+        self.renderbuffers.set(renderbuffer + 1);
+        Ok(RenderbufferId(renderbuffer))
+    }
+
+    fn bind_renderbuffer(&self, _: u32, _: Option<RenderbufferId>) {}
+
+    fn renderbuffer_storage(&self, _: u32, _: u32, _: i32, _: i32) {}
+
+    fn framebuffer_renderbuffer(&self, _: u32, _: u32, _: u32, _: Option<RenderbufferId>) {}
+
+    //
+    // State and drawing.
+    //
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32)
+    {
+        self.state.borrow_mut().viewport = (x, y, width, height);
+    }
+
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32)
+    {
+        self.state.borrow_mut().clear_color = [r, g, b, a];
+    }
+
+    fn clear(&self, mask: u32)
+    {
+        if mask & GL::COLOR_BUFFER_BIT == 0 {
+            return;
+        }
+
+        let state = self.state.borrow();
+        let mut target = self.take_render_target(&state);
+        target.fill(state.clear_color);
+        self.put_back_render_target(&state, target);
+    }
+
+    fn enable(&self, capability: u32)
+    {
+        if capability == GL::BLEND {
+            self.state.borrow_mut().blend.enabled = true;
+        }
+    }
+
+    fn disable(&self, capability: u32)
+    {
+        if capability == GL::BLEND {
+            self.state.borrow_mut().blend.enabled = false;
+        }
+    }
+
+    fn blend_func(&self, source: u32, destination: u32)
+    {
+        let mut state = self.state.borrow_mut();
+        state.blend.source = source;
+        state.blend.destination = destination;
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32)
+    {
+        let indices: Vec<u32> = (first as u32..(first + count) as u32).collect();
+        self.draw(mode, &indices, 1);
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, type_: u32, offset: i32)
+    {
+        let indices = {
+            let state = self.state.borrow();
+            let buffers = self.buffers.borrow();
+            let buffer = match state.element_array_buffer {
+                Some(buffer) => &buffers[buffer.0 as usize],
+                None => return,
+            };
+
+            let size = type_size(type_);
+            let start = offset as usize;
+            let end = (start + count as usize * size).min(buffer.len());
+
+            buffer[start.min(end)..end]
+                .chunks(size)
+                .map(|bytes| read_integer(type_, bytes))
+                .collect::<Vec<u32>>()
+        };
+
+        self.draw(mode, &indices, 1);
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32)
+    {
+        let indices: Vec<u32> = (first as u32..(first + count) as u32).collect();
+        self.draw(mode, &indices, instances as u32);
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Shader emulation.
+// -------------------------------------------------------------------------------------------------
+
+struct Program
+{
+    /// The recognized shaders, or `None` if the program isn't supported.
+    shading: Option<Shading>,
+
+    /// Attributes by location.
+    attributes: Vec<String>,
+
+    /// Names, types and locations of the uniforms declared in both shaders.
+    uniforms: Vec<(String, u32, UniformLocationId)>,
+}
+
+impl Program
+{
+    /// The location of the attribute `name`, which the shading has checked is declared.
+    fn attribute_location(&self, name: &str) -> usize
+    {
+        self.attributes
+            .iter()
+            .position(|other| other == name)
+            .unwrap()
+    }
+}
+
+/// What the shaders of a supported program compute.
+struct Shading
+{
+    /// Matrices transforming `position`, outermost first.
+    matrices: Vec<String>,
+
+    /// Whether `position` is scaled by `instance_size` and moved by `instance_position`.
+    instanced: bool,
+
+    /// Attributes interpolated over the primitives.
+    varyings: Vec<String>,
+
+    /// Samplers of the `texture2D` calls.
+    samplers: Vec<String>,
+
+    /// The `vec4` uniforms among the factors.
+    colors: Vec<String>,
+
+    /// The factors multiplied into the color of the fragments.
+    color: Vec<Factor>,
+}
+
+enum Factor
+{
+    Constant([f32; 4]),
+
+    /// The `vec4` uniform at the index.
+    Uniform(usize),
+
+    /// The varying at the index.
+    Varying(usize),
+
+    /// A sample of the sampler at index `sampler`, at the varying at index `texcoord`.
+    Texture
+    {
+        sampler: usize,
+        texcoord: usize,
+    },
+}
+
+impl Shading
+{
+    /// Recognizes the shaders of a program, or `None` if they do more than is supported.
+    fn new(vertex_source: &str, fragment_source: &str) -> Option<Shading>
+    {
+        let attributes: Vec<String> = declarations(vertex_source, "attribute")
+            .map(|(_, name)| name)
+            .collect();
+        let vertex_main = main(vertex_source)?;
+
+        let mut shading = Shading {
+            matrices: words(vertex_main)
+                .filter(|word| word.ends_with("_matrix"))
+                .map(str::to_string)
+                .collect(),
+            instanced: attributes.iter().any(|name| name == "instance_position")
+                && attributes.iter().any(|name| name == "instance_size"),
+            varyings: Vec::new(),
+            samplers: Vec::new(),
+            colors: Vec::new(),
+            color: Vec::new(),
+        };
+        let color_uniforms: Vec<String> = declarations(fragment_source, "uniform")
+            .filter(|(type_, _)| type_ == "vec4")
+            .map(|(_, name)| name)
+            .collect();
+
+        //
+        // The fragment shader does nothing but setting the color to a product of factors.
+        //
+        let expression = main(fragment_source)?
+            .trim()
+            .strip_prefix('{')?
+            .strip_suffix('}')?
+            .trim()
+            .strip_prefix("gl_FragColor")?
+            .trim_start()
+            .strip_prefix('=')?
+            .strip_suffix(';')?;
+        if expression.contains(';') {
+            return None;
+        }
+
+        for factor in split_top_level(expression, '*') {
+            let factor = factor.trim();
+
+            let factor = if let Some(arguments) = call(factor, "vec4") {
+                let values: Vec<f32> = arguments
+                    .iter()
+                    .map(|argument| argument.parse().ok())
+                    .collect::<Option<_>>()?;
+                match values.as_slice() {
+                    [v] => Factor::Constant([*v; 4]),
+                    [r, g, b, a] => Factor::Constant([*r, *g, *b, *a]),
+                    _ => return None,
+                }
+            } else if let Some(arguments) = call(factor, "texture2D") {
+                match arguments.as_slice() {
+                    [sampler, texcoord] => {
+                        shading.samplers.push(sampler.to_string());
+                        Factor::Texture {
+                            sampler: shading.samplers.len() - 1,
+                            texcoord: shading.varying(texcoord, vertex_main)?,
+                        }
+                    }
+                    _ => return None,
+                }
+            } else if color_uniforms.iter().any(|name| name == factor) {
+                shading.colors.push(factor.to_string());
+                Factor::Uniform(shading.colors.len() - 1)
+            } else {
+                Factor::Varying(shading.varying(factor, vertex_main)?)
+            };
+
+            shading.color.push(factor);
+        }
+
+        //
+        // Any other attribute would be used by the vertex shader in ways that aren't supported.
+        //
+        let supported = |name: &String| {
+            *name == "position"
+                || (shading.instanced && (name == "instance_position" || name == "instance_size"))
+                || shading.varyings.contains(name)
+        };
+        if !attributes.iter().all(supported) || !attributes.iter().any(|name| name == "position") {
+            return None;
+        }
+
+        Some(shading)
+    }
+
+    /// The index of the varying `name`, which must be of the form `_attribute` and be assigned
+    /// `attribute` in `vertex_main`.
+    fn varying(&mut self, name: &str, vertex_main: &str) -> Option<usize>
+    {
+        let attribute = name.strip_prefix('_')?;
+        let assignment = format!("{} = {};", name, attribute);
+        if !vertex_main.contains(&assignment) {
+            return None;
+        }
+
+        if let Some(i) = self.varyings.iter().position(|other| other == attribute) {
+            return Some(i);
+        }
+        self.varyings.push(attribute.to_string());
+        Some(self.varyings.len() - 1)
+    }
+}
+
+/// The body of the `main` function of `source`.
+fn main(source: &str) -> Option<&str>
+{
+    source.split("void main()").nth(1)
+}
+
+/// The identifiers and numbers of `source`.
+fn words(source: &str) -> impl Iterator<Item = &str>
+{
+    source
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .filter(|word| !word.is_empty())
+}
+
+/// The arguments of `expression` if it is a call of `function`.
+fn call<'a>(expression: &'a str, function: &str) -> Option<Vec<&'a str>>
+{
+    let arguments = expression
+        .strip_prefix(function)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+
+    Some(
+        split_top_level(arguments, ',')
+            .into_iter()
+            .map(str::trim)
+            .collect(),
+    )
+}
+
+/// Splits `expression` at each `separator` outside of parentheses.
+fn split_top_level(expression: &str, separator: char) -> Vec<&str>
+{
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in expression.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&expression[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&expression[start..]);
+
+    parts
+}
+
+/// The types and names declared with `qualifier` in `source`, with the size of arrays removed.
+fn declarations<'a>(
+    source: &'a str,
+    qualifier: &'a str,
+) -> impl Iterator<Item = (String, String)> + 'a
+{
+    source.lines().filter_map(move |line| {
+        let words: Vec<&str> = line
+            .trim()
+            .trim_end_matches(';')
+            .split_whitespace()
+            .collect();
+
+        match words.as_slice() {
+            [first, .., type_, name] if *first == qualifier => {
+                let name = name.split('[').next().unwrap_or(name);
+                Some((type_.to_string(), name.to_string()))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// The value `getActiveUniform` reports for the GLSL type `name`.
+fn uniform_type(name: &str) -> u32
+{
+    match name {
+        "int" => GL::INT,
+        "float" => GL::FLOAT,
+        "vec2" => GL::FLOAT_VEC2,
+        "vec3" => GL::FLOAT_VEC3,
+        "vec4" => GL::FLOAT_VEC4,
+        "mat4" => GL::FLOAT_MAT4,
+        "sampler2D" => GL::SAMPLER_2D,
+        _ => 0,
+    }
+}
+
+/// Multiplies the column-major `matrix` with `v`.
+fn transform(matrix: &[f32], v: [f32; 4]) -> [f32; 4]
+{
+    let mut result = [0.; 4];
+    for (row, value) in result.iter_mut().enumerate() {
+        *value = (0..4)
+            .map(|column| matrix[column * 4 + row] * v[column])
+            .sum();
+    }
+    result
+}
+
+// -------------------------------------------------------------------------------------------------
+// Rasterization.
+// -------------------------------------------------------------------------------------------------
+
+struct Vertex
+{
+    /// Position in pixels of the render target.
+    x: f32,
+    y: f32,
+
+    w: f32,
+    varyings: Vec<[f32; 4]>,
+}
+
+/// The vertex indices of the triangles of `count` vertices drawn with `mode`, or `None` if `mode`
+/// doesn't draw triangles.
+fn triangles(mode: u32, count: usize) -> Option<Vec<[usize; 3]>>
+{
+    match mode {
+        GL::TRIANGLES => Some(
+            (0..count / 3)
+                .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+                .collect(),
+        ),
+        GL::TRIANGLE_STRIP => Some(
+            (2..count)
+                .map(|i| match i % 2 {
+                    0 => [i - 2, i - 1, i],
+                    _ => [i - 1, i - 2, i],
+                })
+                .collect(),
+        ),
+        GL::TRIANGLE_FAN => Some((2..count).map(|i| [0, i - 1, i]).collect()),
+        _ => None,
+    }
+}
+
+/// Calls `fragment` with the position and the barycentric weights of each pixel of `bounds`,
+/// given as left, bottom, right and top, whose center is covered by `triangle`.
+///
+/// Pixel centers on an edge are covered by only one of the triangles sharing it, so that blended
+/// triangles of a mesh don't overlap.
+fn rasterize<F: FnMut(usize, usize, [f32; 3])>(
+    triangle: [&Vertex; 3],
+    bounds: (i32, i32, i32, i32),
+    mut fragment: F,
+)
+{
+    let [a, b, c] = triangle;
+    let edge = |from: &Vertex, to: &Vertex, x: f32, y: f32| {
+        (to.x - from.x) * (y - from.y) - (to.y - from.y) * (x - from.x)
+    };
+
+    let area = edge(a, b, c.x, c.y);
+    if area == 0. {
+        return;
+    }
+
+    // The owner of an edge is the triangle to its left when walked counterclockwise.
+    let owns = |from: &Vertex, to: &Vertex| {
+        let (from, to) = if area > 0. { (from, to) } else { (to, from) };
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        dy < 0. || (dy == 0. && dx < 0.)
+    };
+    let covers =
+        |weight: f32, from: &Vertex, to: &Vertex| weight > 0. || (weight == 0. && owns(from, to));
+
+    let (left, bottom, right, top) = bounds;
+    let left = left.max(a.x.min(b.x).min(c.x).floor() as i32);
+    let right = right.min(a.x.max(b.x).max(c.x).ceil() as i32 + 1);
+    let bottom = bottom.max(a.y.min(b.y).min(c.y).floor() as i32);
+    let top = top.min(a.y.max(b.y).max(c.y).ceil() as i32 + 1);
+
+    for y in bottom..top {
+        for x in left..right {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [
+                edge(b, c, px, py) / area,
+                edge(c, a, px, py) / area,
+                edge(a, b, px, py) / area,
+            ];
+
+            if covers(weights[0], b, c) && covers(weights[1], c, a) && covers(weights[2], a, b) {
+                fragment(x as usize, y as usize, weights);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Blend
+{
+    enabled: bool,
+    source: u32,
+    destination: u32,
+}
+
+/// The pixels of a texture or of the canvas, with rows from the bottom.
+#[derive(Default)]
+struct Surface
+{
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 4]>,
+
+    /// Whether the pixels are stored with a byte per channel, and rounded accordingly.
+    bytes: bool,
+}
+
+impl Surface
+{
+    fn new(width: usize, height: usize, bytes: bool) -> Surface
+    {
+        Surface {
+            width,
+            height,
+            pixels: vec![[0.; 4]; width * height],
+            bytes,
+        }
+    }
+
+    fn fill(&mut self, color: [f32; 4])
+    {
+        let color = self.store(color);
+        for pixel in self.pixels.iter_mut() {
+            *pixel = color;
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: [f32; 4], blend: Blend)
+    {
+        let i = y * self.width + x;
+        let pixel = self.pixels[i];
+
+        let color = match blend.enabled {
+            true => {
+                let source = blend_factor(blend.source, color, pixel);
+                let destination = blend_factor(blend.destination, color, pixel);
+
+                let mut blended = [0.; 4];
+                for c in 0..4 {
+                    blended[c] = color[c] * source[c] + pixel[c] * destination[c];
+                }
+                blended
+            }
+            false => color,
+        };
+
+        self.pixels[i] = self.store(color);
+    }
+
+    fn store(&self, color: [f32; 4]) -> [f32; 4]
+    {
+        match self.bytes {
+            true => color.map(|component| (component.clamp(0., 1.) * 255.).round() / 255.),
+            false => color,
+        }
+    }
+
+    /// The color at the texture coordinates `u` and `v`, clamped to the edges.
+    fn sample(&self, u: f32, v: f32, linear: bool) -> [f32; 4]
+    {
+        if self.pixels.is_empty() {
+            return [0., 0., 0., 1.];
+        }
+
+        let texel = |x: i32, y: i32| {
+            let x = x.clamp(0, self.width as i32 - 1) as usize;
+            let y = y.clamp(0, self.height as i32 - 1) as usize;
+            self.pixels[y * self.width + x]
+        };
+
+        let (x, y) = (u * self.width as f32 - 0.5, v * self.height as f32 - 0.5);
+        if !linear {
+            return texel(x.round() as i32, y.round() as i32);
+        }
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+
+        let mut color = [0.; 4];
+        for (texel, weight) in [
+            (texel(x0, y0), (1. - fx) * (1. - fy)),
+            (texel(x0 + 1, y0), fx * (1. - fy)),
+            (texel(x0, y0 + 1), (1. - fx) * fy),
+            (texel(x0 + 1, y0 + 1), fx * fy),
+        ] {
+            for i in 0..4 {
+                color[i] += texel[i] * weight;
+            }
+        }
+        color
+    }
+
+    /// The pixels as an image, with the last row at the top.
+    fn image(&self) -> Image
+    {
+        let pixels = (0..self.height)
+            .rev()
+            .flat_map(|y| self.pixels[y * self.width..(y + 1) * self.width].iter())
+            .flat_map(|pixel| pixel.map(|component| (component.clamp(0., 1.) * 255.).round() as u8))
+            .collect();
+
+        Image::new(self.width as u32, self.height as u32, pixels).unwrap()
+    }
+}
+
+fn blend_factor(factor: u32, source: [f32; 4], destination: [f32; 4]) -> [f32; 4]
+{
+    match factor {
+        GL::ZERO => [0.; 4],
+        GL::ONE => [1.; 4],
+        GL::SRC_COLOR => source,
+        GL::ONE_MINUS_SRC_COLOR => source.map(|c| 1. - c),
+        GL::DST_COLOR => destination,
+        GL::ONE_MINUS_DST_COLOR => destination.map(|c| 1. - c),
+        GL::SRC_ALPHA => [source[3]; 4],
+        GL::ONE_MINUS_SRC_ALPHA => [1. - source[3]; 4],
+        GL::DST_ALPHA => [destination[3]; 4],
+        GL::ONE_MINUS_DST_ALPHA => [1. - destination[3]; 4],
+        _ => [1.; 4],
+    }
+}
+
+#[derive(Default)]
+struct Texture
+{
+    surface: Surface,
+
+    /// Whether the texture is filtered linearly, as set by its magnification filter.
+    linear: bool,
+}
+
+impl Texture
+{
+    fn sample(&self, u: f32, v: f32) -> [f32; 4]
+    {
+        self.surface.sample(u, v, self.linear)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// State.
+// -------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+struct Attribute
+{
+    enabled: bool,
+    buffer: Option<BufferId>,
+    components: usize,
+    type_: u32,
+    normalized: bool,
+    stride: usize,
+    offset: usize,
+    divisor: u32,
+
+    /// The value of the attribute while the array is disabled.
+    constant: [f32; 4],
+}
+
+impl Attribute
+{
+    /// The value of the attribute for `vertex` of `instance`.
+    fn fetch(&self, buffers: &[Vec<u8>], vertex: u32, instance: u32) -> [f32; 4]
+    {
+        let buffer = match (self.enabled, self.buffer) {
+            (true, Some(buffer)) => &buffers[buffer.0 as usize],
+            _ => return self.constant,
+        };
+
+        let index = match self.divisor {
+            0 => vertex,
+            divisor => instance / divisor,
+        } as usize;
+        let start = self.offset + index * self.stride;
+        let size = type_size(self.type_);
+
+        // Reads out of the buffer give zero, like robust buffer access does.
+        let mut value = [0., 0., 0., 1.];
+        for (i, component) in value.iter_mut().take(self.components).enumerate() {
+            *component = match buffer.get(start + i * size..start + (i + 1) * size) {
+                Some(bytes) => read_component(self.type_, self.normalized, bytes),
+                None => 0.,
+            };
+        }
+        value
+    }
+}
+
+struct State
+{
+    program: Option<ProgramId>,
+    array_buffer: Option<BufferId>,
+    element_array_buffer: Option<BufferId>,
+    attributes: [Attribute; MAX_VERTEX_ATTRIBS],
+    texture_unit: usize,
+    textures: [Option<TextureId>; MAX_TEXTURE_UNITS],
+    framebuffer: Option<FramebufferId>,
+    viewport: (i32, i32, i32, i32),
+    clear_color: [f32; 4],
+    blend: Blend,
+}
+
+impl State
+{
+    fn new(width: i32, height: i32) -> State
+    {
+        State {
+            program: None,
+            array_buffer: None,
+            element_array_buffer: None,
+            attributes: [Attribute {
+                enabled: false,
+                buffer: None,
+                components: 4,
+                type_: GL::FLOAT,
+                normalized: false,
+                stride: 16,
+                offset: 0,
+                divisor: 0,
+                constant: [0., 0., 0., 1.],
+            }; MAX_VERTEX_ATTRIBS],
+            texture_unit: 0,
+            textures: [None; MAX_TEXTURE_UNITS],
+            framebuffer: None,
+            viewport: (0, 0, width, height),
+            clear_color: [0., 0., 0., 0.],
+            blend: Blend {
+                enabled: false,
+                source: GL::ONE,
+                destination: GL::ZERO,
+            },
+        }
+    }
+
+    /// The buffer bound to `target`.
+    fn buffer(&self, target: u32) -> Option<BufferId>
+    {
+        match target {
+            GL::ARRAY_BUFFER => self.array_buffer,
+            GL::ELEMENT_ARRAY_BUFFER => self.element_array_buffer,
+            _ => None,
+        }
+    }
+}
+
+fn type_size(type_: u32) -> usize
+{
+    match type_ {
+        GL::BYTE | GL::UNSIGNED_BYTE => 1,
+        GL::SHORT | GL::UNSIGNED_SHORT => 2,
+        _ => 4,
+    }
+}
+
+fn read_component(type_: u32, normalized: bool, bytes: &[u8]) -> f32
+{
+    let (value, max) = match type_ {
+        GL::FLOAT => return f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        GL::BYTE => (bytes[0] as i8 as f32, i8::MAX as f32),
+        GL::UNSIGNED_BYTE => (bytes[0] as f32, u8::MAX as f32),
+        GL::SHORT => (
+            i16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+            i16::MAX as f32,
+        ),
+        GL::UNSIGNED_SHORT => (
+            u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+            u16::MAX as f32,
+        ),
+        _ => return 0.,
+    };
+
+    match normalized {
+        true => (value / max).max(-1.),
+        false => value,
+    }
+}
+
+fn read_integer(type_: u32, bytes: &[u8]) -> u32
+{
+    match type_ {
+        GL::UNSIGNED_BYTE => bytes[0] as u32,
+        GL::UNSIGNED_SHORT => u16::from_le_bytes([bytes[0], bytes[1]]) as u32,
+        _ => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+    }
+}
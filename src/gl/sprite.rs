@@ -0,0 +1,258 @@
+use web_sys::WebGlRenderingContext;
+
+use super::{
+    atlas::{
+        AtlasRegion,
+        SpriteAtlas,
+    },
+    buffer::{
+        ArrayBuffer,
+        BufferUsage,
+        VertexAttributeType,
+        VertexLayout,
+    },
+    device::{
+        GraphicsDevice,
+        ShaderId,
+    },
+    program::{
+        Mat4,
+        Program,
+        Sampler2D,
+        Uniform,
+    },
+    texture::Texture2D,
+};
+use crate::matrix::{
+    Matrix4x4,
+    OrthographicProjection,
+};
+
+type GL = WebGlRenderingContext;
+
+/// Draws quads of a texture tinted with a color in pixels, with the origin at the top left corner
+/// of the render target.
+///
+/// Quads are collected into a batch with `quad`, and drawn at once with `render`.
+pub struct QuadBatch
+{
+    program: Program,
+    projection_matrix: Uniform<Mat4>,
+    sampler: Uniform<Sampler2D>,
+    vertex_layout: VertexLayout,
+    vertex_buffer: ArrayBuffer<f32>,
+    vertices: Vec<f32>,
+}
+
+impl QuadBatch
+{
+    pub fn new(gl: &dyn GraphicsDevice) -> Result<QuadBatch, String>
+    {
+        let vertex_layout = VertexLayout::new()
+            .attribute("position", 2, VertexAttributeType::Float)
+            .attribute("texcoord", 2, VertexAttributeType::Float)
+            .attribute("color", 4, VertexAttributeType::Float);
+
+        let program = Program::new(
+            gl,
+            &vertex_shader(gl)?,
+            &fragment_shader(gl)?,
+            &[&vertex_layout],
+        )?;
+
+        Ok(QuadBatch {
+            projection_matrix: program.uniform("projection_matrix")?,
+            sampler: program.uniform("image")?,
+            program,
+            vertex_layout,
+            vertex_buffer: ArrayBuffer::new(gl)?,
+            vertices: Vec::new(),
+        })
+    }
+
+    /// Adds a quad with its top left corner at `position`, showing the texture between the
+    /// texture coordinates of its top left and bottom right corners.
+    pub fn quad(
+        &mut self,
+        position: [f32; 2],
+        size: [f32; 2],
+        texcoords: ([f32; 2], [f32; 2]),
+        color: [f32; 4],
+    )
+    {
+        let (x0, y0) = (position[0], position[1]);
+        let (x1, y1) = (x0 + size[0], y0 + size[1]);
+        let ([u0, v0], [u1, v1]) = texcoords;
+
+        for (x, y, u, v) in [
+            (x0, y0, u0, v0),
+            (x1, y0, u1, v0),
+            (x1, y1, u1, v1),
+            (x0, y0, u0, v0),
+            (x1, y1, u1, v1),
+            (x0, y1, u0, v1),
+        ]
+        .iter()
+        {
+            self.vertices.extend_from_slice(&[*x, *y, *u, *v]);
+            self.vertices.extend_from_slice(&color);
+        }
+    }
+
+    /// Draws every quad added since the last call with `texture` into a render target of `width`
+    /// by `height` pixels.
+    pub fn render(&mut self, gl: &dyn GraphicsDevice, texture: &Texture2D, width: u32, height: u32)
+    {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        self.vertex_buffer
+            .update(gl, BufferUsage::DynamicDraw, &self.vertices);
+        let count = self.vertices.len() as i32 / (*self.vertex_layout.stride() / 4);
+        self.vertices.clear();
+
+        self.program.use_program(gl);
+
+        let matrix = OrthographicProjection::default()
+            .abscissa(0. ..width as f32)
+            .ordinate(height as f32..0.)
+            .into_array();
+        self.projection_matrix.set(gl, &matrix);
+        self.sampler.set(gl, &0);
+
+        self.program
+            .enable_vertex_layout(gl, &self.vertex_layout, &self.vertex_buffer, 0);
+        texture.bind(gl);
+
+        gl.draw_arrays(GL::TRIANGLES, 0, count);
+
+        texture.unbind(gl);
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
+    }
+}
+
+/// Draws the sprites of a `SpriteAtlas` in pixels, with the origin at the top left corner of the
+/// render target.
+///
+/// Sprites are collected into a batch with `sprite`, and drawn at once with `render`.
+pub struct SpriteRenderer
+{
+    atlas: SpriteAtlas,
+    quads: QuadBatch,
+}
+
+impl SpriteRenderer
+{
+    pub fn new(gl: &dyn GraphicsDevice, atlas: SpriteAtlas) -> Result<SpriteRenderer, String>
+    {
+        Ok(SpriteRenderer {
+            atlas,
+            quads: QuadBatch::new(gl)?,
+        })
+    }
+
+    /// Adds the sprite of `region` with its top left corner at `position`, `scale` times its size
+    /// in pixels and tinted with `color`.
+    pub fn sprite(&mut self, region: &AtlasRegion, position: [f32; 2], scale: f32, color: [f32; 4])
+    {
+        let size = [region.width as f32 * scale, region.height as f32 * scale];
+        self.quads.quad(position, size, region.texcoords(), color);
+    }
+
+    /// Draws every sprite added since the last call into a render target of `width` by `height`
+    /// pixels.
+    pub fn render(&mut self, gl: &dyn GraphicsDevice, width: u32, height: u32)
+    {
+        self.quads.render(gl, self.atlas.texture(), width, height);
+    }
+}
+
+fn vertex_shader(gl: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    super::compile_vertex_shader(
+        gl,
+        r#"
+        attribute vec2 position;
+        attribute vec2 texcoord;
+        attribute vec4 color;
+
+        uniform mat4 projection_matrix;
+
+        varying vec2 _texcoord;
+        varying vec4 _color;
+
+        void main()
+        {
+            _texcoord = texcoord;
+            _color = color;
+
+            gl_Position = projection_matrix * vec4(position, 0.0, 1.0);
+        }
+        "#,
+    )
+}
+
+fn fragment_shader(gl: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    super::compile_fragment_shader(
+        gl,
+        r#"
+        precision mediump float;
+
+        uniform sampler2D image;
+
+        varying vec2 _texcoord;
+        varying vec4 _color;
+
+        void main()
+        {
+            gl_FragColor = _color * texture2D(image, _texcoord);
+        }
+        "#,
+    )
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::gl::{
+        software::SoftwareDevice,
+        texture::{
+            TextureMagnificationFilter,
+            TextureMinificationFilter,
+        },
+    };
+
+    #[test]
+    fn sprites_are_drawn_from_their_region_of_the_atlas()
+    {
+        let device = SoftwareDevice::new(16, 16);
+        let atlas = SpriteAtlas::from_png(
+            &device,
+            include_bytes!("../../assets/hud.png"),
+            "ship 0 0 8 7",
+            TextureMinificationFilter::Nearest,
+            TextureMagnificationFilter::Nearest,
+        )
+        .unwrap();
+        let region = atlas.region("ship").unwrap();
+
+        let mut renderer = SpriteRenderer::new(&device, atlas).unwrap();
+        renderer.sprite(&region, [4., 2.], 1., [1., 0., 0., 1.]);
+        renderer.render(&device, 16, 16);
+
+        assert_eq!(device.skipped_draws(), 0);
+
+        // The nose of the ship is the middle two pixels of the top row of the sprite, and its
+        // tail spans the width of the sprite.
+        let image = device.image();
+        assert_eq!(image.pixel(4 + 3, 2), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(4 + 4, 2), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(4 + 2, 2)[0], 0);
+        assert_eq!(image.pixel(4, 2 + 6), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(4 + 3, 2 + 6)[0], 0);
+    }
+}
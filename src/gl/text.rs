@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use web_sys::WebGlRenderingContext;
+
+use super::{
+    device::GraphicsDevice,
+    sprite::QuadBatch,
+    texture::{
+        Texture2D,
+        TextureMagnificationFilter,
+        TextureMinificationFilter,
+        TextureWrappingFunction,
+    },
+};
+
+type GL = WebGlRenderingContext;
+
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+
+/// Glyphs of the font, `#` marks a filled pixel.
+#[rustfmt::skip]
+const GLYPHS: &[(char, [&str; GLYPH_HEIGHT])] = &[
+    // A solid block, also used to draw rectangles.
+    ('\u{2588}', ["#####", "#####", "#####", "#####", "#####", "#####", "#####"]),
+    (' ', [".....", ".....", ".....", ".....", ".....", ".....", "....."]),
+    ('0', [".###.", "#...#", "#..##", "#.#.#", "##..#", "#...#", ".###."]),
+    ('1', ["..#..", ".##..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('2', [".###.", "#...#", "....#", "...#.", "..#..", ".#...", "#####"]),
+    ('3', ["####.", "....#", "....#", ".###.", "....#", "....#", "####."]),
+    ('4', ["...#.", "..##.", ".#.#.", "#..#.", "#####", "...#.", "...#."]),
+    ('5', ["#####", "#....", "####.", "....#", "....#", "#...#", ".###."]),
+    ('6', [".###.", "#....", "#....", "####.", "#...#", "#...#", ".###."]),
+    ('7', ["#####", "....#", "...#.", "..#..", ".#...", ".#...", ".#..."]),
+    ('8', [".###.", "#...#", "#...#", ".###.", "#...#", "#...#", ".###."]),
+    ('9', [".###.", "#...#", "#...#", ".####", "....#", "....#", ".###."]),
+    ('A', [".###.", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+    ('B', ["####.", "#...#", "#...#", "####.", "#...#", "#...#", "####."]),
+    ('C', [".###.", "#...#", "#....", "#....", "#....", "#...#", ".###."]),
+    ('D', ["####.", "#...#", "#...#", "#...#", "#...#", "#...#", "####."]),
+    ('E', ["#####", "#....", "#....", "####.", "#....", "#....", "#####"]),
+    ('F', ["#####", "#....", "#....", "####.", "#....", "#....", "#...."]),
+    ('G', [".###.", "#...#", "#....", "#.###", "#...#", "#...#", ".####"]),
+    ('H', ["#...#", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+    ('I', [".###.", "..#..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('J', ["..###", "...#.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('K', ["#...#", "#..#.", "#.#..", "##...", "#.#..", "#..#.", "#...#"]),
+    ('L', ["#....", "#....", "#....", "#....", "#....", "#....", "#####"]),
+    ('M', ["#...#", "##.##", "#.#.#", "#.#.#", "#...#", "#...#", "#...#"]),
+    ('N', ["#...#", "#...#", "##..#", "#.#.#", "#..##", "#...#", "#...#"]),
+    ('O', [".###.", "#...#", "#...#", "#...#", "#...#", "#...#", ".###."]),
+    ('P', ["####.", "#...#", "#...#", "####.", "#....", "#....", "#...."]),
+    ('Q', [".###.", "#...#", "#...#", "#...#", "#.#.#", "#..#.", ".##.#"]),
+    ('R', ["####.", "#...#", "#...#", "####.", "#.#..", "#..#.", "#...#"]),
+    ('S', [".####", "#....", "#....", ".###.", "....#", "....#", "####."]),
+    ('T', ["#####", "..#..", "..#..", "..#..", "..#..", "..#..", "..#.."]),
+    ('U', ["#...#", "#...#", "#...#", "#...#", "#...#", "#...#", ".###."]),
+    ('V', ["#...#", "#...#", "#...#", "#...#", "#...#", ".#.#.", "..#.."]),
+    ('W', ["#...#", "#...#", "#...#", "#.#.#", "#.#.#", "#.#.#", ".#.#."]),
+    ('X', ["#...#", "#...#", ".#.#.", "..#..", ".#.#.", "#...#", "#...#"]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#..", "..#.."]),
+    ('Z', ["#####", "....#", "...#.", "..#..", ".#...", "#....", "#####"]),
+    (':', [".....", "..#..", "..#..", ".....", "..#..", "..#..", "....."]),
+    ('.', [".....", ".....", ".....", ".....", ".....", ".##..", ".##.."]),
+    ('-', [".....", ".....", ".....", "#####", ".....", ".....", "....."]),
+    ('/', ["....#", "....#", "...#.", "..#..", ".#...", "#....", "#...."]),
+    ('%', ["##..#", "##..#", "...#.", "..#..", ".#...", "#..##", "#..##"]),
+    ('?', [".###.", "#...#", "....#", "...#.", "..#..", ".....", "..#.."]),
+];
+
+/// The character drawn for rectangles.
+const BLOCK: char = '\u{2588}';
+
+/// The character drawn for characters missing from the font.
+const MISSING: char = '?';
+
+/// A monospaced 5 by 7 pixel font rasterized into a texture, one glyph after the other with a
+/// pixel of padding around each one.
+pub struct BitmapFont
+{
+    texture: Texture2D,
+    glyphs: HashMap<char, usize>,
+}
+
+impl BitmapFont
+{
+    pub fn new(gl: &dyn GraphicsDevice) -> Result<BitmapFont, String>
+    {
+        let (width, height) = BitmapFont::texture_size();
+        let mut pixels = vec![0u8; width * height * 4];
+
+        for (i, (_, rows)) in GLYPHS.iter().enumerate() {
+            for (y, row) in rows.iter().enumerate() {
+                for (x, pixel) in row.chars().enumerate() {
+                    if pixel == '#' {
+                        let offset = ((y + 1) * width + i * (GLYPH_WIDTH + 2) + x + 1) * 4;
+                        pixels[offset..offset + 4].copy_from_slice(&[255, 255, 255, 255]);
+                    }
+                }
+            }
+        }
+
+        let mut texture = Texture2D::new(gl)?;
+        texture
+            .specification()
+            .level(0)
+            .internal_format(GL::RGBA as i32)
+            .width(width as i32)
+            .height(height as i32)
+            .border(0)
+            .format(GL::RGBA)
+            .type_(GL::UNSIGNED_BYTE)
+            .min_filter(TextureMinificationFilter::Nearest)
+            .mag_filter(TextureMagnificationFilter::Nearest)
+            .wrap_s(TextureWrappingFunction::ClampToEdge)
+            .wrap_t(TextureWrappingFunction::ClampToEdge)
+            .pixels(&pixels)
+            .update(gl)?;
+
+        let glyphs = GLYPHS
+            .iter()
+            .enumerate()
+            .map(|(i, (c, _))| (*c, i))
+            .collect();
+
+        Ok(BitmapFont { texture, glyphs })
+    }
+
+    fn texture_size() -> (usize, usize)
+    {
+        (GLYPHS.len() * (GLYPH_WIDTH + 2), GLYPH_HEIGHT + 2)
+    }
+
+    /// The texture coordinates of the top left and bottom right corners of the glyph of `c`.
+    fn texcoords(&self, c: char) -> ([f32; 2], [f32; 2])
+    {
+        let i = self
+            .glyphs
+            .get(&c.to_ascii_uppercase())
+            .or_else(|| self.glyphs.get(&MISSING))
+            .copied()
+            .unwrap_or(0);
+
+        let (width, height) = BitmapFont::texture_size();
+        let (width, height) = (width as f32, height as f32);
+
+        let x = (i * (GLYPH_WIDTH + 2) + 1) as f32;
+        (
+            [x / width, 1. / height],
+            [
+                (x + GLYPH_WIDTH as f32) / width,
+                (1. + GLYPH_HEIGHT as f32) / height,
+            ],
+        )
+    }
+}
+
+/// Draws text and rectangles in pixels, with the origin at the top left corner of the render
+/// target.
+///
+/// Text and rectangles are collected into a batch with `text` and `rect`, and drawn at once with
+/// `render`.
+pub struct TextRenderer
+{
+    font: BitmapFont,
+    quads: QuadBatch,
+}
+
+impl TextRenderer
+{
+    pub fn new(gl: &dyn GraphicsDevice) -> Result<TextRenderer, String>
+    {
+        Ok(TextRenderer {
+            font: BitmapFont::new(gl)?,
+            quads: QuadBatch::new(gl)?,
+        })
+    }
+
+    /// The width and height in pixels of `text` drawn with glyphs `scale` times their size.
+    pub fn measure(text: &str, scale: f32) -> [f32; 2]
+    {
+        let count = text.chars().count() as f32;
+        let advance = (GLYPH_WIDTH + 1) as f32 * scale;
+        [
+            (count * advance - scale).max(0.),
+            GLYPH_HEIGHT as f32 * scale,
+        ]
+    }
+
+    /// Adds `text` with its top left corner at `position`, with glyphs `scale` times their size.
+    pub fn text(&mut self, text: &str, position: [f32; 2], scale: f32, color: [f32; 4])
+    {
+        let advance = (GLYPH_WIDTH + 1) as f32 * scale;
+        let size = [GLYPH_WIDTH as f32 * scale, GLYPH_HEIGHT as f32 * scale];
+
+        for (i, c) in text.chars().enumerate() {
+            if c == ' ' {
+                continue;
+            }
+            let corner = [position[0] + i as f32 * advance, position[1]];
+            let texcoords = self.font.texcoords(c);
+            self.quads.quad(corner, size, texcoords, color);
+        }
+    }
+
+    /// Adds a filled rectangle with its top left corner at `position`.
+    pub fn rect(&mut self, position: [f32; 2], size: [f32; 2], color: [f32; 4])
+    {
+        // Sample the middle of the block so that the edges of the rectangle stay solid.
+        let (min, max) = self.font.texcoords(BLOCK);
+        let middle = [(min[0] + max[0]) / 2., (min[1] + max[1]) / 2.];
+        self.quads.quad(position, size, (middle, middle), color);
+    }
+
+    /// Draws everything added since the last call into a render target of `width` by `height`
+    /// pixels.
+    pub fn render(&mut self, gl: &dyn GraphicsDevice, width: u32, height: u32)
+    {
+        self.quads.render(gl, &self.font.texture, width, height);
+    }
+}
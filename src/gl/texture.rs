@@ -1,8 +1,13 @@
 use getset::Getters;
-use wasm_bindgen::JsValue;
-use web_sys::{
-    WebGlRenderingContext,
-    WebGlTexture,
+use web_sys::WebGlRenderingContext;
+
+use super::{
+    device::{
+        GraphicsDevice,
+        TexImage2D,
+        TextureId,
+    },
+    image::Image,
 };
 
 type GL = WebGlRenderingContext;
@@ -78,6 +83,14 @@ impl TextureMinificationFilter
             LinearMipmapLinear => GL::LINEAR_MIPMAP_LINEAR,
         }
     }
+
+    /// Whether the filter samples the mipmaps of the texture.
+    fn uses_mipmaps(&self) -> bool
+    {
+        use TextureMinificationFilter::*;
+
+        !matches!(self, Linear | Nearest)
+    }
 }
 
 #[derive(Debug, Builder)]
@@ -106,28 +119,46 @@ pub struct Texture2DSpecification<'a>
     wrap_t: Option<TextureWrappingFunction>,
 
     #[builder(setter(strip_option), default)]
-    pixels: Option<js_sys::Object>,
+    pixels: Option<&'a [u8]>,
 }
 
 impl<'a> Texture2DSpecificationBuilder<'a>
 {
-    pub fn update(self, gl: &WebGlRenderingContext) -> Result<(), JsValue>
+    /// Specifies the base level as the pixels of `image`. The top row of the image is at the
+    /// texture coordinate t = 0.
+    pub fn image(self, image: &'a Image) -> Self
+    {
+        self.level(0)
+            .internal_format(GL::RGBA as i32)
+            .width(image.width() as i32)
+            .height(image.height() as i32)
+            .border(0)
+            .format(GL::RGBA)
+            .type_(GL::UNSIGNED_BYTE)
+            .pixels(image.pixels())
+    }
+
+    /// Specifies the texture and sets its parameters. The mipmaps are generated from the base
+    /// level when it is specified with a minification filter using them.
+    pub fn update(self, gl: &dyn GraphicsDevice) -> Result<(), String>
     {
         let specification = self.build().map_err(|error| format!("{}", error))?;
 
         let texture = specification.texture;
         texture.bind(gl);
 
-        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_array_buffer_view(
-            GL::TEXTURE_2D,
-            specification.level,
-            specification.internal_format,
-            specification.width,
-            specification.height,
-            specification.border,
-            specification.format,
-            specification.type_,
-            specification.pixels.as_ref(),
+        gl.tex_image_2d(
+            &TexImage2D {
+                target: GL::TEXTURE_2D,
+                level: specification.level,
+                internal_format: specification.internal_format,
+                width: specification.width,
+                height: specification.height,
+                border: specification.border,
+                format: specification.format,
+                type_: specification.type_,
+            },
+            specification.pixels,
         )?;
 
         if let Some(filter) = specification.min_filter {
@@ -136,6 +167,24 @@ impl<'a> Texture2DSpecificationBuilder<'a>
                 GL::TEXTURE_MIN_FILTER,
                 filter.value() as i32,
             );
+
+            if filter.uses_mipmaps() && specification.level == 0 {
+                let (width, height) = (specification.width, specification.height);
+                let power_of_two = |side: i32| side > 0 && (side & (side - 1)) == 0;
+                let power_of_two_sides = power_of_two(width) && power_of_two(height);
+
+                if !power_of_two_sides && !gl.capabilities().npot_mipmaps {
+                    texture.unbind(gl);
+                    return Err(format!(
+                        "{}x{} texture can't have mipmaps on {}, its sides must be powers of two",
+                        width,
+                        height,
+                        gl.name()
+                    ));
+                }
+
+                gl.generate_mipmap(GL::TEXTURE_2D);
+            }
         }
 
         if let Some(filter) = specification.mag_filter {
@@ -163,30 +212,124 @@ impl<'a> Texture2DSpecificationBuilder<'a>
 pub struct Texture2D
 {
     #[getset(get = "pub")]
-    texture: WebGlTexture,
+    texture: TextureId,
 }
 
 impl Texture2D
 {
-    pub fn new(gl: &WebGlRenderingContext) -> Result<Texture2D, String>
+    pub fn new(gl: &dyn GraphicsDevice) -> Result<Texture2D, String>
     {
-        gl.create_texture()
-            .ok_or(String::from("failed to create texture"))
-            .map(|texture| Texture2D { texture })
+        gl.create_texture().map(|texture| Texture2D { texture })
     }
 
-    pub fn bind(&self, gl: &WebGlRenderingContext)
+    pub fn bind(&self, gl: &dyn GraphicsDevice)
     {
-        gl.bind_texture(GL::TEXTURE_2D, Some(&self.texture));
+        gl.bind_texture(GL::TEXTURE_2D, Some(self.texture));
     }
 
-    pub fn unbind(&self, gl: &WebGlRenderingContext)
+    pub fn unbind(&self, gl: &dyn GraphicsDevice)
     {
         gl.bind_texture(GL::TEXTURE_2D, None);
     }
 
+    /// A texture with the pixels of `image`, clamped to its edges.
+    pub fn with_image(
+        gl: &dyn GraphicsDevice,
+        image: &Image,
+        min_filter: TextureMinificationFilter,
+        mag_filter: TextureMagnificationFilter,
+    ) -> Result<Texture2D, String>
+    {
+        let mut texture = Texture2D::new(gl)?;
+
+        texture
+            .specification()
+            .image(image)
+            .min_filter(min_filter)
+            .mag_filter(mag_filter)
+            .wrap_s(TextureWrappingFunction::ClampToEdge)
+            .wrap_t(TextureWrappingFunction::ClampToEdge)
+            .update(gl)?;
+
+        Ok(texture)
+    }
+
     pub fn specification(&mut self) -> Texture2DSpecificationBuilder
     {
         Texture2DSpecificationBuilder::default().texture(self)
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::gl::{
+        device::Capabilities,
+        recording::{
+            Command,
+            RecordingDevice,
+        },
+    };
+
+    const UNBIND: Command = Command::BindTexture {
+        target: GL::TEXTURE_2D,
+        texture: None,
+    };
+
+    fn image(width: u32, height: u32) -> Image
+    {
+        Image::new(width, height, vec![255; (width * height * 4) as usize]).unwrap()
+    }
+
+    fn with_mipmaps(gl: &dyn GraphicsDevice, image: &Image) -> Result<Texture2D, String>
+    {
+        Texture2D::with_image(
+            gl,
+            image,
+            TextureMinificationFilter::LinearMipmapLinear,
+            TextureMagnificationFilter::Linear,
+        )
+    }
+
+    #[test]
+    fn mipmaps_are_generated_from_the_image()
+    {
+        let device = RecordingDevice::with_capabilities(Capabilities::none());
+        with_mipmaps(&device, &image(4, 2)).unwrap();
+
+        let commands = device.take_commands();
+        assert!(commands.contains(&Command::GenerateMipmap {
+            target: GL::TEXTURE_2D
+        }));
+        assert_eq!(commands.last(), Some(&UNBIND));
+    }
+
+    #[test]
+    fn mipmaps_of_npot_textures_need_the_capability()
+    {
+        let device = RecordingDevice::with_capabilities(Capabilities::none());
+        let error = with_mipmaps(&device, &image(3, 4)).unwrap_err();
+        assert!(
+            error.starts_with("3x4 texture can't have mipmaps"),
+            "{}",
+            error
+        );
+
+        let commands = device.take_commands();
+        assert!(!commands
+            .iter()
+            .any(|command| matches!(command, Command::GenerateMipmap { .. })));
+        assert_eq!(commands.last(), Some(&UNBIND));
+
+        // Without mipmaps, or with the capability, the sides can be of any size.
+        Texture2D::with_image(
+            &device,
+            &image(3, 4),
+            TextureMinificationFilter::Linear,
+            TextureMagnificationFilter::Linear,
+        )
+        .unwrap();
+        with_mipmaps(&RecordingDevice::new(), &image(3, 4)).unwrap();
+    }
+}
@@ -0,0 +1,581 @@
+use std::cell::RefCell;
+
+use web_sys::{
+    WebGlBuffer,
+    WebGlFramebuffer,
+    WebGlProgram,
+    WebGlRenderbuffer,
+    WebGlRenderingContext,
+    WebGlShader,
+    WebGlTexture,
+    WebGlUniformLocation,
+    WebGlVertexArrayObject,
+};
+
+use super::{
+    backend::{
+        Backend,
+        Version,
+    },
+    device::{
+        ActiveUniform,
+        BufferId,
+        Capabilities,
+        FramebufferId,
+        GraphicsDevice,
+        ProgramId,
+        RenderbufferId,
+        ShaderId,
+        TexImage2D,
+        TextureId,
+        UniformLocationId,
+        VertexArrayId,
+    },
+};
+
+type GL = WebGlRenderingContext;
+
+/// WebGL objects indexed by the value of their handles.
+struct Objects<T>
+{
+    objects: Vec<T>,
+}
+
+impl<T> Objects<T>
+{
+    fn new() -> Objects<T>
+    {
+        Objects {
+            objects: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, object: T) -> u32
+    {
+        self.objects.push(object);
+        self.objects.len() as u32 - 1
+    }
+
+    fn get(&self, id: u32) -> &T
+    {
+        &self.objects[id as usize]
+    }
+
+    /// Forgets every object, so that the browser can garbage collect them.
+    fn clear(&mut self)
+    {
+        self.objects.clear();
+    }
+}
+
+/// The `GraphicsDevice` of a WebGL2 or WebGL1 context.
+///
+/// The device keeps every object it creates until the context is restored or the device is dropped,
+/// when the browser garbage collects them and frees their storage.
+pub struct WebGlDevice
+{
+    backend: RefCell<Backend>,
+    shaders: RefCell<Objects<WebGlShader>>,
+    programs: RefCell<Objects<WebGlProgram>>,
+    buffers: RefCell<Objects<WebGlBuffer>>,
+    textures: RefCell<Objects<WebGlTexture>>,
+    framebuffers: RefCell<Objects<WebGlFramebuffer>>,
+    renderbuffers: RefCell<Objects<WebGlRenderbuffer>>,
+    uniform_locations: RefCell<Objects<WebGlUniformLocation>>,
+    vertex_arrays: RefCell<Objects<WebGlVertexArrayObject>>,
+
+    /// The WebGL1 API of the backend, kept outside the `RefCell` for convenience.
+    gl: GL,
+}
+
+impl WebGlDevice
+{
+    pub fn new(backend: Backend) -> WebGlDevice
+    {
+        WebGlDevice {
+            gl: backend.gl().clone(),
+            backend: RefCell::new(backend),
+            shaders: RefCell::new(Objects::new()),
+            programs: RefCell::new(Objects::new()),
+            buffers: RefCell::new(Objects::new()),
+            textures: RefCell::new(Objects::new()),
+            framebuffers: RefCell::new(Objects::new()),
+            renderbuffers: RefCell::new(Objects::new()),
+            uniform_locations: RefCell::new(Objects::new()),
+            vertex_arrays: RefCell::new(Objects::new()),
+        }
+    }
+
+    fn program(&self, program: ProgramId) -> WebGlProgram
+    {
+        self.programs.borrow().get(program.0).clone()
+    }
+
+    fn uniform_location(&self, location: UniformLocationId) -> WebGlUniformLocation
+    {
+        self.uniform_locations.borrow().get(location.0).clone()
+    }
+}
+
+impl GraphicsDevice for WebGlDevice
+{
+    fn name(&self) -> &'static str
+    {
+        match self.backend.borrow().version() {
+            Version::WebGl1 => "WEBGL1",
+            Version::WebGl2 => "WEBGL2",
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities
+    {
+        *self.backend.borrow().capabilities()
+    }
+
+    fn float_texture_format(&self) -> Option<(i32, u32, u32)>
+    {
+        self.backend.borrow().float_texture_format()
+    }
+
+    /// Forgets the objects of the lost context, which are invalid and would otherwise be kept
+    /// alongside the objects created again by the renderers.
+    fn restore(&self)
+    {
+        self.backend.borrow_mut().restore();
+
+        self.shaders.borrow_mut().clear();
+        self.programs.borrow_mut().clear();
+        self.buffers.borrow_mut().clear();
+        self.textures.borrow_mut().clear();
+        self.framebuffers.borrow_mut().clear();
+        self.renderbuffers.borrow_mut().clear();
+        self.uniform_locations.borrow_mut().clear();
+        self.vertex_arrays.borrow_mut().clear();
+    }
+
+    //
+    // Shaders and programs.
+    //
+
+    fn compile_shader(&self, type_: u32, source: &str) -> Result<ShaderId, String>
+    {
+        let gl = &self.gl;
+        let shader = gl
+            .create_shader(type_)
+            .ok_or_else(|| String::from("Unable to create shader object"))?;
+        gl.shader_source(&shader, source);
+        gl.compile_shader(&shader);
+
+        if gl
+            .get_shader_parameter(&shader, GL::COMPILE_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            Ok(ShaderId(self.shaders.borrow_mut().insert(shader)))
+        } else {
+            Err(gl
+                .get_shader_info_log(&shader)
+                .unwrap_or_else(|| String::from("Unknown error creating shader")))
+        }
+    }
+
+    fn link_program(
+        &self,
+        vertex_shader: ShaderId,
+        fragment_shader: ShaderId,
+        attributes: &[&str],
+    ) -> Result<ProgramId, String>
+    {
+        let gl = &self.gl;
+        let program = gl
+            .create_program()
+            .ok_or_else(|| String::from("Unable to create shader object"))?;
+
+        let shaders = self.shaders.borrow();
+        gl.attach_shader(&program, shaders.get(vertex_shader.0));
+        gl.attach_shader(&program, shaders.get(fragment_shader.0));
+
+        for (location, name) in attributes.iter().enumerate() {
+            gl.bind_attrib_location(&program, location as u32, name);
+        }
+
+        gl.link_program(&program);
+
+        if gl
+            .get_program_parameter(&program, GL::LINK_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            Ok(ProgramId(self.programs.borrow_mut().insert(program)))
+        } else {
+            Err(gl
+                .get_program_info_log(&program)
+                .unwrap_or_else(|| String::from("Unknown error creating program object")))
+        }
+    }
+
+    fn attribute_location(&self, program: ProgramId, name: &str) -> i32
+    {
+        self.gl.get_attrib_location(&self.program(program), name)
+    }
+
+    fn active_uniforms(&self, program: ProgramId) -> Vec<ActiveUniform>
+    {
+        let gl = &self.gl;
+        let program = self.program(program);
+
+        let count = gl
+            .get_program_parameter(&program, GL::ACTIVE_UNIFORMS)
+            .as_f64()
+            .unwrap_or(0.) as u32;
+
+        let mut uniforms = Vec::new();
+        for i in 0..count {
+            let info = match gl.get_active_uniform(&program, i) {
+                Some(info) => info,
+                None => continue,
+            };
+
+            // Arrays are reported with the name of their first element.
+            let name = info.name().trim_end_matches("[0]").to_string();
+
+            if let Some(location) = gl.get_uniform_location(&program, &name) {
+                uniforms.push(ActiveUniform {
+                    name,
+                    type_: info.type_(),
+                    location: UniformLocationId(
+                        self.uniform_locations.borrow_mut().insert(location),
+                    ),
+                });
+            }
+        }
+
+        uniforms
+    }
+
+    fn use_program(&self, program: Option<ProgramId>)
+    {
+        self.gl
+            .use_program(program.map(|program| self.program(program)).as_ref());
+    }
+
+    fn uniform1f(&self, location: UniformLocationId, value: f32)
+    {
+        self.gl
+            .uniform1f(Some(&self.uniform_location(location)), value);
+    }
+
+    fn uniform1i(&self, location: UniformLocationId, value: i32)
+    {
+        self.gl
+            .uniform1i(Some(&self.uniform_location(location)), value);
+    }
+
+    fn uniform2fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.gl
+            .uniform2fv_with_f32_array(Some(&self.uniform_location(location)), value);
+    }
+
+    fn uniform4fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.gl
+            .uniform4fv_with_f32_array(Some(&self.uniform_location(location)), value);
+    }
+
+    fn uniform_matrix4fv(&self, location: UniformLocationId, value: &[f32])
+    {
+        self.gl.uniform_matrix4fv_with_f32_array(
+            Some(&self.uniform_location(location)),
+            false,
+            value,
+        );
+    }
+
+    //
+    // Buffers.
+    //
+
+    fn create_buffer(&self) -> Result<BufferId, String>
+    {
+        self.gl
+            .create_buffer()
+            .ok_or_else(|| String::from("failed to create buffer"))
+            .map(|buffer| BufferId(self.buffers.borrow_mut().insert(buffer)))
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferId>)
+    {
+        let buffers = self.buffers.borrow();
+        self.gl
+            .bind_buffer(target, buffer.map(|buffer| buffers.get(buffer.0)));
+    }
+
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32)
+    {
+        self.gl.buffer_data_with_u8_array(target, data, usage);
+    }
+
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8])
+    {
+        self.gl
+            .buffer_sub_data_with_i32_and_u8_array(target, offset, data);
+    }
+
+    //
+    // Vertex arrays.
+    //
+
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String>
+    {
+        self.backend
+            .borrow()
+            .create_vertex_array()
+            .ok_or_else(|| String::from("failed to create vertex array"))
+            .map(|vertex_array| VertexArrayId(self.vertex_arrays.borrow_mut().insert(vertex_array)))
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayId>)
+    {
+        let vertex_arrays = self.vertex_arrays.borrow();
+        self.backend
+            .borrow()
+            .bind_vertex_array(vertex_array.map(|vertex_array| vertex_arrays.get(vertex_array.0)));
+    }
+
+    //
+    // Vertex attributes.
+    //
+
+    fn enable_vertex_attrib_array(&self, location: u32)
+    {
+        self.gl.enable_vertex_attrib_array(location);
+    }
+
+    fn disable_vertex_attrib_array(&self, location: u32)
+    {
+        self.gl.disable_vertex_attrib_array(location);
+    }
+
+    fn vertex_attrib_pointer(
+        &self,
+        location: u32,
+        components: i32,
+        type_: u32,
+        normalized: bool,
+        stride: i32,
+        offset: i32,
+    )
+    {
+        self.gl.vertex_attrib_pointer_with_i32(
+            location, components, type_, normalized, stride, offset,
+        );
+    }
+
+    fn vertex_attrib(&self, location: u32, value: &[f32])
+    {
+        match *value {
+            [x] => self.gl.vertex_attrib1f(location, x),
+            [x, y] => self.gl.vertex_attrib2f(location, x, y),
+            [x, y, z] => self.gl.vertex_attrib3f(location, x, y, z),
+            [x, y, z, w] => self.gl.vertex_attrib4f(location, x, y, z, w),
+            _ => panic!("vertex attributes have between 1 and 4 components"),
+        }
+    }
+
+    fn vertex_attrib_divisor(&self, location: u32, divisor: u32)
+    {
+        self.backend
+            .borrow()
+            .vertex_attrib_divisor(location, divisor);
+    }
+
+    //
+    // Textures.
+    //
+
+    fn create_texture(&self) -> Result<TextureId, String>
+    {
+        self.gl
+            .create_texture()
+            .ok_or_else(|| String::from("failed to create texture"))
+            .map(|texture| TextureId(self.textures.borrow_mut().insert(texture)))
+    }
+
+    fn active_texture(&self, unit: u32)
+    {
+        self.gl.active_texture(unit);
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<TextureId>)
+    {
+        let textures = self.textures.borrow();
+        self.gl
+            .bind_texture(target, texture.map(|texture| textures.get(texture.0)));
+    }
+
+    fn tex_image_2d(&self, image: &TexImage2D, pixels: Option<&[u8]>) -> Result<(), String>
+    {
+        self.gl
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                image.target,
+                image.level,
+                image.internal_format,
+                image.width,
+                image.height,
+                image.border,
+                image.format,
+                image.type_,
+                pixels,
+            )
+            .map_err(|error| format!("{:?}", error))
+    }
+
+    fn tex_parameteri(&self, target: u32, name: u32, value: i32)
+    {
+        self.gl.tex_parameteri(target, name, value);
+    }
+
+    fn generate_mipmap(&self, target: u32)
+    {
+        self.gl.generate_mipmap(target);
+    }
+
+    //
+    // Framebuffers.
+    //
+
+    fn create_framebuffer(&self) -> Result<FramebufferId, String>
+    {
+        self.gl
+            .create_framebuffer()
+            .ok_or_else(|| String::from("failed to create framebuffer"))
+            .map(|framebuffer| FramebufferId(self.framebuffers.borrow_mut().insert(framebuffer)))
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferId>)
+    {
+        let framebuffers = self.framebuffers.borrow();
+        self.gl.bind_framebuffer(
+            target,
+            framebuffer.map(|framebuffer| framebuffers.get(framebuffer.0)),
+        );
+    }
+
+    fn framebuffer_texture_2d(
+        &self,
+        target: u32,
+        attachment: u32,
+        texture_target: u32,
+        texture: Option<TextureId>,
+        level: i32,
+    )
+    {
+        let textures = self.textures.borrow();
+        self.gl.framebuffer_texture_2d(
+            target,
+            attachment,
+            texture_target,
+            texture.map(|texture| textures.get(texture.0)),
+            level,
+        );
+    }
+
+    fn check_framebuffer_status(&self, target: u32) -> u32
+    {
+        self.gl.check_framebuffer_status(target)
+    }
+
+    fn create_renderbuffer(&self) -> Result<RenderbufferId, String>
+    {
+        self.gl
+            .create_renderbuffer()
+            .ok_or_else(|| String::from("failed to create renderbuffer"))
+            .map(|renderbuffer| {
+                RenderbufferId(self.renderbuffers.borrow_mut().insert(renderbuffer))
+            })
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<RenderbufferId>)
+    {
+        let renderbuffers = self.renderbuffers.borrow();
+        self.gl.bind_renderbuffer(
+            target,
+            renderbuffer.map(|renderbuffer| renderbuffers.get(renderbuffer.0)),
+        );
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32)
+    {
+        self.gl
+            .renderbuffer_storage(target, internal_format, width, height);
+    }
+
+    fn framebuffer_renderbuffer(
+        &self,
+        target: u32,
+        attachment: u32,
+        renderbuffer_target: u32,
+        renderbuffer: Option<RenderbufferId>,
+    )
+    {
+        let renderbuffers = self.renderbuffers.borrow();
+        self.gl.framebuffer_renderbuffer(
+            target,
+            attachment,
+            renderbuffer_target,
+            renderbuffer.map(|renderbuffer| renderbuffers.get(renderbuffer.0)),
+        );
+    }
+
+    //
+    // State and drawing.
+    //
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32)
+    {
+        self.gl.viewport(x, y, width, height);
+    }
+
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32)
+    {
+        self.gl.clear_color(r, g, b, a);
+    }
+
+    fn clear(&self, mask: u32)
+    {
+        self.gl.clear(mask);
+    }
+
+    fn enable(&self, capability: u32)
+    {
+        self.gl.enable(capability);
+    }
+
+    fn disable(&self, capability: u32)
+    {
+        self.gl.disable(capability);
+    }
+
+    fn blend_func(&self, source: u32, destination: u32)
+    {
+        self.gl.blend_func(source, destination);
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32)
+    {
+        self.gl.draw_arrays(mode, first, count);
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, type_: u32, offset: i32)
+    {
+        self.gl.draw_elements_with_i32(mode, count, type_, offset);
+    }
+
+    fn draw_arrays_instanced(&self, mode: u32, first: i32, count: i32, instances: i32)
+    {
+        self.backend
+            .borrow()
+            .draw_arrays_instanced(mode, first, count, instances);
+    }
+}
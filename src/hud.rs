@@ -0,0 +1,168 @@
+use crate::{
+    context::Context,
+    gl::{
+        atlas::{
+            AtlasRegion,
+            SpriteAtlas,
+        },
+        sprite::SpriteRenderer,
+        text::TextRenderer,
+        texture::{
+            TextureMagnificationFilter,
+            TextureMinificationFilter,
+        },
+    },
+    world::World,
+};
+
+const TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+const DIM_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 0.6];
+const BAR_BACKGROUND_COLOR: [f32; 4] = [0.2, 0.2, 0.2, 0.6];
+const ENERGY_COLOR: [f32; 4] = [0.3, 0.8, 1.0, 1.0];
+const BOOST_COLOR: [f32; 4] = [0.6, 0.9, 1.0, 1.0];
+const GUN_COLOR: [f32; 4] = [1.0, 0.8, 0.4, 1.0];
+
+/// Number of glyph pixels that fit the height of the canvas, which decides the scale of the HUD.
+const PIXELS_PER_CANVAS_HEIGHT: f32 = 270.;
+
+/// The sprites of the HUD and their regions.
+const ATLAS_IMAGE: &[u8] = include_bytes!("../assets/hud.png");
+const ATLAS_REGIONS: &str = include_str!("../assets/hud.atlas");
+
+/// The heads-up display showing the state of the ship and the game.
+///
+/// The HUD is drawn in canvas pixels on top of the letterboxed foreground, so it doesn't depend on
+/// the projection of the world.
+pub struct Hud
+{
+    text: TextRenderer,
+    sprites: SpriteRenderer,
+
+    /// The sprite of a life, a small ship.
+    life: AtlasRegion,
+}
+
+impl Hud
+{
+    pub fn new(context: &Context) -> Result<Hud, String>
+    {
+        let gl = context.render_context();
+
+        // The sprites are scaled by whole pixels like the glyphs, so they are sampled unfiltered.
+        let atlas = SpriteAtlas::from_png(
+            gl,
+            ATLAS_IMAGE,
+            ATLAS_REGIONS,
+            TextureMinificationFilter::Nearest,
+            TextureMagnificationFilter::Nearest,
+        )?;
+
+        Ok(Hud {
+            text: TextRenderer::new(gl)?,
+            life: atlas.region("ship")?,
+            sprites: SpriteRenderer::new(gl, atlas)?,
+        })
+    }
+
+    pub fn render(&mut self, context: &Context, world: &World)
+    {
+        let width = *context.canvas_width() as f32;
+        let height = *context.canvas_height() as f32;
+
+        // Scale the glyphs by whole pixels so that they stay sharp.
+        let scale = (height / PIXELS_PER_CANVAS_HEIGHT).round().max(1.);
+        let margin = 4. * scale;
+        let line = 10. * scale;
+        let text = &mut self.text;
+
+        //
+        // Score and wave in the top left corner, lives in the top right corner.
+        //
+        text.text(
+            &format!("SCORE {:06}", world.score()),
+            [margin, margin],
+            scale,
+            TEXT_COLOR,
+        );
+        text.text(
+            &format!("WAVE {}", world.wave()),
+            [margin, margin + line],
+            scale,
+            TEXT_COLOR,
+        );
+
+        let advance = (self.life.width + 1) as f32 * scale;
+        for i in 1..=world.lives() {
+            self.sprites.sprite(
+                &self.life,
+                [width - margin - i as f32 * advance + scale, margin],
+                scale,
+                TEXT_COLOR,
+            );
+        }
+
+        //
+        // Energy, boost and gun in the bottom left corner.
+        //
+        let controller = world.ship_controller();
+        let label_width = TextRenderer::measure("ENERGY ", scale)[0];
+        let bar_size = [60. * scale, 5. * scale];
+
+        let y = height - margin - 3. * line;
+        text.text("ENERGY", [margin, y], scale, TEXT_COLOR);
+        bar(
+            text,
+            [margin + label_width, y + scale],
+            bar_size,
+            controller.energy() / controller.energy_max(),
+            ENERGY_COLOR,
+        );
+
+        let y = height - margin - 2. * line;
+        let gun = controller.gun();
+        text.text("GUN", [margin, y], scale, TEXT_COLOR);
+        bar(
+            text,
+            [margin + label_width, y + scale],
+            bar_size,
+            1. - *gun.period_countdown() as f32 / (*gun.period()).max(1) as f32,
+            GUN_COLOR,
+        );
+
+        let y = height - margin - line;
+        let color = if controller.is_boosting() {
+            BOOST_COLOR
+        } else if controller.boost_is_available() {
+            TEXT_COLOR
+        } else {
+            DIM_COLOR
+        };
+        text.text("BOOST", [margin, y], scale, color);
+
+        //
+        // Game over in the middle.
+        //
+        if world.is_game_over() {
+            let scale = 2. * scale;
+            let size = TextRenderer::measure("GAME OVER", scale);
+            text.text(
+                "GAME OVER",
+                [(width - size[0]) / 2., (height - size[1]) / 2.],
+                scale,
+                TEXT_COLOR,
+            );
+        }
+
+        let gl = context.render_context();
+        let (width, height) = (*context.canvas_width(), *context.canvas_height());
+        self.sprites.render(gl, width, height);
+        text.render(gl, width, height);
+    }
+}
+
+/// Adds a bar filled to `fraction` with `color`.
+fn bar(text: &mut TextRenderer, position: [f32; 2], size: [f32; 2], fraction: f32, color: [f32; 4])
+{
+    text.rect(position, size, BAR_BACKGROUND_COLOR);
+    text.rect(position, [size[0] * fraction.clamp(0., 1.), size[1]], color);
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+/// An action the player can perform on the ship.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Action
+{
+    ThrustForward,
+    ThrustBackwards,
+    SteerCounterClockwise,
+    SteerClockwise,
+    Fire,
+    Boost,
+}
+
+impl Action
+{
+    pub fn iter() -> impl Iterator<Item = Action>
+    {
+        use Action::*;
+
+        [
+            ThrustForward,
+            ThrustBackwards,
+            SteerCounterClockwise,
+            SteerClockwise,
+            Fire,
+            Boost,
+        ]
+        .iter()
+        .copied()
+    }
+
+    fn bit(self) -> u8
+    {
+        1 << self as u8
+    }
+}
+
+/// A command controlling the game rather than the ship.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Command
+{
+    TogglePause,
+    StepFrame,
+    ToggleSlowMotion,
+    ToggleDebugOverlay,
+    NextTheme,
+}
+
+/// The input of a single frame; the set of actions performed and optionally a point to aim at.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InputState
+{
+    actions: u8,
+    aim: Option<[f32; 2]>,
+}
+
+impl InputState
+{
+    pub fn new() -> InputState
+    {
+        InputState::default()
+    }
+
+    /// Creates an `InputState` from the bit representation of its actions, ignoring unknown bits.
+    pub fn from_bits(bits: u8) -> InputState
+    {
+        InputState {
+            actions: bits & Action::iter().fold(0, |mask, action| mask | action.bit()),
+            aim: None,
+        }
+    }
+
+    /// The bit representation of the actions.
+    pub fn bits(&self) -> u8
+    {
+        self.actions
+    }
+
+    pub fn insert(&mut self, action: Action)
+    {
+        self.actions |= action.bit();
+    }
+
+    pub fn remove(&mut self, action: Action)
+    {
+        self.actions &= !action.bit();
+    }
+
+    pub fn contains(&self, action: Action) -> bool
+    {
+        self.actions & action.bit() != 0
+    }
+
+    /// The point in the foreground the ship should turn towards.
+    pub fn aim(&self) -> Option<[f32; 2]>
+    {
+        self.aim
+    }
+
+    pub fn set_aim(&mut self, target: Option<[f32; 2]>)
+    {
+        self.aim = target;
+    }
+}
+
+/// Maps keys to actions and commands.
+#[derive(Clone, Debug)]
+pub struct KeyBindings
+{
+    actions: HashMap<char, Action>,
+    commands: HashMap<char, Command>,
+}
+
+impl KeyBindings
+{
+    /// The actions of the bound keys in `keys_held_down`.
+    pub fn input_state<I: IntoIterator<Item = char>>(&self, keys_held_down: I) -> InputState
+    {
+        keys_held_down
+            .into_iter()
+            .filter_map(|key| self.actions.get(&key))
+            .fold(InputState::new(), |mut state, action| {
+                state.insert(*action);
+                state
+            })
+    }
+
+    /// The commands of the bound keys in `keys_pressed`, in the same order.
+    pub fn commands<I: IntoIterator<Item = char>>(&self, keys_pressed: I) -> Vec<Command>
+    {
+        keys_pressed
+            .into_iter()
+            .filter_map(|key| self.commands.get(&key).copied())
+            .collect()
+    }
+}
+
+impl Default for KeyBindings
+{
+    fn default() -> KeyBindings
+    {
+        use Action::*;
+        use Command::*;
+
+        KeyBindings {
+            actions: [
+                ('w', ThrustForward),
+                ('s', ThrustBackwards),
+                ('a', SteerCounterClockwise),
+                ('d', SteerClockwise),
+                (' ', Fire),
+                ('n', Boost),
+            ]
+            .iter()
+            .copied()
+            .collect(),
+            commands: [
+                ('p', TogglePause),
+                ('.', StepFrame),
+                ('o', ToggleSlowMotion),
+                ('h', ToggleDebugOverlay),
+                ('t', NextTheme),
+            ]
+            .iter()
+            .copied()
+            .collect(),
+        }
+    }
+}
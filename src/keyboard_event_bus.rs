@@ -4,76 +4,95 @@ use std::{
     rc::Rc,
 };
 
-use wasm_bindgen::{
-    prelude::*,
-    JsCast,
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    Event,
+    EventTarget,
+    KeyboardEvent,
 };
-use web_sys::KeyboardEvent;
 
-use crate::dom;
+use crate::dom::EventListener;
 
 pub struct KeyboardEventBus
 {
     keys_held_down: Rc<RefCell<HashSet<char>>>,
+    keys_pressed: Rc<RefCell<Vec<char>>>,
+    _listeners: Vec<EventListener>,
 }
 
 impl KeyboardEventBus
 {
-    pub fn new() -> Result<KeyboardEventBus, JsValue>
+    /// Creates a `KeyboardEventBus` listening to keyboard events on `target`.
+    pub fn new(target: &EventTarget) -> Result<KeyboardEventBus, JsValue>
     {
         let keys_held_down = Rc::new(RefCell::new(HashSet::new()));
+        let keys_pressed = Rc::new(RefCell::new(Vec::new()));
 
-        //
-        // Set on keydown closure.
-        //
-        let closure = Closure::wrap(Box::new({
-            let keys_held_down = Rc::downgrade(&keys_held_down);
-            move |event: KeyboardEvent| {
-                keys_held_down.upgrade().map(|keys_held_down| {
-                    // TODO:
-                    //   There should be some kind of mechanic controlling whether or not an event
-                    //   should prevent default.
-                    event.prevent_default();
+        let listeners = vec![
+            //
+            // Set on keydown listener.
+            //
+            EventListener::new(target, "keydown", {
+                let keys_held_down = Rc::downgrade(&keys_held_down);
+                let keys_pressed = Rc::downgrade(&keys_pressed);
+                move |event: KeyboardEvent| {
+                    if let Some(keys_held_down) = keys_held_down.upgrade() {
+                        // TODO:
+                        //   There should be some kind of mechanic controlling whether or not an
+                        //   event should prevent default.
+                        event.prevent_default();
 
-                    event
-                        .key()
-                        .chars()
-                        .next()
-                        .map(|key| keys_held_down.borrow_mut().insert(key));
-                });
-            }
-        }) as Box<dyn FnMut(_)>);
+                        if let Some(key) = event.key().chars().next() {
+                            keys_held_down.borrow_mut().insert(key);
+                        }
+                    }
 
-        dom::window()
-            .add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+                    if !event.repeat() {
+                        if let (Some(keys_pressed), Some(key)) =
+                            (keys_pressed.upgrade(), event.key().chars().next())
+                        {
+                            keys_pressed.borrow_mut().push(key);
+                        }
+                    }
+                }
+            })?,
+            //
+            // Set on keyup listener.
+            //
+            EventListener::new(target, "keyup", {
+                let keys_held_down = Rc::downgrade(&keys_held_down);
+                move |event: KeyboardEvent| {
+                    if let Some(keys_held_down) = keys_held_down.upgrade() {
+                        // TODO:
+                        //   There should be some kind of mechanic controlling whether or not an
+                        //   event should prevent default.
+                        event.prevent_default();
 
-        //
-        // Set on keyup closure.
-        //
-        let closure = Closure::wrap(Box::new({
-            let keys_held_down = Rc::downgrade(&keys_held_down);
-            move |event: KeyboardEvent| {
-                keys_held_down.upgrade().map(|keys_held_down| {
-                    // TODO:
-                    //   There should be some kind of mechanic controlling whether or not an event
-                    //   should prevent default.
-                    event.prevent_default();
+                        if let Some(key) = event.key().chars().next() {
+                            keys_held_down.borrow_mut().remove(&key);
+                        }
+                    }
+                }
+            })?,
+            //
+            // Release all keys when the target loses focus, since the keyup events would be missed
+            // otherwise.
+            //
+            EventListener::new(target, "blur", {
+                let keys_held_down = Rc::downgrade(&keys_held_down);
+                move |_: Event| {
+                    if let Some(keys_held_down) = keys_held_down.upgrade() {
+                        keys_held_down.borrow_mut().clear();
+                    }
+                }
+            })?,
+        ];
 
-                    event
-                        .key()
-                        .chars()
-                        .next()
-                        .map(|key| keys_held_down.borrow_mut().remove(&key));
-                });
-            }
-        }) as Box<dyn FnMut(_)>);
-
-        dom::window()
-            .add_event_listener_with_callback("keyup", closure.as_ref().unchecked_ref())?;
-        closure.forget();
-
-        Ok(KeyboardEventBus { keys_held_down })
+        Ok(KeyboardEventBus {
+            keys_held_down,
+            keys_pressed,
+            _listeners: listeners,
+        })
     }
 
     pub fn keys_held_down(&self) -> impl Iterator<Item = char>
@@ -82,8 +101,10 @@ impl KeyboardEventBus
         keys.into_iter()
     }
 
-    pub fn key_is_down(&self, key: char) -> bool
+    /// The keys pressed since the last call, in the order they were pressed.
+    pub fn take_keys_pressed(&self) -> impl Iterator<Item = char>
     {
-        self.keys_held_down.borrow().contains(&key)
+        let keys: Vec<char> = self.keys_pressed.borrow_mut().drain(..).collect();
+        keys.into_iter()
     }
 }
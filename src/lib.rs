@@ -1,20 +1,37 @@
 #[macro_use]
 extern crate derive_builder;
 
+mod accessibility;
 mod background;
 mod bullet;
 mod bullet_renderer;
+mod camera;
+mod clock;
 mod collision;
 mod context;
+mod debug_overlay;
 mod dom;
 mod foreground;
 mod foreground_renderer;
+mod game;
 mod gl;
+mod hud;
+mod input;
 mod keyboard_event_bus;
 mod matrix;
+mod mouse_event_bus;
+#[cfg(feature = "native")]
+pub mod native;
+mod outline_renderer;
+mod particle;
+mod particle_renderer;
+mod post_process;
+mod replay;
 mod rock;
-mod run;
 mod run_loop;
+mod scene;
 mod ship;
+mod theme;
+mod world;
 
-pub use run::run;
+pub use game::Game;
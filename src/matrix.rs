@@ -7,7 +7,10 @@ pub use self::{
         Id,
         Matrix4x4,
     },
-    orthographic::OrthographicProjection,
+    orthographic::{
+        OrthographicProjection,
+        OrthographicProjectionBuilder,
+    },
     transform::{
         Rotate,
         Scale,
@@ -17,6 +17,17 @@ impl OrthographicProjection
     {
         OrthographicProjectionBuilder::default()
     }
+
+    /// Maps a point in normalized device coordinates back to the space of the projection.
+    pub fn unproject(&self, point: [f32; 2]) -> [f32; 2]
+    {
+        let x = &self.abscissa;
+        let y = &self.ordinate;
+        [
+            x.start + (point[0] + 1.) / 2. * (x.end - x.start),
+            y.start + (point[1] + 1.) / 2. * (y.end - y.start),
+        ]
+    }
 }
 
 impl OrthographicProjectionBuilder
@@ -112,6 +112,7 @@ impl Rotate
     }
 }
 
+#[allow(dead_code)]
 impl RotateBuilder
 {
     pub fn radians(self, radians: f32) -> RotateBuilder
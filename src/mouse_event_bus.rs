@@ -0,0 +1,87 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+};
+
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    HtmlCanvasElement,
+    MouseEvent,
+};
+
+use crate::dom::{
+    self,
+    EventListener,
+};
+
+#[derive(Debug, Default)]
+struct MouseState
+{
+    position: Option<[f32; 2]>,
+    left_button_down: bool,
+}
+
+/// Keeps track of the mouse cursor and the left mouse button over a canvas.
+pub struct MouseEventBus
+{
+    state: Rc<RefCell<MouseState>>,
+    _listeners: Vec<EventListener>,
+}
+
+impl MouseEventBus
+{
+    pub fn new(canvas: &HtmlCanvasElement) -> Result<MouseEventBus, JsValue>
+    {
+        let state = Rc::new(RefCell::new(MouseState::default()));
+
+        let listener = |event_type, lambda: fn(&mut MouseState, MouseEvent)| {
+            let state = Rc::downgrade(&state);
+            EventListener::new(canvas, event_type, move |event: MouseEvent| {
+                if let Some(state) = state.upgrade() {
+                    lambda(&mut state.borrow_mut(), event);
+                }
+            })
+        };
+
+        let listeners = vec![
+            listener("mousemove", |state, event| {
+                let ratio = dom::window().device_pixel_ratio() as f32;
+                state.position = Some([
+                    event.offset_x() as f32 * ratio,
+                    event.offset_y() as f32 * ratio,
+                ]);
+            })?,
+            listener("mousedown", |state, event| {
+                if event.button() == 0 {
+                    state.left_button_down = true;
+                }
+            })?,
+            listener("mouseup", |state, event| {
+                if event.button() == 0 {
+                    state.left_button_down = false;
+                }
+            })?,
+            // Release the button if the cursor leaves the canvas, since the mouseup event would be
+            // missed otherwise.
+            listener("mouseleave", |state, _| {
+                state.left_button_down = false;
+            })?,
+        ];
+
+        Ok(MouseEventBus {
+            state,
+            _listeners: listeners,
+        })
+    }
+
+    /// The position of the cursor in device pixels, relative to the top left corner of the canvas.
+    pub fn position(&self) -> Option<[f32; 2]>
+    {
+        self.state.borrow().position
+    }
+
+    pub fn left_button_is_down(&self) -> bool
+    {
+        self.state.borrow().left_button_down
+    }
+}
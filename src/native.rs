@@ -0,0 +1,364 @@
+use std::{
+    collections::HashSet,
+    fs,
+    num::NonZeroU32,
+    path::PathBuf,
+    rc::Rc,
+    time::Instant,
+};
+
+use glutin::{
+    config::ConfigTemplateBuilder,
+    context::{
+        ContextApi,
+        ContextAttributesBuilder,
+        NotCurrentGlContext,
+        PossiblyCurrentContext,
+        Version,
+    },
+    display::{
+        GetGlDisplay,
+        GlDisplay,
+    },
+    surface::{
+        GlSurface,
+        Surface,
+        SwapInterval,
+        WindowSurface,
+    },
+};
+use glutin_winit::{
+    DisplayBuilder,
+    GlWindow,
+};
+use raw_window_handle::HasWindowHandle;
+use winit::{
+    application::ApplicationHandler,
+    dpi::LogicalSize,
+    event::{
+        ElementState,
+        KeyEvent,
+        MouseButton,
+        WindowEvent,
+    },
+    event_loop::{
+        ActiveEventLoop,
+        EventLoop,
+    },
+    keyboard::{
+        Key,
+        NamedKey,
+    },
+    window::{
+        Window,
+        WindowId,
+    },
+};
+
+use crate::{
+    accessibility::AccessibilitySettings,
+    context::{
+        Context,
+        ContextDescriptorBuilder,
+    },
+    gl::gles::GlesDevice,
+    scene::{
+        self,
+        FrameInput,
+        Scene,
+    },
+};
+
+/// Options the native game can be started with, the counterpart of the options of `Game`.
+#[derive(Clone, Debug, Default)]
+pub struct NativeOptions
+{
+    /// Seed of every world, a random seed is used for each world if not set.
+    pub seed: Option<u64>,
+
+    /// Start with mouse aim enabled.
+    pub mouse_aim: bool,
+
+    /// File the replay of the game is written to when the window is closed.
+    pub replay_path: Option<PathBuf>,
+}
+
+/// Runs the game in a window until it is closed.
+///
+/// The game is played with the same keys as in the browser.
+pub fn run(options: NativeOptions) -> Result<(), String>
+{
+    let event_loop = EventLoop::new().map_err(|error| format!("{}", error))?;
+
+    let mut application = Application {
+        options,
+        game: None,
+        error: None,
+    };
+    event_loop
+        .run_app(&mut application)
+        .map_err(|error| format!("{}", error))?;
+
+    if let Some(error) = application.error {
+        return Err(error);
+    }
+
+    match (&application.options.replay_path, &application.game) {
+        (Some(path), Some(game)) => fs::write(path, game.scene.recorded_replay().encode())
+            .map_err(|error| format!("failed to write {}: {}", path.display(), error)),
+        _ => Ok(()),
+    }
+}
+
+struct Application
+{
+    options: NativeOptions,
+    game: Option<NativeGame>,
+
+    /// The error that made the event loop exit.
+    error: Option<String>,
+}
+
+impl ApplicationHandler for Application
+{
+    fn resumed(&mut self, event_loop: &ActiveEventLoop)
+    {
+        if self.game.is_some() {
+            return;
+        }
+
+        match NativeGame::new(event_loop, &self.options) {
+            Ok(game) => self.game = Some(game),
+            Err(error) => {
+                self.error = Some(error);
+                event_loop.exit();
+            }
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent)
+    {
+        let game = match self.game.as_mut() {
+            Some(game) => game,
+            None => return,
+        };
+
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(_) => game.resize_pending = true,
+            WindowEvent::Focused(false) => {
+                // The key and button releases are missed while the window doesn't have focus.
+                game.keys_held_down.clear();
+                game.left_button_down = false;
+                game.scene.clock.pause();
+            }
+            WindowEvent::KeyboardInput { event, .. } => game.key(&event),
+            WindowEvent::CursorMoved { position, .. } => {
+                game.mouse_position = Some([position.x as f32, position.y as f32]);
+            }
+            WindowEvent::CursorLeft { .. } => game.left_button_down = false,
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => game.left_button_down = state == ElementState::Pressed,
+            WindowEvent::RedrawRequested => {
+                if let Err(error) = game.frame() {
+                    self.error = Some(error);
+                    event_loop.exit();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn about_to_wait(&mut self, _: &ActiveEventLoop)
+    {
+        // Frames are paced by the swap interval of the surface.
+        if let Some(game) = self.game.as_ref() {
+            game.window.request_redraw();
+        }
+    }
+}
+
+/// A game running in a window, the native counterpart of `Game`.
+struct NativeGame
+{
+    window: Window,
+    surface: Surface<WindowSurface>,
+    gl_context: PossiblyCurrentContext,
+    context: Context,
+
+    /// Set when the window has been resized and the surface should be resized to match.
+    resize_pending: bool,
+
+    /// Time the game started, from which the time of the frames is measured.
+    start: Instant,
+
+    keys_held_down: HashSet<char>,
+    keys_pressed: Vec<char>,
+
+    /// Position of the cursor in physical pixels, relative to the top left corner of the window.
+    mouse_position: Option<[f32; 2]>,
+    left_button_down: bool,
+
+    scene: Scene,
+}
+
+impl NativeGame
+{
+    fn new(event_loop: &ActiveEventLoop, options: &NativeOptions) -> Result<NativeGame, String>
+    {
+        // -----------------------------------------------------------------------------------------
+        // Create window and OpenGL ES context.
+        // -----------------------------------------------------------------------------------------
+
+        let window_attributes = Window::default_attributes()
+            .with_title("starrock")
+            .with_inner_size(LogicalSize::new(960., 720.));
+
+        let (window, config) = DisplayBuilder::new()
+            .with_window_attributes(Some(window_attributes))
+            .build(event_loop, ConfigTemplateBuilder::new(), |mut configs| {
+                configs.next().expect("no OpenGL config available")
+            })
+            .map_err(|error| format!("{}", error))?;
+        let window = window.ok_or("failed to create window")?;
+
+        let display = config.display();
+        let handle = window
+            .window_handle()
+            .map_err(|error| format!("{}", error))?
+            .as_raw();
+
+        let context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::Gles(Some(Version::new(3, 0))))
+            .build(Some(handle));
+
+        let surface_attributes = window
+            .build_surface_attributes(Default::default())
+            .map_err(|error| format!("{}", error))?;
+
+        let (surface, gl_context) = unsafe {
+            let surface = display
+                .create_window_surface(&config, &surface_attributes)
+                .map_err(|error| format!("{}", error))?;
+            let gl_context = display
+                .create_context(&config, &context_attributes)
+                .and_then(|context| context.make_current(&surface))
+                .map_err(|error| format!("{}", error))?;
+            (surface, gl_context)
+        };
+
+        // Pace frames with the display like `requestAnimationFrame` does, where supported.
+        let _ =
+            surface.set_swap_interval(&gl_context, SwapInterval::Wait(NonZeroU32::new(1).unwrap()));
+
+        let gl = unsafe {
+            glow::Context::from_loader_function_cstr(|name| display.get_proc_address(name))
+        };
+
+        let size = window.inner_size();
+        let context = Context::new(
+            ContextDescriptorBuilder::default()
+                .device(Rc::new(GlesDevice::new(gl)))
+                .canvas_width(size.width.max(1))
+                .canvas_height(size.height.max(1))
+                .foreground_projection_matrix(scene::foreground_projection_matrix())
+                .build()
+                .map_err(|error| format!("{}", error))?,
+        );
+
+        // -----------------------------------------------------------------------------------------
+        // Initialize scene.
+        // -----------------------------------------------------------------------------------------
+
+        let mut scene = Scene::new(&context, options.seed, AccessibilitySettings::default())?;
+        scene.mouse_aim = options.mouse_aim;
+
+        Ok(NativeGame {
+            window,
+            surface,
+            gl_context,
+            context,
+            resize_pending: true,
+            start: Instant::now(),
+            keys_held_down: HashSet::new(),
+            keys_pressed: Vec::new(),
+            mouse_position: None,
+            left_button_down: false,
+            scene,
+        })
+    }
+
+    /// Updates the keys held down and pressed with the keyboard `event`.
+    fn key(&mut self, event: &KeyEvent)
+    {
+        // Keys are identified by their character like `KeyboardEvent.key` in the browser.
+        let key = match &event.logical_key {
+            Key::Character(text) => text.chars().next(),
+            Key::Named(NamedKey::Space) => Some(' '),
+            _ => None,
+        };
+        let key = match key {
+            Some(key) => key,
+            None => return,
+        };
+
+        match event.state {
+            ElementState::Pressed => {
+                self.keys_held_down.insert(key);
+                if !event.repeat {
+                    self.keys_pressed.push(key);
+                }
+            }
+            ElementState::Released => {
+                self.keys_held_down.remove(&key);
+            }
+        }
+    }
+
+    /// Steps and renders the scene with the input collected since last frame, and presents the
+    /// frame.
+    fn frame(&mut self) -> Result<(), String>
+    {
+        if self.resize_pending {
+            self.resize()?;
+        }
+
+        let input = FrameInput {
+            time: Some(self.start.elapsed().as_secs_f64() * 1000.),
+            keys_pressed: self.keys_pressed.drain(..).collect(),
+            keys_held_down: self.keys_held_down.iter().copied().collect(),
+            mouse_position: self.mouse_position,
+            left_button_down: self.left_button_down,
+        };
+        self.scene.frame(&mut self.context, &input);
+
+        self.surface
+            .swap_buffers(&self.gl_context)
+            .map_err(|error| format!("{}", error))
+    }
+
+    /// Resizes the surface, and everything depending on its size, to fit the window.
+    fn resize(&mut self) -> Result<(), String>
+    {
+        self.resize_pending = false;
+
+        let size = self.window.inner_size();
+        let (width, height) = (size.width.max(1), size.height.max(1));
+        if (width, height) == (*self.context.canvas_width(), *self.context.canvas_height()) {
+            return Ok(());
+        }
+
+        self.window.resize_surface(&self.surface, &self.gl_context);
+
+        self.context.set_canvas_width(width);
+        self.context.set_canvas_height(height);
+        self.context
+            .render_context()
+            .viewport(0, 0, width as i32, height as i32);
+
+        self.scene.resize(&self.context)
+    }
+}
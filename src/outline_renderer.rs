@@ -0,0 +1,275 @@
+use web_sys::WebGlRenderingContext;
+
+use crate::{
+    context::Context,
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        instanced::InstanceBuffer,
+        program::{
+            Float,
+            Mat4,
+            Program,
+            Uniform,
+            Vec2,
+            Vec4,
+        },
+    },
+};
+
+/// Height in pixels of the render target at which the widths of the strokes and the glows are
+/// given, they are scaled with the height of the actual target.
+const REFERENCE_HEIGHT: f32 = 1000.;
+
+/// Number of floats of an instance, its position, size and direction.
+const INSTANCE_FLOATS: usize = 6;
+
+/// A closed polygon drawn by an `OutlineRenderer`, as one quad per side.
+pub struct Outline
+{
+    vertex_buffer: ArrayBuffer<f32>,
+    count: i32,
+}
+
+impl Outline
+{
+    /// The outline going through `points` in order and back to the first one.
+    pub fn closed(gl: &dyn GraphicsDevice, points: &[[f32; 2]]) -> Result<Outline, String>
+    {
+        // Each side is extruded by the vertex shader from a quad along it, the first component of
+        // the corners going from its start to its end and the second from one side to the other.
+        const CORNERS: [[f32; 2]; 6] = [
+            [0., -1.],
+            [1., -1.],
+            [1., 1.],
+            [0., -1.],
+            [1., 1.],
+            [0., 1.],
+        ];
+
+        let mut vertices = Vec::with_capacity(points.len() * CORNERS.len() * 6);
+        for (i, start) in points.iter().enumerate() {
+            let end = &points[(i + 1) % points.len()];
+            for corner in CORNERS.iter() {
+                vertices.extend_from_slice(start);
+                vertices.extend_from_slice(end);
+                vertices.extend_from_slice(corner);
+            }
+        }
+
+        Ok(Outline {
+            vertex_buffer: ArrayBuffer::with_data(gl, BufferUsage::StaticDraw, &vertices)?,
+            count: (points.len() * CORNERS.len()) as i32,
+        })
+    }
+}
+
+/// How an `OutlineRenderer` strokes the outlines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stroke
+{
+    pub color: [f32; 4],
+
+    /// Width of the line in pixels, at a render target 1000 pixels high.
+    pub width: f32,
+
+    /// Width of the halo around the line in pixels, at a render target 1000 pixels high.
+    pub glow: f32,
+}
+
+/// Renderer that draws `Outline`s as thick antialiased lines, like a vector display.
+///
+/// The sides are extruded in screen space, so that the lines have the same width however the
+/// outlines are scaled, and the ends of the sides are rounded so that they join smoothly.
+pub struct OutlineRenderer
+{
+    program: Program,
+    projection_matrix: Uniform<Mat4>,
+    viewport: Uniform<Vec2>,
+    stroke_width: Uniform<Float>,
+    glow: Uniform<Float>,
+    color: Uniform<Vec4>,
+    vertex_layout: VertexLayout,
+    instances: InstanceBuffer,
+}
+
+impl OutlineRenderer
+{
+    pub fn new(gl: &dyn GraphicsDevice) -> Result<OutlineRenderer, String>
+    {
+        let vertex_layout = VertexLayout::new()
+            .attribute("start", 2, VertexAttributeType::Float)
+            .attribute("end", 2, VertexAttributeType::Float)
+            .attribute("corner", 2, VertexAttributeType::Float);
+
+        // Every instance is positioned, scaled and rotated to its direction.
+        let instance_layout = VertexLayout::new()
+            .attribute("instance_position", 2, VertexAttributeType::Float)
+            .attribute("instance_size", 2, VertexAttributeType::Float)
+            .attribute("instance_direction", 2, VertexAttributeType::Float);
+
+        let program = Program::new(
+            gl,
+            &vertex_shader(gl)?,
+            &fragment_shader(gl)?,
+            &[&vertex_layout, &instance_layout],
+        )?;
+
+        Ok(OutlineRenderer {
+            projection_matrix: program.uniform("projection_matrix")?,
+            viewport: program.uniform("viewport")?,
+            stroke_width: program.uniform("stroke_width")?,
+            glow: program.uniform("glow")?,
+            color: program.uniform("color")?,
+            program,
+            vertex_layout,
+            instances: InstanceBuffer::new(gl, instance_layout)?,
+        })
+    }
+
+    /// Renders `outline` once for each instance in `instances`, which holds the position, size
+    /// and direction of every instance one after the other.
+    pub fn render(
+        &mut self,
+        context: &Context,
+        outline: &Outline,
+        instances: Vec<f32>,
+        stroke: &Stroke,
+    )
+    {
+        let gl = context.render_context();
+        let count = instances.len() / INSTANCE_FLOATS;
+        self.instances.set_data(gl, instances);
+
+        self.program.use_program(gl);
+
+        let matrix = context.foreground_matrix();
+        self.projection_matrix.set(gl, &matrix);
+
+        let [_, _, width, height] = context.render_target().viewport;
+        let scale = height as f32 / REFERENCE_HEIGHT;
+        self.viewport.set(gl, &[width as f32, height as f32]);
+        self.stroke_width.set(gl, &(stroke.width * scale));
+        self.glow.set(gl, &(stroke.glow * scale));
+        self.color.set(gl, &stroke.color);
+
+        self.program
+            .enable_vertex_layout(gl, &self.vertex_layout, &outline.vertex_buffer, 0);
+
+        self.instances.draw(
+            gl,
+            &self.program,
+            WebGlRenderingContext::TRIANGLES,
+            outline.count,
+            0..count,
+        );
+
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
+    }
+}
+
+fn vertex_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_vertex_shader(
+        context,
+        r#"
+        attribute vec2 start;
+        attribute vec2 end;
+        attribute vec2 corner;
+        attribute vec2 instance_position;
+        attribute vec2 instance_size;
+        attribute vec2 instance_direction;
+
+        uniform mat4 projection_matrix;
+        uniform vec2 viewport;
+        uniform float stroke_width;
+        uniform float glow;
+
+        varying vec2 _pixel;
+        varying vec2 _start;
+        varying vec2 _end;
+        varying float _stroke_width;
+        varying float _glow;
+
+        vec2 to_pixels(vec2 p)
+        {
+            p *= instance_size;
+            p = vec2(p.x * instance_direction.x - p.y * instance_direction.y,
+                     p.x * instance_direction.y + p.y * instance_direction.x);
+            p += instance_position;
+
+            vec4 clip = projection_matrix * vec4(p, 0.0, 1.0);
+            return (clip.xy / clip.w * 0.5 + 0.5) * viewport;
+        }
+
+        void main()
+        {
+            vec2 a = to_pixels(start);
+            vec2 b = to_pixels(end);
+
+            float length_ = length(b - a);
+            vec2 direction = length_ > 0.0 ? (b - a) / length_ : vec2(1.0, 0.0);
+            vec2 normal = vec2(-direction.y, direction.x);
+
+            // Room for the line, its glow and a pixel of antialiasing, past the ends too.
+            float radius = stroke_width * 0.5 + glow + 1.0;
+            vec2 p = mix(a, b, corner.x)
+                + (direction * (corner.x * 2.0 - 1.0) + normal * corner.y) * radius;
+
+            _pixel = p;
+            _start = a;
+            _end = b;
+            _stroke_width = stroke_width;
+            _glow = glow;
+            gl_Position = vec4(p / viewport * 2.0 - 1.0, 0.0, 1.0);
+        }
+        "#,
+    )
+}
+
+fn fragment_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_fragment_shader(
+        context,
+        r#"
+        #ifdef GL_FRAGMENT_PRECISION_HIGH
+        precision highp float;
+        #else
+        precision mediump float;
+        #endif
+
+        uniform vec4 color;
+
+        // The widths are passed from the vertex shader, as uniforms of both shaders must have the
+        // same precision which the fragment shader might not have.
+        varying vec2 _pixel;
+        varying vec2 _start;
+        varying vec2 _end;
+        varying float _stroke_width;
+        varying float _glow;
+
+        void main()
+        {
+            // Distance to the side, whose ends are rounded.
+            vec2 side = _end - _start;
+            float t = clamp(dot(_pixel - _start, side) / max(dot(side, side), 0.0001), 0.0, 1.0);
+            float d = length(_pixel - _start - side * t) - _stroke_width * 0.5;
+
+            float line = clamp(0.5 - d, 0.0, 1.0);
+            float halo = _glow > 0.0 ? 1.0 - clamp(d / _glow, 0.0, 1.0) : 0.0;
+
+            gl_FragColor = vec4(color.rgb, color.a * max(line, halo * halo * 0.5));
+        }
+        "#,
+    )
+}
@@ -0,0 +1,287 @@
+use std::{
+    f32::consts::PI,
+    ops::Range,
+};
+
+use getset::Getters;
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use vecmath::vec2_add;
+
+use crate::{
+    foreground,
+    world::WorldEvent,
+};
+
+/// Maximum number of particles alive at once, new particles are not emitted past it.
+const CAPACITY: usize = 4096;
+
+/// Size of the rock the explosion emitter's count is given for.
+const EXPLOSION_ROCK_SIZE: f32 = 0.1;
+
+/// A color that changes linearly from `start` to `end` over the lifetime of a particle.
+#[derive(Clone, Copy, Debug)]
+pub struct ColorRamp
+{
+    pub start: [f32; 4],
+    pub end: [f32; 4],
+}
+
+impl ColorRamp
+{
+    /// The color at `t`, from 0 at birth to 1 at death.
+    pub fn sample(&self, t: f32) -> [f32; 4]
+    {
+        let mut color = self.start;
+        for (c, end) in color.iter_mut().zip(self.end.iter()) {
+            *c += (end - *c) * t;
+        }
+        color
+    }
+}
+
+/// A size that changes from `start` to `end` over the lifetime of a particle, faster at the start
+/// for exponents below 1 and faster at the end for exponents above 1.
+#[derive(Clone, Copy, Debug)]
+pub struct SizeCurve
+{
+    pub start: f32,
+    pub end: f32,
+    pub exponent: f32,
+}
+
+impl SizeCurve
+{
+    /// The size at `t`, from 0 at birth to 1 at death.
+    pub fn sample(&self, t: f32) -> f32
+    {
+        self.start + (self.end - self.start) * t.powf(self.exponent)
+    }
+}
+
+#[derive(Clone, Debug, Getters)]
+pub struct Particle
+{
+    #[getset(get = "pub")]
+    position: [f32; 2],
+
+    velocity: [f32; 2],
+
+    /// Number of frames the particle has lived.
+    age: u32,
+
+    /// Number of frames the particle lives.
+    lifetime: u32,
+
+    colors: ColorRamp,
+    sizes: SizeCurve,
+}
+
+impl Particle
+{
+    /// How far the particle is into its lifetime, from 0 to 1.
+    fn t(&self) -> f32
+    {
+        self.age as f32 / self.lifetime as f32
+    }
+
+    pub fn color(&self) -> [f32; 4]
+    {
+        self.colors.sample(self.t())
+    }
+
+    pub fn size(&self) -> f32
+    {
+        self.sizes.sample(self.t())
+    }
+
+    /// Moves the particle one frame. Returns `false` when the particle has died.
+    fn update(&mut self) -> bool
+    {
+        self.position = vec2_add(self.position, self.velocity);
+        foreground::position_modulo(&mut self.position);
+
+        self.age += 1;
+        self.age < self.lifetime
+    }
+}
+
+/// Emits bursts of particles.
+#[derive(Builder, Clone, Debug)]
+pub struct ParticleEmitter
+{
+    /// Number of particles emitted by each burst.
+    count: u32,
+
+    /// Speed of the particles relative to the velocity they are emitted with.
+    speed: Range<f32>,
+
+    /// Angle in radians around the direction of the burst particles are spread over.
+    spread: f32,
+
+    /// Lifetime of the particles in frames.
+    lifetime: Range<u32>,
+
+    colors: ColorRamp,
+    sizes: SizeCurve,
+}
+
+impl ParticleEmitter
+{
+    pub fn builder() -> ParticleEmitterBuilder
+    {
+        ParticleEmitterBuilder::default()
+    }
+
+    /// Emits `count` particles at `position` heading towards `direction` on top of `velocity`.
+    fn emit<R: Rng>(
+        &self,
+        rng: &mut R,
+        particles: &mut Vec<Particle>,
+        count: u32,
+        position: [f32; 2],
+        velocity: [f32; 2],
+        direction: f32,
+    )
+    {
+        let count = (count as usize).min(CAPACITY.saturating_sub(particles.len()));
+
+        particles.extend((0..count).map(|_| {
+            let angle = direction + self.spread * (rng.gen::<f32>() - 0.5);
+            let speed = rng.gen_range(self.speed.clone());
+
+            Particle {
+                position,
+                velocity: vec2_add(velocity, [angle.cos() * speed, angle.sin() * speed]),
+                age: 0,
+                lifetime: rng.gen_range(self.lifetime.clone()),
+                colors: self.colors,
+                sizes: self.sizes,
+            }
+        }));
+    }
+}
+
+/// The emitters a `ParticleSystem` triggers on world events.
+#[derive(Builder, Clone, Debug)]
+pub struct ParticleEffects
+{
+    thrust: ParticleEmitter,
+
+    /// Emitted instead of `thrust` while boosting.
+    boost: ParticleEmitter,
+
+    impact: ParticleEmitter,
+
+    /// Emitted when a rock is destroyed. The number of particles is scaled by the size of the rock,
+    /// the emitter's count is the number of particles for a rock of size `EXPLOSION_ROCK_SIZE`.
+    explosion: ParticleEmitter,
+}
+
+impl ParticleEffects
+{
+    pub fn builder() -> ParticleEffectsBuilder
+    {
+        ParticleEffectsBuilder::default()
+    }
+}
+
+/// Particles emitted by the events of a world.
+///
+/// The particles are only visual, they use their own random number generator so that they never
+/// affect the simulation.
+pub struct ParticleSystem
+{
+    effects: ParticleEffects,
+    particles: Vec<Particle>,
+    rng: StdRng,
+}
+
+impl ParticleSystem
+{
+    pub fn new(effects: ParticleEffects) -> ParticleSystem
+    {
+        ParticleSystem {
+            effects,
+            particles: Vec::new(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle]
+    {
+        &self.particles
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.particles.clear();
+    }
+
+    /// Emits particles for `events` and moves every particle one frame.
+    pub fn step(&mut self, events: &[WorldEvent])
+    {
+        self.particles.retain_mut(Particle::update);
+
+        let effects = &self.effects;
+        let rng = &mut self.rng;
+        let particles = &mut self.particles;
+
+        for event in events {
+            match event {
+                WorldEvent::Thrust {
+                    position,
+                    velocity,
+                    yaw,
+                    boosted,
+                } => {
+                    let emitter = if *boosted {
+                        &effects.boost
+                    } else {
+                        &effects.thrust
+                    };
+                    // The exhaust leaves the tail of the ship in the opposite direction.
+                    emitter.emit(
+                        rng,
+                        particles,
+                        emitter.count,
+                        *position,
+                        *velocity,
+                        yaw + PI,
+                    );
+                }
+                WorldEvent::Impact { position, velocity } => {
+                    let emitter = &effects.impact;
+                    // The sparks bounce back towards where the bullet came from.
+                    let direction = velocity[1].atan2(velocity[0]) + PI;
+                    emitter.emit(
+                        rng,
+                        particles,
+                        emitter.count,
+                        *position,
+                        [0., 0.],
+                        direction,
+                    );
+                }
+                WorldEvent::RockDestroyed {
+                    position,
+                    velocity,
+                    size,
+                } => {
+                    let emitter = &effects.explosion;
+                    let count =
+                        (emitter.count as f32 * size[0] / EXPLOSION_ROCK_SIZE).ceil() as u32;
+                    emitter.emit(rng, particles, count, *position, *velocity, 0.);
+                }
+                WorldEvent::ShipHit { position } => {
+                    let emitter = &effects.impact;
+                    emitter.emit(rng, particles, emitter.count, *position, [0., 0.], 0.);
+                }
+                WorldEvent::ShipCollision { .. } => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use web_sys::WebGlRenderingContext;
+
+use crate::{
+    context::Context,
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        instanced::InstanceBuffer,
+        program::{
+            Mat4,
+            Program,
+            Uniform,
+        },
+    },
+    particle::Particle,
+};
+
+/// Renderer that renders all particles with one draw call.
+pub struct ParticleRenderer
+{
+    program: Program,
+    projection_matrix: Uniform<Mat4>,
+    vertex_layout: VertexLayout,
+    vertex_buffer: ArrayBuffer<f32>,
+    instances: InstanceBuffer,
+}
+
+impl ParticleRenderer
+{
+    pub fn new(context: &Context) -> Result<ParticleRenderer, String>
+    {
+        let gl = context.render_context();
+
+        let vertex_layout = VertexLayout::new().attribute("corner", 2, VertexAttributeType::Float);
+
+        let instance_layout = VertexLayout::new()
+            .attribute("instance_position", 2, VertexAttributeType::Float)
+            .attribute("instance_size", 1, VertexAttributeType::Float)
+            .attribute("instance_color", 4, VertexAttributeType::Float);
+
+        let program = Program::new(
+            gl,
+            &vertex_shader(gl)?,
+            &fragment_shader(gl)?,
+            &[&vertex_layout, &instance_layout],
+        )?;
+
+        let vertex_buffer = ArrayBuffer::with_data(
+            gl,
+            BufferUsage::StaticDraw,
+            &[-1., -1., -1., 1., 1., -1., 1., 1.],
+        )?;
+
+        Ok(ParticleRenderer {
+            projection_matrix: program.uniform("projection_matrix")?,
+            program,
+            vertex_layout,
+            vertex_buffer,
+            instances: InstanceBuffer::new(context.render_context(), instance_layout)?,
+        })
+    }
+
+    pub fn render(&mut self, context: &Context, particles: &[Particle])
+    {
+        let gl = context.render_context();
+
+        //
+        // Upload the instances.
+        //
+        let mut data = Vec::with_capacity(particles.len() * 7);
+        for particle in particles {
+            data.extend_from_slice(particle.position());
+            data.push(particle.size());
+            data.extend_from_slice(&particle.color());
+        }
+        self.instances.set_data(gl, data);
+
+        //
+        // Setup the program.
+        //
+        self.program.use_program(gl);
+
+        let matrix = context.foreground_matrix();
+        self.projection_matrix.set(gl, &matrix);
+
+        self.program
+            .enable_vertex_layout(gl, &self.vertex_layout, &self.vertex_buffer, 0);
+
+        //
+        // Draw particles.
+        //
+        self.instances.draw(
+            gl,
+            &self.program,
+            WebGlRenderingContext::TRIANGLE_STRIP,
+            4,
+            0..particles.len(),
+        );
+
+        //
+        // Clean-up
+        //
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
+    }
+}
+
+fn vertex_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_vertex_shader(
+        context,
+        r#"
+        attribute vec2 corner;
+        attribute vec2 instance_position;
+        attribute float instance_size;
+        attribute vec4 instance_color;
+
+        uniform mat4 projection_matrix;
+
+        varying vec2 _corner;
+        varying vec4 _color;
+
+        void main()
+        {
+            vec2 p = instance_position + corner * instance_size;
+
+            _corner = corner;
+            _color = instance_color;
+
+            gl_Position = projection_matrix * vec4(p, 0.0, 1.0);
+        }
+        "#,
+    )
+}
+
+fn fragment_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_fragment_shader(
+        context,
+        r#"
+        precision mediump float;
+
+        varying vec2 _corner;
+        varying vec4 _color;
+
+        void main()
+        {
+            // Round particles that fade out towards the edge.
+            float d = length(_corner);
+            if (d > 1.0) {
+                discard;
+            }
+
+            gl_FragColor = vec4(_color.rgb, _color.a * (1.0 - d * d));
+        }
+        "#,
+    )
+}
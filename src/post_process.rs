@@ -0,0 +1,687 @@
+use getset::{
+    Getters,
+    Setters,
+};
+use web_sys::WebGlRenderingContext;
+
+use crate::{
+    context::{
+        Context,
+        RenderTarget,
+    },
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        framebuffer::{
+            Framebuffer,
+            FramebufferDescriptorBuilder,
+            TextureFormat,
+            RGBA8,
+        },
+        program::{
+            Float,
+            Program,
+            Sampler2D,
+            Uniform,
+            Vec2,
+        },
+    },
+};
+
+type GL = WebGlRenderingContext;
+
+/// Settings of the post-processing passes. A pass whose amount is 0 is skipped.
+#[derive(Clone, Debug, Getters, Setters)]
+pub struct PostProcessSettings
+{
+    /// Enables post-processing. When disabled the scene is drawn straight to the canvas.
+    #[getset(get = "pub", set = "pub")]
+    enabled: bool,
+
+    /// Intensity of the glow added around bright parts of the scene.
+    #[getset(get = "pub", set = "pub")]
+    bloom: f32,
+
+    /// Brightness above which parts of the scene glow, from 0 to 1.
+    #[getset(get = "pub", set = "pub")]
+    bloom_threshold: f32,
+
+    /// How dark the gaps between scanlines are, from 0 to 1.
+    #[getset(get = "pub", set = "pub")]
+    scanlines: f32,
+
+    /// How much the screen bulges like the glass of a CRT.
+    #[getset(get = "pub", set = "pub")]
+    curvature: f32,
+
+    /// How far apart the red and blue channels are at the edges of the screen, in texture
+    /// coordinates.
+    #[getset(get = "pub", set = "pub")]
+    chromatic_aberration: f32,
+
+    /// How dark the corners of the screen are, from 0 to 1.
+    #[getset(get = "pub", set = "pub")]
+    vignette: f32,
+}
+
+impl Default for PostProcessSettings
+{
+    /// Post-processing is disabled by default, the amounts are tuned for the look of a vector
+    /// display once it is enabled.
+    fn default() -> PostProcessSettings
+    {
+        PostProcessSettings {
+            enabled: false,
+            bloom: 0.8,
+            bloom_threshold: 0.5,
+            scanlines: 0.25,
+            curvature: 0.08,
+            chromatic_aberration: 0.002,
+            vignette: 0.35,
+        }
+    }
+}
+
+/// Where a pass draws to.
+enum Output<'a>
+{
+    Target(&'a Framebuffer),
+
+    /// The render target that was current when the chain started.
+    Current,
+}
+
+/// A rectangle covering the whole viewport.
+struct FullscreenQuad
+{
+    layout: VertexLayout,
+    buffer: ArrayBuffer<f32>,
+}
+
+impl FullscreenQuad
+{
+    fn new(gl: &dyn GraphicsDevice) -> Result<FullscreenQuad, String>
+    {
+        let layout = VertexLayout::new().attribute("position", 2, VertexAttributeType::Float);
+        let buffer = ArrayBuffer::with_data(
+            gl,
+            BufferUsage::StaticDraw,
+            &[-1., -1., 1., -1., -1., 1., 1., 1.],
+        )?;
+
+        Ok(FullscreenQuad { layout, buffer })
+    }
+
+    fn draw(&self, gl: &dyn GraphicsDevice, program: &Program)
+    {
+        program.enable_vertex_layout(gl, &self.layout, &self.buffer, 0);
+        gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
+        program.disable_vertex_layout(gl, &self.layout);
+    }
+}
+
+/// Copies the source unchanged.
+struct CopyPass
+{
+    program: Program,
+    source: Uniform<Sampler2D>,
+}
+
+/// One direction of a separable gaussian blur of the parts of the source brighter than a
+/// threshold.
+struct BlurPass
+{
+    program: Program,
+    source: Uniform<Sampler2D>,
+    direction: Uniform<Vec2>,
+    threshold: Uniform<Float>,
+}
+
+/// Adds the blurred bright parts of the scene to the scene.
+struct BloomCompositePass
+{
+    program: Program,
+    source: Uniform<Sampler2D>,
+    bloom: Uniform<Sampler2D>,
+    intensity: Uniform<Float>,
+}
+
+struct ChromaticAberrationPass
+{
+    program: Program,
+    source: Uniform<Sampler2D>,
+    amount: Uniform<Float>,
+}
+
+/// Scanlines on a curved screen.
+struct CrtPass
+{
+    program: Program,
+    source: Uniform<Sampler2D>,
+    resolution: Uniform<Vec2>,
+    scanlines: Uniform<Float>,
+    curvature: Uniform<Float>,
+}
+
+struct VignettePass
+{
+    program: Program,
+    source: Uniform<Sampler2D>,
+    amount: Uniform<Float>,
+}
+
+/// The passes of the chain, in the order they are applied after `Copy`, which is only used when no
+/// other pass is enabled.
+#[derive(Clone, Copy, Debug)]
+enum Pass
+{
+    Copy,
+    Bloom,
+    ChromaticAberration,
+    Crt,
+    Vignette,
+}
+
+/// A chain of post-processing passes applied to the rendered scene.
+///
+/// The scene is rendered into an offscreen texture, then every enabled pass reads the output of
+/// the previous one and renders into the other of two ping-pong textures. The last pass renders
+/// into the render target that was current, usually the canvas. Bloom blurs at half resolution in
+/// two textures of its own.
+pub struct PostProcessor
+{
+    quad: FullscreenQuad,
+    targets: [Framebuffer; 2],
+    bloom_targets: [Framebuffer; 2],
+    copy: CopyPass,
+    blur: BlurPass,
+    bloom_composite: BloomCompositePass,
+    chromatic_aberration: ChromaticAberrationPass,
+    crt: CrtPass,
+    vignette: VignettePass,
+}
+
+impl PostProcessor
+{
+    pub fn new(context: &Context) -> Result<PostProcessor, String>
+    {
+        let gl = context.render_context();
+
+        let quad = FullscreenQuad::new(gl)?;
+        let layouts = [&quad.layout];
+        let vertex_shader = vertex_shader(gl)?;
+        let compile = |source: &str| -> Result<Program, String> {
+            Program::new(
+                gl,
+                &vertex_shader,
+                &gl::compile_fragment_shader(gl, source)?,
+                &layouts,
+            )
+        };
+
+        let program = compile(COPY_FRAGMENT_SHADER)?;
+        let copy = CopyPass {
+            source: program.uniform("source")?,
+            program,
+        };
+
+        let program = compile(BLUR_FRAGMENT_SHADER)?;
+        let blur = BlurPass {
+            source: program.uniform("source")?,
+            direction: program.uniform("direction")?,
+            threshold: program.uniform("threshold")?,
+            program,
+        };
+
+        let program = compile(BLOOM_COMPOSITE_FRAGMENT_SHADER)?;
+        let bloom_composite = BloomCompositePass {
+            source: program.uniform("source")?,
+            bloom: program.uniform("bloom")?,
+            intensity: program.uniform("intensity")?,
+            program,
+        };
+
+        let program = compile(CHROMATIC_ABERRATION_FRAGMENT_SHADER)?;
+        let chromatic_aberration = ChromaticAberrationPass {
+            source: program.uniform("source")?,
+            amount: program.uniform("amount")?,
+            program,
+        };
+
+        let program = compile(CRT_FRAGMENT_SHADER)?;
+        let crt = CrtPass {
+            source: program.uniform("source")?,
+            resolution: program.uniform("resolution")?,
+            scanlines: program.uniform("scanlines")?,
+            curvature: program.uniform("curvature")?,
+            program,
+        };
+
+        let program = compile(VIGNETTE_FRAGMENT_SHADER)?;
+        let vignette = VignettePass {
+            source: program.uniform("source")?,
+            amount: program.uniform("amount")?,
+            program,
+        };
+
+        let (width, height) = target_size(context);
+        let (bloom_width, bloom_height) = bloom_target_size(context);
+
+        // The bloom keeps its precision in half float textures where they can be rendered into.
+        let bloom_format = context
+            .render_context()
+            .float_texture_format()
+            .unwrap_or(RGBA8);
+
+        Ok(PostProcessor {
+            quad,
+            targets: [
+                framebuffer(gl, RGBA8, width, height)?,
+                framebuffer(gl, RGBA8, width, height)?,
+            ],
+            bloom_targets: [
+                framebuffer(gl, bloom_format, bloom_width, bloom_height)?,
+                framebuffer(gl, bloom_format, bloom_width, bloom_height)?,
+            ],
+            copy,
+            blur,
+            bloom_composite,
+            chromatic_aberration,
+            crt,
+            vignette,
+        })
+    }
+
+    /// Reallocates the render targets to fit the current canvas size of `context`.
+    pub fn resize(&mut self, context: &Context) -> Result<(), String>
+    {
+        let gl = context.render_context();
+
+        let (width, height) = target_size(context);
+        for target in self.targets.iter_mut() {
+            target.resize(gl, width, height)?;
+        }
+
+        let (width, height) = bloom_target_size(context);
+        for target in self.bloom_targets.iter_mut() {
+            target.resize(gl, width, height)?;
+        }
+
+        Ok(())
+    }
+
+    /// Performs `lambda`, which renders the scene, and applies the enabled passes of `settings` to
+    /// what it rendered.
+    pub fn render<F: FnOnce()>(&self, context: &Context, settings: &PostProcessSettings, lambda: F)
+    {
+        let passes = passes(settings);
+        if passes.is_empty() {
+            lambda();
+            return;
+        }
+
+        let gl = context.render_context();
+
+        context.with_render_target(RenderTarget::framebuffer(&self.targets[0]), lambda);
+
+        gl.disable(GL::BLEND);
+
+        let mut source = 0;
+        for (i, pass) in passes.iter().enumerate() {
+            let output = if i + 1 == passes.len() {
+                Output::Current
+            } else {
+                Output::Target(&self.targets[1 - source])
+            };
+
+            self.apply(context, settings, *pass, &self.targets[source], output);
+            source = 1 - source;
+        }
+
+        gl.enable(GL::BLEND);
+    }
+
+    fn apply(
+        &self,
+        context: &Context,
+        settings: &PostProcessSettings,
+        pass: Pass,
+        source: &Framebuffer,
+        output: Output,
+    )
+    {
+        let gl = context.render_context();
+
+        if let Pass::Bloom = pass {
+            self.blur_bright_parts(context, settings, source);
+        }
+
+        if let Output::Target(target) = output {
+            context.push_render_target(RenderTarget::framebuffer(target));
+        }
+
+        gl.active_texture(GL::TEXTURE0);
+        source.texture().bind(gl);
+
+        let program = match pass {
+            Pass::Copy => {
+                let pass = &self.copy;
+                pass.program.use_program(gl);
+                pass.source.set(gl, &0);
+                &pass.program
+            }
+            Pass::Bloom => {
+                let pass = &self.bloom_composite;
+                pass.program.use_program(gl);
+                pass.source.set(gl, &0);
+                pass.bloom.set(gl, &1);
+                pass.intensity.set(gl, settings.bloom());
+
+                gl.active_texture(GL::TEXTURE1);
+                self.bloom_targets[1].texture().bind(gl);
+                gl.active_texture(GL::TEXTURE0);
+
+                &pass.program
+            }
+            Pass::ChromaticAberration => {
+                let pass = &self.chromatic_aberration;
+                pass.program.use_program(gl);
+                pass.source.set(gl, &0);
+                pass.amount.set(gl, settings.chromatic_aberration());
+                &pass.program
+            }
+            Pass::Crt => {
+                let pass = &self.crt;
+                pass.program.use_program(gl);
+                pass.source.set(gl, &0);
+                pass.resolution
+                    .set(gl, &[*source.width() as f32, *source.height() as f32]);
+                pass.scanlines.set(gl, settings.scanlines());
+                pass.curvature.set(gl, settings.curvature());
+                &pass.program
+            }
+            Pass::Vignette => {
+                let pass = &self.vignette;
+                pass.program.use_program(gl);
+                pass.source.set(gl, &0);
+                pass.amount.set(gl, settings.vignette());
+                &pass.program
+            }
+        };
+
+        self.quad.draw(gl, program);
+
+        //
+        // Clean-up.
+        //
+        if let Pass::Bloom = pass {
+            gl.active_texture(GL::TEXTURE1);
+            self.bloom_targets[1].texture().unbind(gl);
+            gl.active_texture(GL::TEXTURE0);
+        }
+        source.texture().unbind(gl);
+        program.unuse_program(gl);
+
+        if let Output::Target(_) = output {
+            context.pop_render_target();
+        }
+    }
+
+    /// Blurs the parts of `source` brighter than the bloom threshold into the second bloom target,
+    /// horizontally into the first bloom target then vertically into the second.
+    fn blur_bright_parts(
+        &self,
+        context: &Context,
+        settings: &PostProcessSettings,
+        source: &Framebuffer,
+    )
+    {
+        let gl = context.render_context();
+        let pass = &self.blur;
+        let [horizontal, vertical] = &self.bloom_targets;
+
+        pass.program.use_program(gl);
+        pass.source.set(gl, &0);
+        gl.active_texture(GL::TEXTURE0);
+
+        context.with_render_target(RenderTarget::framebuffer(horizontal), || {
+            source.texture().bind(gl);
+            pass.direction
+                .set(gl, &[1. / *horizontal.width() as f32, 0.]);
+            pass.threshold.set(gl, settings.bloom_threshold());
+            self.quad.draw(gl, &pass.program);
+        });
+
+        context.with_render_target(RenderTarget::framebuffer(vertical), || {
+            horizontal.texture().bind(gl);
+            pass.direction
+                .set(gl, &[0., 1. / *vertical.height() as f32]);
+            pass.threshold.set(gl, &0.);
+            self.quad.draw(gl, &pass.program);
+        });
+
+        horizontal.texture().unbind(gl);
+        pass.program.unuse_program(gl);
+    }
+}
+
+/// The passes enabled by `settings`, in the order they are applied. Empty when post-processing is
+/// disabled. When post-processing is enabled but every amount is 0 the scene is still copied to
+/// the current render target, so that toggling it doesn't change what the scene renders into.
+fn passes(settings: &PostProcessSettings) -> Vec<Pass>
+{
+    if !settings.enabled {
+        return Vec::new();
+    }
+
+    let mut passes: Vec<Pass> = [
+        (Pass::Bloom, settings.bloom),
+        (Pass::ChromaticAberration, settings.chromatic_aberration),
+        (Pass::Crt, settings.scanlines.max(settings.curvature)),
+        (Pass::Vignette, settings.vignette),
+    ]
+    .iter()
+    .filter(|(_, amount)| *amount > 0.)
+    .map(|(pass, _)| *pass)
+    .collect();
+
+    if passes.is_empty() {
+        passes.push(Pass::Copy);
+    }
+
+    passes
+}
+
+/// The size of the ping-pong targets, which is the size of the canvas.
+fn target_size(context: &Context) -> (i32, i32)
+{
+    (
+        *context.canvas_width() as i32,
+        *context.canvas_height() as i32,
+    )
+}
+
+/// The size of the bloom targets, half the size of the canvas.
+fn bloom_target_size(context: &Context) -> (i32, i32)
+{
+    let (width, height) = target_size(context);
+    ((width / 2).max(1), (height / 2).max(1))
+}
+
+/// A ping-pong or bloom target.
+fn framebuffer(
+    gl: &dyn GraphicsDevice,
+    format: TextureFormat,
+    width: i32,
+    height: i32,
+) -> Result<Framebuffer, String>
+{
+    Framebuffer::new(
+        gl,
+        FramebufferDescriptorBuilder::default()
+            .width(width)
+            .height(height)
+            .format(format)
+            .build()
+            .map_err(|error| format!("{}", error))?,
+    )
+}
+
+fn vertex_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
+{
+    gl::compile_vertex_shader(
+        context,
+        r#"
+        attribute vec2 position;
+
+        varying vec2 _texcoord;
+
+        void main()
+        {
+            gl_Position = vec4(position, 0.0, 1.0);
+            _texcoord = position * 0.5 + 0.5;
+        }
+        "#,
+    )
+}
+
+const COPY_FRAGMENT_SHADER: &str = r#"
+    precision mediump float;
+
+    uniform sampler2D source;
+
+    varying vec2 _texcoord;
+
+    void main()
+    {
+        gl_FragColor = texture2D(source, _texcoord);
+    }
+"#;
+
+const BLUR_FRAGMENT_SHADER: &str = r#"
+    precision mediump float;
+
+    uniform sampler2D source;
+
+    // One texel in the direction of the blur.
+    uniform vec2 direction;
+    uniform float threshold;
+
+    varying vec2 _texcoord;
+
+    vec3 bright(vec2 texcoord)
+    {
+        return max(texture2D(source, texcoord).rgb - threshold, 0.0);
+    }
+
+    void main()
+    {
+        // Gaussian weights with linear sampling between texels.
+        vec3 color = bright(_texcoord) * 0.227027;
+        color += bright(_texcoord + direction * 1.384615) * 0.316216;
+        color += bright(_texcoord - direction * 1.384615) * 0.316216;
+        color += bright(_texcoord + direction * 3.230769) * 0.070270;
+        color += bright(_texcoord - direction * 3.230769) * 0.070270;
+
+        gl_FragColor = vec4(color, 1.0);
+    }
+"#;
+
+const BLOOM_COMPOSITE_FRAGMENT_SHADER: &str = r#"
+    precision mediump float;
+
+    uniform sampler2D source;
+    uniform sampler2D bloom;
+    uniform float intensity;
+
+    varying vec2 _texcoord;
+
+    void main()
+    {
+        vec4 color = texture2D(source, _texcoord);
+        color.rgb += texture2D(bloom, _texcoord).rgb * intensity;
+
+        gl_FragColor = color;
+    }
+"#;
+
+const CHROMATIC_ABERRATION_FRAGMENT_SHADER: &str = r#"
+    precision mediump float;
+
+    uniform sampler2D source;
+    uniform float amount;
+
+    varying vec2 _texcoord;
+
+    void main()
+    {
+        // The channels separate more towards the edges of the screen.
+        vec2 offset = (_texcoord - 0.5) * 2.0 * amount;
+
+        vec4 color = texture2D(source, _texcoord);
+        color.r = texture2D(source, _texcoord + offset).r;
+        color.b = texture2D(source, _texcoord - offset).b;
+
+        gl_FragColor = color;
+    }
+"#;
+
+const CRT_FRAGMENT_SHADER: &str = r#"
+    precision mediump float;
+
+    uniform sampler2D source;
+    uniform vec2 resolution;
+    uniform float scanlines;
+    uniform float curvature;
+
+    varying vec2 _texcoord;
+
+    void main()
+    {
+        // Bulge the screen outwards, more the further from the middle.
+        vec2 position = _texcoord * 2.0 - 1.0;
+        position *= 1.0 + curvature * dot(position.yx, position.yx);
+        vec2 texcoord = position * 0.5 + 0.5;
+
+        if (texcoord.x < 0.0 || texcoord.x > 1.0 || texcoord.y < 0.0 || texcoord.y > 1.0) {
+            gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+            return;
+        }
+
+        vec4 color = texture2D(source, texcoord);
+
+        // A scanline every other device pixel.
+        float line = 0.5 + 0.5 * sin(texcoord.y * resolution.y * 3.141593);
+        color.rgb *= 1.0 - scanlines * line;
+
+        gl_FragColor = color;
+    }
+"#;
+
+const VIGNETTE_FRAGMENT_SHADER: &str = r#"
+    precision mediump float;
+
+    uniform sampler2D source;
+    uniform float amount;
+
+    varying vec2 _texcoord;
+
+    void main()
+    {
+        vec2 position = _texcoord * 2.0 - 1.0;
+
+        vec4 color = texture2D(source, _texcoord);
+        color.rgb *= clamp(1.0 - amount * 0.5 * dot(position, position), 0.0, 1.0);
+
+        gl_FragColor = color;
+    }
+"#;
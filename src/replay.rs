@@ -0,0 +1,430 @@
+use std::{
+    convert::TryInto,
+    vec,
+};
+
+use getset::Getters;
+
+use crate::{
+    input::InputState,
+    rock::SpawnRandomizedRocksAnywhere,
+    ship::{
+        Ship,
+        ShipBoost,
+        ShipControllerDescriptor,
+        ShipGun,
+    },
+    world::{
+        World,
+        WorldDescriptor,
+    },
+};
+
+const MAGIC: &[u8; 4] = b"SRRP";
+const VERSION: u16 = 3;
+
+/// Greatest number of frames a replay can decode to, six hours at 60 frames per second. Limits the
+/// memory taken by the frames of a replay, as a few bytes can encode a very long run.
+const MAX_FRAMES: u64 = 6 * 60 * 60 * 60;
+
+/// Set in the action bits of a frame that is followed by an aim target.
+const AIM_FLAG: u8 = 0x80;
+
+/// A recorded game.
+///
+/// A replay holds the descriptor the world was created from and the input state of every frame.
+/// Feeding the inputs back into a world created from the descriptor reproduces the game exactly.
+///
+/// The encoded format is little endian and starts with the magic bytes `SRRP` followed by a `u16`
+/// version. The frames are run-length encoded since the input rarely changes between frames. Replays
+/// of older versions are rejected, as the rules of the game have changed since they were recorded.
+#[derive(Clone, Debug, Getters)]
+pub struct Replay
+{
+    #[getset(get = "pub")]
+    descriptor: WorldDescriptor,
+
+    #[getset(get = "pub")]
+    frames: Vec<InputState>,
+}
+
+impl Replay
+{
+    pub fn encode(&self) -> Vec<u8>
+    {
+        let mut writer = Writer(Vec::new());
+
+        writer.bytes(MAGIC);
+        writer.u16(VERSION);
+        writer.descriptor(&self.descriptor);
+
+        let runs = self.frames.iter().fold(Vec::new(), |mut runs, input| {
+            match runs.last_mut() {
+                Some((length, last)) if last == input && *length < u32::MAX => *length += 1,
+                _ => runs.push((1, *input)),
+            }
+            runs
+        });
+
+        writer.u32(runs.len() as u32);
+        for (length, input) in runs {
+            writer.u32(length);
+            match input.aim() {
+                Some(target) => {
+                    writer.u8(input.bits() | AIM_FLAG);
+                    writer.vec2(&target);
+                }
+                None => writer.u8(input.bits()),
+            }
+        }
+
+        writer.0
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Replay, String>
+    {
+        let mut reader = Reader(data);
+
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(String::from("data is not a replay"));
+        }
+
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(format!("unsupported replay version {}", version));
+        }
+
+        let descriptor = reader.descriptor()?;
+
+        let mut frames = Vec::new();
+        let mut frame_count: u64 = 0;
+        for _ in 0..reader.u32()? {
+            let length = reader.u32()?;
+
+            frame_count += u64::from(length);
+            if frame_count > MAX_FRAMES {
+                return Err(format!("replay is longer than {} frames", MAX_FRAMES));
+            }
+            let bits = reader.u8()?;
+
+            let mut input = InputState::from_bits(bits);
+            if bits & AIM_FLAG != 0 {
+                input.set_aim(Some(reader.vec2()?));
+            }
+
+            frames.extend((0..length).map(|_| input));
+        }
+
+        if !reader.0.is_empty() {
+            return Err(format!("{} trailing bytes after replay", reader.0.len()));
+        }
+
+        Ok(Replay { descriptor, frames })
+    }
+}
+
+/// Records the input of every frame of a game.
+pub struct ReplayRecorder
+{
+    descriptor: WorldDescriptor,
+    frames: Vec<InputState>,
+}
+
+impl ReplayRecorder
+{
+    /// Creates a recorder for a world created from `descriptor`.
+    pub fn new(descriptor: &WorldDescriptor) -> ReplayRecorder
+    {
+        ReplayRecorder {
+            descriptor: descriptor.clone(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Records the input of the next frame.
+    pub fn record(&mut self, input: &InputState)
+    {
+        self.frames.push(*input);
+    }
+
+    /// A replay of all frames recorded so far.
+    pub fn replay(&self) -> Replay
+    {
+        Replay {
+            descriptor: self.descriptor.clone(),
+            frames: self.frames.clone(),
+        }
+    }
+}
+
+/// Plays a `Replay` by feeding its inputs into a world frame by frame.
+pub struct ReplayPlayer
+{
+    world: World,
+    frames: vec::IntoIter<InputState>,
+}
+
+impl ReplayPlayer
+{
+    pub fn new(replay: &Replay) -> ReplayPlayer
+    {
+        ReplayPlayer {
+            world: World::new(&replay.descriptor),
+            frames: replay.frames.clone().into_iter(),
+        }
+    }
+
+    pub fn world(&self) -> &World
+    {
+        &self.world
+    }
+
+    /// Steps the world with the input of the next frame. Returns the input or `None` if the replay
+    /// has finished.
+    pub fn step(&mut self) -> Option<InputState>
+    {
+        let input = self.frames.next()?;
+        self.world.step(&input);
+        Some(input)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Encoding.
+// -------------------------------------------------------------------------------------------------
+
+struct Writer(Vec<u8>);
+
+impl Writer
+{
+    fn bytes(&mut self, bytes: &[u8])
+    {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, value: u8)
+    {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16)
+    {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32)
+    {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64)
+    {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32)
+    {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn vec2(&mut self, vector: &[f32; 2])
+    {
+        self.f32(vector[0]);
+        self.f32(vector[1]);
+    }
+
+    fn descriptor(&mut self, descriptor: &WorldDescriptor)
+    {
+        self.u64(*descriptor.seed());
+
+        let ship = descriptor.ship();
+        self.vec2(ship.position());
+        self.vec2(ship.size());
+        self.f32(*ship.weight());
+        self.f32(*ship.yaw());
+        self.f32(*ship.tail_x());
+        self.f32(*ship.wing_angle());
+
+        let controller = descriptor.ship_controller();
+        self.f32(*controller.forward_acceleration());
+        self.f32(*controller.backward_acceleration());
+        self.f32(*controller.yaw_acceleration());
+        self.f32(*controller.energy_max());
+        self.f32(*controller.energy_regeneracy());
+
+        let boost = controller.boost();
+        self.f32(boost.speed_multiplier());
+        self.f32(*boost.cost());
+
+        let gun = controller.gun();
+        self.f32(*gun.bullet_speed());
+        self.u32(*gun.bullet_duration());
+        self.f32(*gun.energy_cost());
+        self.u32(*gun.period());
+
+        let spawner = descriptor.rock_spawner();
+        self.f32(spawner.size_range().start);
+        self.f32(spawner.size_range().end);
+        self.f32(spawner.speed_range().start);
+        self.f32(spawner.speed_range().end);
+        self.u32(*descriptor.rock_count());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a>
+{
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String>
+    {
+        if self.0.len() < n {
+            return Err(String::from("unexpected end of replay data"));
+        }
+        let (bytes, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String>
+    {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String>
+    {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String>
+    {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String>
+    {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, String>
+    {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn vec2(&mut self) -> Result<[f32; 2], String>
+    {
+        Ok([self.f32()?, self.f32()?])
+    }
+
+    fn descriptor(&mut self) -> Result<WorldDescriptor, String>
+    {
+        let seed = self.u64()?;
+
+        let ship = Ship::builder()
+            .position(self.vec2()?)
+            .size(self.vec2()?)
+            .weight(self.f32()?)
+            .yaw(self.f32()?)
+            .tail_x(self.f32()?)
+            .wing_angle(self.f32()?)
+            .build()
+            .map_err(|error| format!("{}", error))?;
+
+        let mut controller = ShipControllerDescriptor::builder();
+        controller
+            .forward_acceleration(self.f32()?)
+            .backward_acceleration(self.f32()?)
+            .yaw_acceleration(self.f32()?)
+            .energy_max(self.f32()?)
+            .energy_regeneracy(self.f32()?)
+            .boost(
+                ShipBoost::builder()
+                    .multiplier(self.f32()?)
+                    .cost(self.f32()?)
+                    .build()
+                    .map_err(|error| format!("{}", error))?,
+            )
+            .gun(
+                ShipGun::builder()
+                    .bullet_speed(self.f32()?)
+                    .bullet_duration(self.u32()?)
+                    .energy_cost(self.f32()?)
+                    .period(self.u32()?)
+                    .build()
+                    .map_err(|error| format!("{}", error))?,
+            );
+
+        let rock_spawner = SpawnRandomizedRocksAnywhere::builder()
+            .size_range(self.f32()?..self.f32()?)
+            .speed_range(self.f32()?..self.f32()?)
+            .build()
+            .map_err(|error| format!("{}", error))?;
+
+        WorldDescriptor::builder()
+            .seed(seed)
+            .ship(ship)
+            .ship_controller(controller.build().map_err(|error| format!("{}", error))?)
+            .rock_spawner(rock_spawner)
+            .rock_count(self.u32()?)
+            .build()
+            .map_err(|error| format!("{}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::{
+        input::Action,
+        scene,
+    };
+
+    #[test]
+    fn decoded_replay_reproduces_the_game()
+    {
+        let descriptor = scene::world_descriptor(1234).unwrap();
+        let mut world = World::new(&descriptor);
+        let mut recorder = ReplayRecorder::new(&descriptor);
+
+        for frame in 0..1200 {
+            let mut input = InputState::new();
+            input.insert(Action::Fire);
+            if frame % 90 < 30 {
+                input.insert(Action::ThrustForward);
+            }
+            if frame % 200 < 50 {
+                input.insert(Action::SteerClockwise);
+            }
+            if frame > 600 {
+                input.set_aim(Some([(frame % 4) as f32, 1.5]));
+            }
+
+            recorder.record(&input);
+            world.step(&input);
+        }
+
+        let replay = Replay::decode(&recorder.replay().encode()).unwrap();
+        let mut player = ReplayPlayer::new(&replay);
+        while player.step().is_some() {}
+
+        assert!(world.score() > 0);
+        assert_eq!(player.world().ship().position(), world.ship().position());
+        assert_eq!(player.world().rocks().len(), world.rocks().len());
+        assert_eq!(player.world().score(), world.score());
+    }
+
+    #[test]
+    fn decode_rejects_too_many_frames()
+    {
+        let descriptor = scene::world_descriptor(1234).unwrap();
+        let mut data = ReplayRecorder::new(&descriptor).replay().encode();
+
+        // Replace the count of runs by a single run as long as possible.
+        data.truncate(data.len() - 4);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.push(0);
+
+        assert!(Replay::decode(&data).is_err());
+    }
+}
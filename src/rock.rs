@@ -1,6 +1,6 @@
 mod renderer;
 mod rock;
-mod shape;
+pub mod shape;
 mod spawner;
 
 pub use renderer::RockRenderer;
@@ -4,145 +4,257 @@ use std::{
     iter,
 };
 
-use web_sys::{
-    WebGlBuffer,
-    WebGlProgram,
-    WebGlRenderingContext,
-    WebGlShader,
-};
+use web_sys::WebGlRenderingContext;
 
 use super::shape::RockShape;
 use crate::{
     context::Context,
-    gl,
-    matrix::{
-        Matrix4x4,
-        Scale,
-        Translate,
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        instanced::InstanceBuffer,
+        program::{
+            Mat4,
+            Program,
+            Uniform,
+            Vec4,
+        },
+    },
+    outline_renderer::{
+        Outline,
+        OutlineRenderer,
+        Stroke,
     },
     rock::Rock,
+    theme::ShapeStyle,
 };
 
-/// Renderer that renders a `Rock` into a canvas.
+/// Renderer that renders all `Rock`s of a world into a canvas, one draw call per shape.
 pub struct RockRenderer
 {
-    program: WebGlProgram,
-    vertex_buffers: HashMap<RockShape, WebGlBuffer>,
+    program: Program,
+    projection_matrix: Uniform<Mat4>,
+    color: Uniform<Vec4>,
+    vertex_layout: VertexLayout,
+    vertex_buffers: HashMap<RockShape, ArrayBuffer<f32>>,
+    instances: InstanceBuffer,
+    outlines: HashMap<RockShape, Outline>,
+    outline_renderer: OutlineRenderer,
 }
 
 impl RockRenderer
 {
-    pub fn new(context: &WebGlRenderingContext) -> Result<RockRenderer, String>
+    pub fn new(context: &Context) -> Result<RockRenderer, String>
     {
-        let program = gl::link_program(
-            context,
-            &vertex_shader(context)?,
-            &fragment_shader(context)?,
+        let gl = context.render_context();
+
+        let vertex_layout =
+            VertexLayout::new().attribute("position", 3, VertexAttributeType::Float);
+
+        // Every instance is positioned and scaled.
+        let instance_layout = VertexLayout::new()
+            .attribute("instance_position", 2, VertexAttributeType::Float)
+            .attribute("instance_size", 2, VertexAttributeType::Float);
+
+        let program = Program::new(
+            gl,
+            &vertex_shader(gl)?,
+            &fragment_shader(gl)?,
+            &[&vertex_layout, &instance_layout],
         )?;
 
         let vertex_buffers: Result<_, String> = RockShape::iter()
             .map(|shape| {
-                let buffer = gl::make_static_draw_array_buffer_f32(
-                    context,
-                    polygon_vertices(shape.sides()).unwrap(),
+                let buffer = ArrayBuffer::with_data(
+                    gl,
+                    BufferUsage::StaticDraw,
+                    &polygon_vertices(shape.sides()).unwrap(),
                 )?;
                 Ok((shape, buffer))
             })
             .collect();
         let vertex_buffers = vertex_buffers?;
 
+        let outlines: Result<_, String> = RockShape::iter()
+            .map(|shape| {
+                let outline = Outline::closed(gl, &polygon(shape.sides()))?;
+                Ok((shape, outline))
+            })
+            .collect();
+
         Ok(RockRenderer {
+            projection_matrix: program.uniform("projection_matrix")?,
+            color: program.uniform("color")?,
             program,
+            vertex_layout,
             vertex_buffers,
+            instances: InstanceBuffer::new(gl, instance_layout)?,
+            outlines: outlines?,
+            outline_renderer: OutlineRenderer::new(gl)?,
         })
     }
 
-    pub fn render(&self, context: &Context, rock: &Rock)
+    /// Renders `rocks` in the style of the theme of `context`.
+    pub fn render(&mut self, context: &Context, rocks: &[Rock])
     {
-        let gl = context.render_context();
-
-        gl.use_program(Some(&self.program));
-
-        gl.bind_buffer(
-            WebGlRenderingContext::ARRAY_BUFFER,
-            Some(&self.vertex_buffers[rock.shape()]),
-        );
+        if let ShapeStyle::Vector { stroke_width, glow } = context.theme().shapes {
+            let stroke = Stroke {
+                color: context.theme().rocks.outline,
+                width: stroke_width,
+                glow,
+            };
+            self.render_outlines(context, rocks, &stroke);
+            return;
+        }
 
-        gl.enable_vertex_attrib_array(0);
-        gl.vertex_attrib_pointer_with_i32(0, 3, WebGlRenderingContext::FLOAT, false, 0, 0);
+        let gl = context.render_context();
 
         //
-        // Position and scale the rock with a world matrix.
+        // Upload the instances grouped by shape, so that each shape is drawn at once.
         //
-        let matrix = Scale::id()
-            .vec2(rock.size())
-            .into_arr2()
-            .dot(&Translate::id().vec2(rock.position()).into_arr2());
+        let mut data = Vec::with_capacity(rocks.len() * 4);
+        let mut groups = Vec::new();
 
-        let location = gl.get_uniform_location(&self.program, "world_matrix");
+        for shape in RockShape::iter() {
+            let start = data.len() / 4;
+            for rock in rocks.iter().filter(|rock| *rock.shape() == shape) {
+                data.extend_from_slice(rock.position());
+                data.extend_from_slice(rock.size());
+            }
+            groups.push((shape, start..data.len() / 4));
+        }
 
-        gl.uniform_matrix4fv_with_f32_array(
-            location.as_ref(),
-            false,
-            matrix.view().as_slice().unwrap(),
-        );
+        self.instances.set_data(gl, data);
 
         //
-        // Setup the projection matrix.
+        // Setup the program.
         //
-        let location = gl.get_uniform_location(&self.program, "projection_matrix");
-        let matrix = context.foreground_projection_matrix().clone().into_array();
+        self.program.use_program(gl);
 
-        gl.uniform_matrix4fv_with_f32_array(location.as_ref(), false, &matrix);
+        let matrix = context.foreground_matrix();
+        self.projection_matrix.set(gl, &matrix);
 
         //
-        // Draw.
+        // Draw, then draw the outlines through the vertices after the center of the fans.
         //
-        gl.draw_arrays(
-            WebGlRenderingContext::TRIANGLE_FAN,
-            0,
-            rock.shape().sides() as i32 + 2,
-        );
+        let colors = &context.theme().rocks;
+        let outlined = colors.outline[3] > 0.;
+
+        for (shape, instances) in groups {
+            let vertex_buffer = &self.vertex_buffers[&shape];
+
+            self.color.set(gl, &colors.fill);
+            self.program
+                .enable_vertex_layout(gl, &self.vertex_layout, vertex_buffer, 0);
+            self.instances.draw(
+                gl,
+                &self.program,
+                WebGlRenderingContext::TRIANGLE_FAN,
+                shape.sides() as i32 + 2,
+                instances.clone(),
+            );
+
+            if outlined {
+                self.color.set(gl, &colors.outline);
+                self.program
+                    .enable_vertex_layout(gl, &self.vertex_layout, vertex_buffer, 3 * 4);
+                self.instances.draw(
+                    gl,
+                    &self.program,
+                    WebGlRenderingContext::LINE_STRIP,
+                    shape.sides() as i32 + 1,
+                    instances,
+                );
+            }
+        }
 
         //
         // Clean-up.
         //
-        gl.use_program(None);
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, None);
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
     }
 }
 
-fn vertex_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
+impl RockRenderer
+{
+    /// Renders the outlines of `rocks`, one draw call per shape.
+    fn render_outlines(&mut self, context: &Context, rocks: &[Rock], stroke: &Stroke)
+    {
+        for shape in RockShape::iter() {
+            let mut instances = Vec::new();
+            for rock in rocks.iter().filter(|rock| *rock.shape() == shape) {
+                instances.extend_from_slice(rock.position());
+                instances.extend_from_slice(rock.size());
+                instances.extend_from_slice(&[1., 0.]);
+            }
+
+            if !instances.is_empty() {
+                self.outline_renderer
+                    .render(context, &self.outlines[&shape], instances, stroke);
+            }
+        }
+    }
+}
+
+fn vertex_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
 {
     gl::compile_vertex_shader(
         context,
         r#"
         attribute vec4 position;
+        attribute vec2 instance_position;
+        attribute vec2 instance_size;
 
-        uniform mat4 world_matrix;
         uniform mat4 projection_matrix;
 
         void main()
         {
-            gl_Position = projection_matrix * world_matrix * position;
+            vec4 p = position;
+            p.xy = p.xy * instance_size + instance_position;
+
+            gl_Position = projection_matrix * p;
         }
         "#,
     )
 }
 
-fn fragment_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
+fn fragment_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
 {
     gl::compile_fragment_shader(
         context,
         r#"
+        precision mediump float;
+
+        uniform vec4 color;
+
         void main()
         {
-            gl_FragColor = vec4(0.10, 0.10, 0.05, 1.00);
+            gl_FragColor = color;
         }
         "#,
     )
 }
 
+/// The corners of a regular polygon with `n` sides inscribed in the unit circle.
+fn polygon(n: u32) -> Vec<[f32; 2]>
+{
+    let r = 2.0 * PI * (1.0 / (n as f32));
+    (0..n)
+        .map(|i| i as f32 * r)
+        .map(|r| [r.cos(), r.sin()])
+        .collect()
+}
+
 fn polygon_vertices(n: u32) -> Option<Vec<f32>>
 {
     (n > 2).then(|| {
@@ -163,3 +275,154 @@ fn xyz<T>(x: T, y: T, z: T) -> impl Iterator<Item = T>
 {
     iter::once(x).chain(iter::once(y)).chain(iter::once(z))
 }
+
+#[cfg(test)]
+mod tests
+{
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        gl::{
+            device::Capabilities,
+            recording::{
+                Command,
+                RecordingDevice,
+            },
+        },
+        rock::RockDescriptor,
+        theme::Theme,
+    };
+
+    type GL = WebGlRenderingContext;
+
+    fn rock(shape: RockShape, position: [f32; 2]) -> Rock
+    {
+        Rock::new(
+            &RockDescriptor::builder()
+                .shape(shape)
+                .size([0.2, 0.2])
+                .position(position)
+                .velocity([0., 0.])
+                .build()
+                .unwrap(),
+        )
+    }
+
+    /// Renders two pentagons and an octagon, returning the renderer and the commands it issued
+    /// to render them.
+    fn render(device: RecordingDevice, theme: Theme) -> (RockRenderer, Vec<Command>)
+    {
+        let device = Rc::new(device);
+        let mut context = Context::with_device(device.clone(), 800, 600);
+        context.set_theme(theme);
+
+        let mut renderer = RockRenderer::new(&context).unwrap();
+        let rocks = [
+            rock(RockShape::Pentagon, [1., 1.]),
+            rock(RockShape::Octagon, [2., 1.]),
+            rock(RockShape::Pentagon, [3., 2.]),
+        ];
+
+        device.take_commands();
+        renderer.render(&context, &rocks);
+        (renderer, device.take_commands())
+    }
+
+    fn draws(commands: &[Command]) -> Vec<Command>
+    {
+        commands
+            .iter()
+            .filter(|command| {
+                matches!(
+                    command,
+                    Command::DrawArrays { .. } | Command::DrawArraysInstanced { .. }
+                )
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn fan(count: i32, instances: i32) -> Command
+    {
+        Command::DrawArraysInstanced {
+            mode: GL::TRIANGLE_FAN,
+            first: 0,
+            count,
+            instances,
+        }
+    }
+
+    #[test]
+    fn each_shape_is_drawn_with_one_instanced_fan()
+    {
+        let (_, commands) = render(RecordingDevice::new(), Theme::classic());
+
+        assert_eq!(draws(&commands), [fan(5 + 2, 2), fan(8 + 2, 1)]);
+        assert!(commands.contains(&Command::BufferData {
+            target: GL::ARRAY_BUFFER,
+            len: 3 * 4 * 4,
+            usage: GL::DYNAMIC_DRAW,
+        }));
+    }
+
+    #[test]
+    fn each_rock_is_drawn_with_its_own_fan_without_instancing()
+    {
+        let (renderer, commands) = render(
+            RecordingDevice::with_capabilities(Capabilities::none()),
+            Theme::classic(),
+        );
+
+        let fan = |count| Command::DrawArrays {
+            mode: GL::TRIANGLE_FAN,
+            first: 0,
+            count,
+        };
+        assert_eq!(draws(&commands), [fan(7), fan(7), fan(10)]);
+
+        let location = renderer.program.attribute("instance_position");
+        let positions: Vec<Vec<f32>> = commands
+            .iter()
+            .filter_map(|command| match command {
+                Command::VertexAttrib { location: l, value } if *l == location => {
+                    Some(value.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(positions, [[1., 1.], [3., 2.], [2., 1.]]);
+    }
+
+    #[test]
+    fn outlines_are_drawn_through_the_vertices_after_the_center()
+    {
+        let (renderer, commands) = render(RecordingDevice::new(), Theme::high_contrast());
+
+        let strip = |count, instances| Command::DrawArraysInstanced {
+            mode: GL::LINE_STRIP,
+            first: 0,
+            count,
+            instances,
+        };
+        assert_eq!(
+            draws(&commands),
+            [
+                fan(5 + 2, 2),
+                strip(5 + 1, 2),
+                fan(8 + 2, 1),
+                strip(8 + 1, 1)
+            ]
+        );
+
+        let location = renderer.program.attribute("position");
+        assert!(commands.contains(&Command::VertexAttribPointer {
+            location,
+            components: 3,
+            type_: GL::FLOAT,
+            normalized: false,
+            stride: 3 * 4,
+            offset: 3 * 4,
+        }));
+    }
+}
@@ -3,15 +3,23 @@ use std::{
     ops::Range,
 };
 
-use rand::Rng;
+use getset::Getters;
+use rand::{
+    distributions::Distribution,
+    Rng,
+};
 use vecmath::vec2_scale;
 
 use crate::rock::RockDescriptor;
 
-#[derive(Builder, Debug)]
+/// Distribution of rocks with random shape, size and velocity placed anywhere in the foreground.
+#[derive(Builder, Clone, Debug, Getters)]
 pub struct SpawnRandomizedRocksAnywhere
 {
+    #[getset(get = "pub")]
     size_range: Range<f32>,
+
+    #[getset(get = "pub")]
     speed_range: Range<f32>,
 }
 
@@ -23,28 +31,22 @@ impl SpawnRandomizedRocksAnywhere
     }
 }
 
-impl Iterator for SpawnRandomizedRocksAnywhere
+impl Distribution<RockDescriptor> for SpawnRandomizedRocksAnywhere
 {
-    type Item = RockDescriptor;
-
-    fn next(&mut self) -> Option<Self::Item>
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> RockDescriptor
     {
-        let mut rng = rand::thread_rng();
-
         let size = rng.gen_range(self.size_range.clone());
 
         let speed = rng.gen_range(self.speed_range.clone());
         let direction = rng.gen_range(0. ..PI * 2.);
         let velocity = vec2_scale([direction.cos(), direction.sin()], speed);
 
-        Some(
-            RockDescriptor::builder()
-                .shape(rng.gen())
-                .position([rng.gen_range(0. ..4.), rng.gen_range(0. ..3.)])
-                .size([size, size])
-                .velocity(velocity)
-                .build()
-                .unwrap(),
-        )
+        RockDescriptor::builder()
+            .shape(rng.gen())
+            .position([rng.gen_range(0. ..4.), rng.gen_range(0. ..3.)])
+            .size([size, size])
+            .velocity(velocity)
+            .build()
+            .unwrap()
     }
 }
@@ -1,16 +1,28 @@
 use std::{
-    cell::RefCell,
+    cell::{
+        Cell,
+        RefCell,
+    },
     rc::Rc,
 };
 
 use wasm_bindgen::{
     closure::Closure,
     JsCast,
+    JsValue,
 };
 
 use crate::dom;
 
-pub struct RunLoop(Rc<RefCell<Option<Closure<dyn FnMut()>>>>);
+type Callback = Closure<dyn FnMut()>;
+
+pub struct RunLoop
+{
+    callback: Rc<RefCell<Option<Callback>>>,
+
+    /// Id of the pending animation frame request, `None` if the loop is stopped.
+    request: Rc<Cell<Option<i32>>>,
+}
 
 impl RunLoop
 {
@@ -19,23 +31,60 @@ impl RunLoop
         let run0 = Rc::new(RefCell::new(None));
         let run1 = Rc::clone(&run0);
 
-        *run0.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-            lambda();
-            request_animation_frame(run1.borrow().as_ref().unwrap());
+        let request = Rc::new(Cell::new(None));
+
+        *run0.borrow_mut() = Some(Closure::wrap(Box::new({
+            let request = Rc::clone(&request);
+            move || {
+                lambda();
+
+                // The loop might have been stopped by `lambda`.
+                if request.get().is_some() {
+                    request.set(request_animation_frame(run1.borrow().as_ref().unwrap()).ok());
+                }
+            }
         }) as Box<dyn FnMut()>));
 
-        RunLoop(run0)
+        RunLoop {
+            callback: run0,
+            request,
+        }
+    }
+
+    pub fn start(&self) -> Result<(), JsValue>
+    {
+        if self.request.get().is_none() {
+            let id = request_animation_frame(self.callback.borrow().as_ref().unwrap())?;
+            self.request.set(Some(id));
+        }
+        Ok(())
+    }
+
+    pub fn stop(&self)
+    {
+        if let Some(id) = self.request.take() {
+            dom::window().cancel_animation_frame(id).ok();
+        }
     }
 
-    pub fn start(&self)
+    pub fn is_running(&self) -> bool
     {
-        request_animation_frame(self.0.borrow().as_ref().unwrap())
+        self.request.get().is_some()
+    }
+}
+
+impl Drop for RunLoop
+{
+    fn drop(&mut self)
+    {
+        self.stop();
+
+        // The callback holds a reference to itself, take it to break the cycle.
+        self.callback.borrow_mut().take();
     }
 }
 
-fn request_animation_frame(f: &Closure<dyn FnMut()>)
+fn request_animation_frame(f: &Callback) -> Result<i32, JsValue>
 {
-    dom::window()
-        .request_animation_frame(f.as_ref().unchecked_ref())
-        .unwrap();
+    dom::window().request_animation_frame(f.as_ref().unchecked_ref())
 }
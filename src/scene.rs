@@ -0,0 +1,809 @@
+use std::f32::consts::PI;
+
+use web_sys::WebGlRenderingContext;
+
+use crate::{
+    accessibility::{
+        AccessibilitySettings,
+        InputAssist,
+    },
+    background::Background,
+    bullet_renderer::BulletRenderer,
+    camera::Camera,
+    clock::SimulationClock,
+    context::Context,
+    debug_overlay::DebugOverlay,
+    foreground_renderer::{
+        canvas_to_foreground,
+        ForegroundRenderer,
+    },
+    hud::Hud,
+    input::{
+        Action,
+        Command,
+        InputState,
+        KeyBindings,
+    },
+    matrix::OrthographicProjection,
+    particle::{
+        ColorRamp,
+        Particle,
+        ParticleEffects,
+        ParticleEmitter,
+        ParticleSystem,
+        SizeCurve,
+    },
+    particle_renderer::ParticleRenderer,
+    post_process::{
+        PostProcessSettings,
+        PostProcessor,
+    },
+    replay::{
+        Replay,
+        ReplayPlayer,
+        ReplayRecorder,
+    },
+    rock::{
+        RockRenderer,
+        SpawnRandomizedRocksAnywhere,
+    },
+    ship::{
+        Ship,
+        ShipBoost,
+        ShipControllerDescriptor,
+        ShipGun,
+        ShipRenderer,
+    },
+    world::{
+        World,
+        WorldDescriptor,
+    },
+};
+
+/// Seed of the starfield of the background, the same in every world.
+const STARFIELD_SEED: u64 = 0x5741_5253;
+
+/// How a frame is rendered, apart from the world and the particles in it.
+pub struct FrameSettings<'a>
+{
+    pub post_process: &'a PostProcessSettings,
+    pub reduced_motion: bool,
+
+    /// Smoothed time between frames in milliseconds, shown with the debug overlay when set.
+    pub debug_overlay: Option<f64>,
+}
+
+/// Everything holding GPU resources. All of it becomes invalid when the WebGL context is lost and
+/// is created again when the context is restored.
+pub struct Renderers
+{
+    pub background: Background,
+    pub ship_renderer: ShipRenderer,
+    pub rock_renderer: RockRenderer,
+    pub bullet_renderer: BulletRenderer,
+    pub particle_renderer: ParticleRenderer,
+    pub foreground_renderer: ForegroundRenderer,
+    pub post_processor: PostProcessor,
+    pub hud: Hud,
+    pub debug_overlay: DebugOverlay,
+}
+
+impl Renderers
+{
+    pub fn new(context: &Context, world: &World) -> Result<Renderers, String>
+    {
+        let gl = context.render_context();
+
+        Ok(Renderers {
+            background: Background::new(gl, STARFIELD_SEED)?,
+            ship_renderer: ShipRenderer::new(gl, &world.ship())?,
+            rock_renderer: RockRenderer::new(context)?,
+            bullet_renderer: BulletRenderer::new(context)?,
+            particle_renderer: ParticleRenderer::new(context)?,
+            foreground_renderer: ForegroundRenderer::new(context)?,
+            post_processor: PostProcessor::new(context)?,
+            hud: Hud::new(context)?,
+            debug_overlay: DebugOverlay::new(context)?,
+        })
+    }
+
+    /// Resizes everything depending on the size of the canvas to the size of `context`.
+    pub fn resize(&mut self, context: &Context) -> Result<(), String>
+    {
+        self.foreground_renderer.resize(context)?;
+        self.post_processor.resize(context)
+    }
+
+    pub fn render(
+        &mut self,
+        context: &Context,
+        world: &World,
+        particles: &[Particle],
+        settings: &FrameSettings,
+    )
+    {
+        let gl = context.render_context();
+
+        gl.enable(WebGlRenderingContext::BLEND);
+        gl.blend_func(
+            WebGlRenderingContext::SRC_ALPHA,
+            WebGlRenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+
+        let rock_renderer = &mut self.rock_renderer;
+        let bullet_renderer = &mut self.bullet_renderer;
+        let particle_renderer = &mut self.particle_renderer;
+        let ship_renderer = &mut self.ship_renderer;
+        let debug_overlay = &mut self.debug_overlay;
+        let debug_overlay_enabled = settings.debug_overlay.is_some();
+
+        self.foreground_renderer
+            .with_render_target_foreground_texture(context, || {
+                context.clear();
+
+                // Uncomment to see better how the texture is rendered.
+                /*
+                gl.clear_color(0., 0., 1., 0.1);
+                gl.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+                */
+
+                rock_renderer.render(context, world.rocks());
+                particle_renderer.render(context, particles);
+                bullet_renderer.render(context, world.bullets());
+
+                ship_renderer.render(context, &world.ship());
+
+                if debug_overlay_enabled {
+                    debug_overlay.render_world(context, world);
+                }
+            });
+
+        //
+        // Compose the background and the foreground, through the post-processing passes. The HUD
+        // is drawn on top so that it stays legible.
+        //
+        let background = &mut self.background;
+        let foreground_renderer = &self.foreground_renderer;
+        background.reduced_motion = settings.reduced_motion;
+        background.update(&world.ship());
+
+        self.post_processor
+            .render(context, settings.post_process, || {
+                let [r, g, b, a] = context.theme().clear_color;
+                gl.clear_color(r, g, b, a);
+                gl.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+
+                background.render(context);
+                foreground_renderer.render(context);
+            });
+        self.hud.render(context, world);
+
+        if let Some(frame_time) = settings.debug_overlay {
+            self.debug_overlay
+                .render_panel(context, world, particles.len(), frame_time);
+        }
+    }
+}
+
+/// What the player did since the last frame, collected by a front end.
+#[derive(Clone, Debug, Default)]
+pub struct FrameInput
+{
+    /// Time of the frame in milliseconds, if the front end can tell.
+    pub time: Option<f64>,
+
+    /// Keys pressed since the last frame in the order they were pressed, without repeats. Keys
+    /// are identified by their character like `KeyboardEvent.key` in the browser.
+    pub keys_pressed: Vec<char>,
+
+    pub keys_held_down: Vec<char>,
+
+    /// Position of the cursor in device pixels, relative to the top left corner of the canvas.
+    pub mouse_position: Option<[f32; 2]>,
+
+    pub left_button_down: bool,
+}
+
+/// A game, independent of the front end it runs in.
+///
+/// Every frame the front end collects the input for `frame`, which performs the commands, steps
+/// the world, or the replay played instead, and renders it into the context. The front end then
+/// presents what was rendered.
+pub struct Scene
+{
+    /// Seed of every world, a random seed is used for each world if not set.
+    seed: Option<u64>,
+
+    pub renderers: Renderers,
+    pub clock: SimulationClock,
+    pub accessibility: AccessibilitySettings,
+
+    /// The ship turns towards the cursor and the left mouse button fires.
+    pub mouse_aim: bool,
+
+    pub post_process: PostProcessSettings,
+    pub debug_overlay_enabled: bool,
+
+    key_bindings: KeyBindings,
+    input_assist: InputAssist,
+    world: World,
+    particles: ParticleSystem,
+    camera: Camera,
+    recorder: ReplayRecorder,
+    replay_player: Option<ReplayPlayer>,
+
+    /// Time of the last frame in milliseconds.
+    last_frame: Option<f64>,
+
+    /// Smoothed time between frames in milliseconds.
+    frame_time: f64,
+}
+
+impl Scene
+{
+    pub fn new(
+        context: &Context,
+        seed: Option<u64>,
+        accessibility: AccessibilitySettings,
+    ) -> Result<Scene, String>
+    {
+        let world_descriptor = world_descriptor(seed.unwrap_or_else(rand::random))?;
+        let world = World::new(&world_descriptor);
+
+        let mut clock = SimulationClock::new();
+        clock.set_time_scale(*accessibility.game_speed());
+
+        Ok(Scene {
+            seed,
+            renderers: Renderers::new(context, &world)?,
+            clock,
+            accessibility,
+            mouse_aim: false,
+            post_process: PostProcessSettings::default(),
+            debug_overlay_enabled: false,
+            key_bindings: KeyBindings::default(),
+            input_assist: InputAssist::new(),
+            world,
+            particles: ParticleSystem::new(particle_effects()?),
+            camera: Camera::new(),
+            recorder: ReplayRecorder::new(&world_descriptor),
+            replay_player: None,
+            last_frame: None,
+            frame_time: 0.,
+        })
+    }
+
+    /// Replaces the world with a new one and starts a new recording.
+    pub fn restart(&mut self) -> Result<(), String>
+    {
+        let world_descriptor = world_descriptor(self.seed.unwrap_or_else(rand::random))?;
+
+        self.world = World::new(&world_descriptor);
+        self.particles.clear();
+        self.recorder = ReplayRecorder::new(&world_descriptor);
+        self.replay_player = None;
+
+        Ok(())
+    }
+
+    /// The replay of the current game.
+    pub fn recorded_replay(&self) -> Replay
+    {
+        self.recorder.replay()
+    }
+
+    /// Stops the current game and plays `replay` instead.
+    pub fn play_replay(&mut self, replay: &Replay)
+    {
+        self.replay_player = Some(ReplayPlayer::new(replay));
+        self.particles.clear();
+    }
+
+    /// Resizes the renderers to the canvas size of `context`.
+    pub fn resize(&mut self, context: &Context) -> Result<(), String>
+    {
+        self.renderers.resize(context)
+    }
+
+    /// Recreates the renderers after the context has been restored, keeping their state that
+    /// isn't on the GPU.
+    pub fn restore(&mut self, context: &Context) -> Result<(), String>
+    {
+        let background_position = self.renderers.background.position;
+
+        self.renderers = Renderers::new(context, &self.world)?;
+        self.renderers.background.position = background_position;
+
+        Ok(())
+    }
+
+    /// Performs the commands issued since last frame, steps the world and renders it into
+    /// `context`.
+    pub fn frame(&mut self, context: &mut Context, input: &FrameInput)
+    {
+        self.measure_frame_time(input.time);
+
+        for command in self
+            .key_bindings
+            .commands(input.keys_pressed.iter().copied())
+        {
+            match command {
+                Command::TogglePause => self.clock.toggle_pause(),
+                Command::StepFrame => self.clock.step_frame(),
+                Command::ToggleSlowMotion => self.clock.toggle_slow_motion(),
+                Command::ToggleDebugOverlay => {
+                    self.debug_overlay_enabled = !self.debug_overlay_enabled
+                }
+                Command::NextTheme => {
+                    let theme = context.theme().next();
+                    context.set_theme(theme);
+                }
+            }
+        }
+
+        let input_state = self
+            .key_bindings
+            .input_state(input.keys_held_down.iter().copied());
+        self.input_assist.sample(&self.accessibility, &input_state);
+
+        self.camera.reduced_motion = *self.accessibility.reduced_motion();
+        for _ in 0..self
+            .camera
+            .hold(self.clock.advance(), self.clock.is_paused())
+        {
+            self.step(context, input, input_state);
+        }
+
+        self.render(context);
+    }
+
+    /// Updates the smoothed frame time with the time since the last frame.
+    fn measure_frame_time(&mut self, time: Option<f64>)
+    {
+        let now = match time {
+            Some(time) => time,
+            None => return,
+        };
+
+        if let Some(last_frame) = self.last_frame {
+            self.frame_time += (now - last_frame - self.frame_time) * 0.1;
+        }
+        self.last_frame = Some(now);
+    }
+
+    /// Steps the world, either with the player's input or with the input of a replay, and the
+    /// particles and camera effects caused by it. A finished replay causes none.
+    fn step(&mut self, context: &Context, input: &FrameInput, input_state: InputState)
+    {
+        let events = match self.replay_player.as_mut() {
+            Some(player) => match player.step() {
+                Some(_) => player.world().events(),
+                None => &[],
+            },
+            None => {
+                self.step_world(context, input, input_state);
+                self.world.events()
+            }
+        };
+
+        self.particles.step(events);
+        self.camera.step(events);
+    }
+
+    /// Steps the world with the player's input.
+    fn step_world(&mut self, context: &Context, input: &FrameInput, mut input_state: InputState)
+    {
+        if self.mouse_aim {
+            input_state.set_aim(
+                input
+                    .mouse_position
+                    .map(|position| canvas_to_foreground(context, position)),
+            );
+            if input.left_button_down {
+                input_state.insert(Action::Fire);
+            }
+        }
+
+        self.input_assist
+            .apply(&self.accessibility, &mut input_state);
+
+        self.recorder.record(&input_state);
+        self.world.step(&input_state);
+    }
+
+    fn render(&mut self, context: &mut Context)
+    {
+        context.set_foreground_view(self.camera.update());
+
+        let world = match self.replay_player.as_ref() {
+            Some(player) => player.world(),
+            None => &self.world,
+        };
+
+        self.renderers.render(
+            context,
+            world,
+            self.particles.particles(),
+            &FrameSettings {
+                post_process: &self.post_process,
+                reduced_motion: *self.accessibility.reduced_motion(),
+                debug_overlay: match self.debug_overlay_enabled {
+                    true => Some(self.frame_time),
+                    false => None,
+                },
+            },
+        );
+    }
+}
+
+/// The projection of the foreground, which shows the world with a margin of one unit around it so
+/// that objects wrapping around its edges are drawn on both sides.
+pub fn foreground_projection_matrix() -> OrthographicProjection
+{
+    OrthographicProjection::default()
+        .abscissa(-1. ..5.)
+        .ordinate(-1. ..4.)
+        .build()
+}
+
+/// The particle effects of the world events.
+pub fn particle_effects() -> Result<ParticleEffects, String>
+{
+    let exhaust = |count, speed, colors| {
+        ParticleEmitter::builder()
+            .count(count)
+            .speed(speed)
+            .spread(PI / 6.)
+            .lifetime(15..30)
+            .colors(colors)
+            .sizes(SizeCurve {
+                start: 0.008,
+                end: 0.002,
+                exponent: 1.,
+            })
+            .build()
+            .map_err(|error| format!("{}", error))
+    };
+
+    ParticleEffects::builder()
+        .thrust(exhaust(
+            2,
+            0.005..0.01,
+            ColorRamp {
+                start: [1.0, 0.8, 0.4, 1.0],
+                end: [0.8, 0.2, 0.1, 0.0],
+            },
+        )?)
+        .boost(exhaust(
+            5,
+            0.01..0.02,
+            ColorRamp {
+                start: [0.6, 0.9, 1.0, 1.0],
+                end: [0.3, 0.3, 1.0, 0.0],
+            },
+        )?)
+        .impact(
+            ParticleEmitter::builder()
+                .count(8)
+                .speed(0.005..0.015)
+                .spread(PI / 2.)
+                .lifetime(8..16)
+                .colors(ColorRamp {
+                    start: [1.0, 1.0, 0.8, 1.0],
+                    end: [1.0, 0.6, 0.2, 0.0],
+                })
+                .sizes(SizeCurve {
+                    start: 0.005,
+                    end: 0.001,
+                    exponent: 1.,
+                })
+                .build()
+                .map_err(|error| format!("{}", error))?,
+        )
+        .explosion(
+            ParticleEmitter::builder()
+                .count(40)
+                .speed(0.001..0.008)
+                .spread(2. * PI)
+                .lifetime(30..60)
+                .colors(ColorRamp {
+                    start: [0.9, 0.8, 0.6, 1.0],
+                    end: [0.3, 0.2, 0.2, 0.0],
+                })
+                .sizes(SizeCurve {
+                    start: 0.012,
+                    end: 0.003,
+                    exponent: 0.5,
+                })
+                .build()
+                .map_err(|error| format!("{}", error))?,
+        )
+        .build()
+        .map_err(|error| format!("{}", error))
+}
+
+/// The descriptor of the worlds the game is played in.
+pub fn world_descriptor(seed: u64) -> Result<WorldDescriptor, String>
+{
+    WorldDescriptor::builder()
+        .seed(seed)
+        .ship(
+            Ship::builder()
+                .position([2., 3. / 2.])
+                .size([0.075, 0.075])
+                .weight(5. * 10e-3)
+                .yaw(PI / 4.)
+                .tail_x(-1. / 9.)
+                .wing_angle(23. / 36. * PI)
+                .build()
+                .map_err(|error| format!("{}", error))?,
+        )
+        .ship_controller(
+            ShipControllerDescriptor::builder()
+                .forward_acceleration(0.0025)
+                .backward_acceleration(0.0015)
+                .yaw_acceleration(PI / 77.)
+                .energy_max(100.)
+                .energy_regeneracy(0.5)
+                .boost(
+                    ShipBoost::builder()
+                        .multiplier(2.5)
+                        .cost(3.)
+                        .build()
+                        .map_err(|error| format!("{}", error))?,
+                )
+                .gun(
+                    ShipGun::builder()
+                        .bullet_duration(120)
+                        .bullet_speed(0.05)
+                        .energy_cost(15.)
+                        .period(15)
+                        .build()
+                        .map_err(|error| format!("{}", error))?,
+                )
+                .build()
+                .map_err(|error| format!("{}", error))?,
+        )
+        .rock_spawner(
+            SpawnRandomizedRocksAnywhere::builder()
+                .size_range(0.05..0.15)
+                .speed_range(10e-4..1.5 * 10e-3)
+                .build()
+                .map_err(|error| format!("{}", error))?,
+        )
+        .rock_count(11)
+        .build()
+        .map_err(|error| format!("{}", error))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::{
+        env,
+        fs,
+        path::PathBuf,
+        rc::Rc,
+    };
+
+    use super::*;
+    use crate::{
+        camera::CameraView,
+        gl::{
+            image::{
+                Image,
+                Tolerance,
+            },
+            software::SoftwareDevice,
+        },
+        replay::ReplayRecorder,
+        rock::{
+            shape::RockShape,
+            Rock,
+            RockDescriptor,
+        },
+        world::WorldEvent,
+    };
+
+    /// Mismatching pixels allowed for rounding differences of the rasterizer between platforms.
+    const TOLERANCE: Tolerance = Tolerance {
+        channel: 2,
+        pixels: 8,
+    };
+
+    /// Renders `rocks` and `ship` into the foreground texture filled with `fill`, then composes
+    /// the texture onto a canvas of `width` by `height` pixels, without background.
+    fn render(
+        width: u32,
+        height: u32,
+        fill: [f32; 4],
+        rocks: &[Rock],
+        ship: Option<&Ship>,
+    ) -> Rc<SoftwareDevice>
+    {
+        let device = Rc::new(SoftwareDevice::new(width, height));
+        let context = Context::with_device(device.clone(), width, height);
+        let world = World::new(&world_descriptor(1).unwrap());
+
+        let mut renderers = Renderers::new(&context, &world).unwrap();
+        let Renderers {
+            foreground_renderer,
+            rock_renderer,
+            ship_renderer,
+            ..
+        } = &mut renderers;
+
+        let gl = context.render_context();
+        gl.enable(WebGlRenderingContext::BLEND);
+        gl.blend_func(
+            WebGlRenderingContext::SRC_ALPHA,
+            WebGlRenderingContext::ONE_MINUS_SRC_ALPHA,
+        );
+
+        foreground_renderer.with_render_target_foreground_texture(&context, || {
+            let [r, g, b, a] = fill;
+            gl.clear_color(r, g, b, a);
+            gl.clear(WebGlRenderingContext::COLOR_BUFFER_BIT);
+
+            rock_renderer.render(&context, rocks);
+            if let Some(ship) = ship {
+                ship_renderer.render(&context, ship);
+            }
+        });
+
+        context.clear();
+        foreground_renderer.render(&context);
+
+        device
+    }
+
+    /// Checks that nothing was skipped and that the canvas of `device` matches the golden image
+    /// `tests/golden/<name>.png`, which is written instead when `UPDATE_GOLDEN_IMAGES` is set.
+    fn assert_golden(device: &SoftwareDevice, name: &str)
+    {
+        assert_eq!(device.skipped_draws(), 0);
+
+        let image = device.image();
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{}.png", name));
+
+        if env::var_os("UPDATE_GOLDEN_IMAGES").is_some() {
+            fs::write(&path, image.encode_png().unwrap()).unwrap();
+            return;
+        }
+
+        let bytes = fs::read(&path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+        let expected = Image::decode_png(&bytes).unwrap();
+        if let Err(error) = image.compare(&expected, TOLERANCE) {
+            panic!("{} doesn't match its golden image: {}", name, error);
+        }
+    }
+
+    fn rock(shape: RockShape, position: [f32; 2]) -> Rock
+    {
+        Rock::new(
+            &RockDescriptor::builder()
+                .shape(shape)
+                .size([0.6, 0.6])
+                .position(position)
+                .velocity([0., 0.])
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn rocks_are_filled_fans()
+    {
+        let rocks = [
+            rock(RockShape::Pentagon, [1., 1.5]),
+            rock(RockShape::Octagon, [3., 1.5]),
+        ];
+        let device = render(200, 150, [0.; 4], &rocks, None);
+
+        assert_golden(&device, "rock_fans");
+    }
+
+    #[test]
+    fn ship_is_filled()
+    {
+        let ship = Ship::builder()
+            .position([2., 1.5])
+            .size([0.8, 0.8])
+            .weight(0.05)
+            .yaw(PI / 4.)
+            .tail_x(-1. / 9.)
+            .wing_angle(23. / 36. * PI)
+            .build()
+            .unwrap();
+        let device = render(200, 150, [0.; 4], &[], Some(&ship));
+
+        assert_golden(&device, "ship");
+    }
+
+    /// The foreground is filled so that the bars around it are visible.
+    fn render_letterboxed(width: u32, height: u32) -> Rc<SoftwareDevice>
+    {
+        let world = World::new(&world_descriptor(1).unwrap());
+        let ship = world.ship();
+        render(
+            width,
+            height,
+            [0.1, 0.2, 0.4, 1.],
+            world.rocks(),
+            Some(&ship),
+        )
+    }
+
+    #[test]
+    fn wide_canvas_has_bars_on_the_sides()
+    {
+        assert_golden(&render_letterboxed(240, 120), "letterbox_wide");
+    }
+
+    #[test]
+    fn tall_canvas_has_bars_above_and_below()
+    {
+        assert_golden(&render_letterboxed(120, 240), "letterbox_tall");
+    }
+
+    /// A replay of the world of seed 1 with the ship thrusting and firing, which ends with the
+    /// step a rock is destroyed in.
+    fn replay_ending_with_rock_destroyed() -> Replay
+    {
+        let descriptor = world_descriptor(1).unwrap();
+        let mut world = World::new(&descriptor);
+        let mut recorder = ReplayRecorder::new(&descriptor);
+
+        let mut input = InputState::new();
+        input.insert(Action::ThrustForward);
+        input.insert(Action::SteerClockwise);
+        input.insert(Action::Fire);
+
+        for _ in 0..10_000 {
+            recorder.record(&input);
+            world.step(&input);
+
+            let rock_destroyed = world
+                .events()
+                .iter()
+                .any(|event| matches!(event, WorldEvent::RockDestroyed { .. }));
+            if rock_destroyed {
+                return recorder.replay();
+            }
+        }
+
+        panic!("no rock was destroyed");
+    }
+
+    #[test]
+    fn finished_replay_emits_no_particles_and_shakes_no_more()
+    {
+        let device = Rc::new(SoftwareDevice::new(64, 48));
+        let mut context = Context::with_device(device, 64, 48);
+        let mut scene = Scene::new(&context, Some(1), AccessibilitySettings::default()).unwrap();
+
+        let replay = replay_ending_with_rock_destroyed();
+        scene.play_replay(&replay);
+
+        // Hit-stops hold some of the frames, so more frames than steps are needed to finish.
+        for _ in 0..replay.frames().len() * 2 {
+            scene.frame(&mut context, &FrameInput::default());
+        }
+
+        let mut particles = scene.particles.particles().len();
+        for _ in 0..30 {
+            scene.frame(&mut context, &FrameInput::default());
+
+            assert!(scene.particles.particles().len() <= particles);
+            particles = scene.particles.particles().len();
+        }
+
+        // The trauma of the last step has decayed by now.
+        for _ in 0..30 {
+            scene.frame(&mut context, &FrameInput::default());
+        }
+        assert_eq!(scene.camera.update(), CameraView::default());
+    }
+}
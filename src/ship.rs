@@ -5,7 +5,10 @@ mod renderer;
 mod ship;
 
 pub use boost::ShipBoost;
-pub use controller::ShipController;
+pub use controller::{
+    ShipController,
+    ShipControllerDescriptor,
+};
 pub use gun::ShipGun;
 pub use renderer::ShipRenderer;
 pub use ship::Ship;
@@ -1,7 +1,11 @@
-#[derive(Builder, Clone, Debug)]
+use getset::Getters;
+
+#[derive(Builder, Clone, Debug, Getters)]
 pub struct ShipBoost
 {
     multiplier: f32,
+
+    #[getset(get = "pub")]
     cost: f32,
 
     #[builder(setter(skip), default = "false")]
@@ -18,6 +22,12 @@ impl ShipBoost
         ShipBoostBuilder::default()
     }
 
+    /// The speed multiplier applied while boosting, regardless of energy.
+    pub fn speed_multiplier(&self) -> f32
+    {
+        self.multiplier
+    }
+
     /// The speed multiplier for active boost.
     ///
     /// If boost is possible the cost of boosting one frame is subtracted from `energy` and the
@@ -41,6 +51,12 @@ impl ShipBoost
         }
     }
 
+    /// Whether the boost can be used with `energy`, or is disabled until it has been released.
+    pub fn is_available(&self, energy: f32) -> bool
+    {
+        self.enabled && energy > self.cost
+    }
+
     /// Set the active state of the boost.
     pub fn set(&mut self, state: bool)
     {
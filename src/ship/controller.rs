@@ -1,8 +1,11 @@
 use std::{
     cell::RefCell,
+    f32::consts::PI,
     rc::Weak,
 };
 
+use getset::Getters;
+
 use crate::{
     bullet::Bullet,
     ship::{
@@ -12,7 +15,41 @@ use crate::{
     },
 };
 
-#[derive(Builder, Debug)]
+/// The tuning parameters of a `ShipController`.
+#[derive(Builder, Clone, Debug, Getters)]
+pub struct ShipControllerDescriptor
+{
+    #[getset(get = "pub")]
+    forward_acceleration: f32,
+
+    #[getset(get = "pub")]
+    backward_acceleration: f32,
+
+    #[getset(get = "pub")]
+    yaw_acceleration: f32,
+
+    #[getset(get = "pub")]
+    energy_max: f32,
+
+    #[getset(get = "pub")]
+    energy_regeneracy: f32,
+
+    #[getset(get = "pub")]
+    boost: ShipBoost,
+
+    #[getset(get = "pub")]
+    gun: ShipGun,
+}
+
+impl ShipControllerDescriptor
+{
+    pub fn builder() -> ShipControllerDescriptorBuilder
+    {
+        ShipControllerDescriptorBuilder::default()
+    }
+}
+
+#[derive(Debug)]
 pub struct ShipController
 {
     ship: Weak<RefCell<Ship>>,
@@ -23,19 +60,28 @@ pub struct ShipController
     energy_regeneracy: f32,
     boost: ShipBoost,
     gun: ShipGun,
-
-    #[builder(setter(skip), default = "self.energy_max.unwrap()")]
     energy: f32,
-
-    #[builder(setter(skip), default = "1.")]
     boost_multiplier: f32,
+    boosting: bool,
 }
 
 impl ShipController
 {
-    pub fn builder() -> ShipControllerBuilder
+    pub fn new(ship: Weak<RefCell<Ship>>, descriptor: &ShipControllerDescriptor) -> ShipController
     {
-        ShipControllerBuilder::default()
+        ShipController {
+            ship,
+            forward_acceleration: descriptor.forward_acceleration,
+            backward_acceleration: descriptor.backward_acceleration,
+            yaw_acceleration: descriptor.yaw_acceleration,
+            energy_max: descriptor.energy_max,
+            energy_regeneracy: descriptor.energy_regeneracy,
+            boost: descriptor.boost.clone(),
+            gun: descriptor.gun.clone(),
+            energy: descriptor.energy_max,
+            boost_multiplier: 1.,
+            boosting: false,
+        }
     }
 
     pub fn thrust_forward(&mut self)
@@ -70,18 +116,64 @@ impl ShipController
         }
     }
 
+    /// Steers the ship so that it turns towards `angle`, limited by the yaw acceleration.
+    pub fn rotate_towards(&mut self, angle: f32)
+    {
+        if let Some(ship) = self.ship.upgrade() {
+            let mut ship = ship.borrow_mut();
+
+            // The shortest angle between the current yaw and the target, in the range [-PI, PI).
+            let delta = (angle - ship.yaw() + PI).rem_euclid(2. * PI) - PI;
+
+            // Aim for the yaw to land on the target after the next update.
+            let acceleration =
+                (delta - ship.yaw_delta()).clamp(-self.yaw_acceleration, self.yaw_acceleration);
+
+            ship.accelerate_yaw_rotation(acceleration);
+        }
+    }
+
     pub fn fire_bullet(&mut self) -> Option<Bullet>
     {
         self.ship
             .upgrade()
-            .map(|ship| self.gun.fire(&mut self.energy, &ship.borrow()))
-            .flatten()
+            .and_then(|ship| self.gun.fire(&mut self.energy, &ship.borrow()))
     }
 
     pub fn set_boost(&mut self, state: bool)
     {
         self.boost.set(state);
-        self.boost_multiplier = self.boost.multiplier(&mut self.energy).unwrap_or(1.);
+
+        let multiplier = self.boost.multiplier(&mut self.energy);
+        self.boosting = multiplier.is_some();
+        self.boost_multiplier = multiplier.unwrap_or(1.);
+    }
+
+    pub fn energy(&self) -> f32
+    {
+        self.energy
+    }
+
+    pub fn energy_max(&self) -> f32
+    {
+        self.energy_max
+    }
+
+    pub fn gun(&self) -> &ShipGun
+    {
+        &self.gun
+    }
+
+    /// Whether there is enough energy to boost and the boost isn't disabled.
+    pub fn boost_is_available(&self) -> bool
+    {
+        self.boost.is_available(self.energy)
+    }
+
+    /// Whether the boost was applied when it was last set.
+    pub fn is_boosting(&self) -> bool
+    {
+        self.boosting
     }
 
     pub fn update(&mut self)
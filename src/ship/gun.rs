@@ -1,3 +1,4 @@
+use getset::Getters;
 use vecmath::{
     vec2_add,
     vec2_mul,
@@ -9,14 +10,23 @@ use crate::{
     ship::Ship,
 };
 
-#[derive(Builder, Clone, Debug)]
+#[derive(Builder, Clone, Debug, Getters)]
 pub struct ShipGun
 {
+    #[getset(get = "pub")]
     bullet_speed: f32,
+
+    #[getset(get = "pub")]
     bullet_duration: u32,
+
+    #[getset(get = "pub")]
     energy_cost: f32,
+
+    #[getset(get = "pub")]
     period: u32,
 
+    /// Number of frames until the gun can fire again.
+    #[getset(get = "pub")]
     #[builder(setter(skip), default = "0")]
     period_countdown: u32,
 }
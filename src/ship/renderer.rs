@@ -1,50 +1,80 @@
-use web_sys::{
-    WebGlBuffer,
-    WebGlProgram,
-    WebGlRenderingContext,
-    WebGlShader,
-};
+use web_sys::WebGlRenderingContext;
 
 use crate::{
     context::Context,
-    gl,
+    gl::{
+        self,
+        buffer::{
+            ArrayBuffer,
+            BufferUsage,
+            VertexAttributeType,
+            VertexLayout,
+        },
+        device::{
+            GraphicsDevice,
+            ShaderId,
+        },
+        program::{
+            Mat4,
+            Program,
+            Uniform,
+            Vec4,
+        },
+    },
     matrix::{
         Matrix4x4,
         Rotate,
         Scale,
         Translate,
     },
+    outline_renderer::{
+        Outline,
+        OutlineRenderer,
+        Stroke,
+    },
     ship::Ship,
+    theme::ShapeStyle,
 };
 
 /// Renderer for ships.
 pub struct ShipRenderer
 {
-    program: WebGlProgram,
-    vertex_buffer: WebGlBuffer,
+    program: Program,
+    world_matrix: Uniform<Mat4>,
+    projection_matrix: Uniform<Mat4>,
+    color: Uniform<Vec4>,
+    vertex_layout: VertexLayout,
+    vertex_buffer: ArrayBuffer<f32>,
+    outline: Outline,
+    outline_renderer: OutlineRenderer,
 }
 
 impl ShipRenderer
 {
     /// Creates a new `ShipRenderer`.
-    pub fn new(context: &WebGlRenderingContext, ship: &Ship) -> Result<ShipRenderer, String>
+    pub fn new(context: &dyn GraphicsDevice, ship: &Ship) -> Result<ShipRenderer, String>
     {
         //
         // Create program.
         //
-        let program = gl::link_program(
+        let vertex_layout =
+            VertexLayout::new().attribute("position", 3, VertexAttributeType::Float);
+
+        let program = Program::new(
             context,
             &vertex_shader(context)?,
             &fragment_shader(context)?,
+            &[&vertex_layout],
         )?;
 
         //
         // Initialize vertex buffer.
         //
         let r = ship.wing_angle();
-        let vertex_buffer = gl::make_static_draw_array_buffer_f32(
+        let vertex_buffer = ArrayBuffer::with_data(
             context,
-            vec![
+            BufferUsage::StaticDraw,
+            &[
                 // 0
                 1.0,
                 0.0,
@@ -72,25 +102,55 @@ impl ShipRenderer
             ],
         )?;
 
+        let outline = Outline::closed(
+            context,
+            &[
+                [1., 0.],
+                [r.cos(), r.sin()],
+                [*ship.tail_x(), 0.],
+                [r.cos(), -r.sin()],
+            ],
+        )?;
+
         Ok(ShipRenderer {
+            world_matrix: program.uniform("world_matrix")?,
+            projection_matrix: program.uniform("projection_matrix")?,
+            color: program.uniform("color")?,
             program,
+            vertex_layout,
             vertex_buffer,
+            outline,
+            outline_renderer: OutlineRenderer::new(context)?,
         })
     }
 
-    /// Renders `ship`.
-    pub fn render(&self, context: &Context, ship: &Ship)
+    /// Renders `ship` in the style of the theme of `context`.
+    pub fn render(&mut self, context: &Context, ship: &Ship)
     {
-        let gl = context.render_context();
+        let theme = context.theme();
 
-        gl.bind_buffer(
-            WebGlRenderingContext::ARRAY_BUFFER,
-            Some(&self.vertex_buffer),
-        );
-        gl.enable_vertex_attrib_array(0);
+        if let ShapeStyle::Vector { stroke_width, glow } = theme.shapes {
+            let yaw = *ship.yaw();
+            let mut instance = Vec::with_capacity(6);
+            instance.extend_from_slice(ship.position());
+            instance.extend_from_slice(ship.size());
+            instance.extend_from_slice(&[yaw.cos(), yaw.sin()]);
 
-        gl.use_program(Some(&self.program));
-        gl.vertex_attrib_pointer_with_i32(0, 3, WebGlRenderingContext::FLOAT, false, 0, 0);
+            let stroke = Stroke {
+                color: theme.ship.outline,
+                width: stroke_width,
+                glow,
+            };
+            self.outline_renderer
+                .render(context, &self.outline, instance, &stroke);
+            return;
+        }
+
+        let gl = context.render_context();
+
+        self.program.use_program(gl);
+        self.program
+            .enable_vertex_layout(gl, &self.vertex_layout, &self.vertex_buffer, 0);
 
         //
         // Calculate world matrix and set the uniform.
@@ -101,36 +161,35 @@ impl ShipRenderer
             .dot(&Rotate::id().radians(*ship.yaw()).into_arr2())
             .dot(&Translate::id().vec2(ship.position()).into_arr2());
 
-        let location = gl.get_uniform_location(&self.program, "world_matrix");
-
-        gl.uniform_matrix4fv_with_f32_array(
-            location.as_ref(),
-            false,
-            matrix.view().as_slice().unwrap(),
-        );
+        self.world_matrix.set(gl, matrix.view().as_slice().unwrap());
 
         //
         // Set the projection matrix uniform
         //
-        let matrix = context.foreground_projection_matrix().clone().into_array();
-        let location = gl.get_uniform_location(&self.program, "projection_matrix");
-
-        gl.uniform_matrix4fv_with_f32_array(location.as_ref(), false, &matrix);
+        let matrix = context.foreground_matrix();
+        self.projection_matrix.set(gl, &matrix);
 
         //
-        // Draw ship.
+        // Draw ship, then its outline which goes through the vertices in order.
         //
+        let colors = &theme.ship;
+        self.color.set(gl, &colors.fill);
         gl.draw_arrays(WebGlRenderingContext::TRIANGLES, 0, 6);
 
+        if colors.outline[3] > 0. {
+            self.color.set(gl, &colors.outline);
+            gl.draw_arrays(WebGlRenderingContext::LINE_STRIP, 0, 6);
+        }
+
         //
         // Clean-up
         //
-        gl.use_program(None);
-        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, None);
+        self.program.disable_vertex_layout(gl, &self.vertex_layout);
+        self.program.unuse_program(gl);
     }
 }
 
-fn vertex_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
+fn vertex_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
 {
     gl::compile_vertex_shader(
         context,
@@ -148,17 +207,158 @@ fn vertex_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
     )
 }
 
-fn fragment_shader(context: &WebGlRenderingContext) -> Result<WebGlShader, String>
+fn fragment_shader(context: &dyn GraphicsDevice) -> Result<ShaderId, String>
 {
     gl::compile_fragment_shader(
         context,
         r#"
         precision mediump float;
 
+        uniform vec4 color;
+
         void main()
         {
-            gl_FragColor = vec4(0.5, 0.8, 0.9, 1.0);
+            gl_FragColor = color;
         }
     "#,
     )
 }
+
+#[cfg(test)]
+mod tests
+{
+    use std::{
+        f32::consts::PI,
+        rc::Rc,
+    };
+
+    use super::*;
+    use crate::{
+        gl::{
+            device::Capabilities,
+            recording::{
+                Command,
+                RecordingDevice,
+            },
+        },
+        theme::Theme,
+    };
+
+    type GL = WebGlRenderingContext;
+
+    fn ship() -> Ship
+    {
+        Ship::builder()
+            .position([2., 1.5])
+            .size([0.075, 0.075])
+            .weight(0.05)
+            .yaw(PI / 4.)
+            .tail_x(-1. / 9.)
+            .wing_angle(23. / 36. * PI)
+            .build()
+            .unwrap()
+    }
+
+    /// Renders the ship for two frames, returning the commands issued for each.
+    fn render(device: RecordingDevice, theme: Theme) -> (Rc<RecordingDevice>, [Vec<Command>; 2])
+    {
+        let device = Rc::new(device);
+        let mut context = Context::with_device(device.clone(), 800, 600);
+        context.set_theme(theme);
+
+        let ship = ship();
+        let mut renderer = ShipRenderer::new(context.render_context(), &ship).unwrap();
+        device.take_commands();
+
+        renderer.render(&context, &ship);
+        let first = device.take_commands();
+        renderer.render(&context, &ship);
+        let second = device.take_commands();
+
+        (device, [first, second])
+    }
+
+    fn draws(commands: &[Command]) -> Vec<Command>
+    {
+        commands
+            .iter()
+            .filter(|command| matches!(command, Command::DrawArrays { .. }))
+            .cloned()
+            .collect()
+    }
+
+    fn pointers(commands: &[Command]) -> usize
+    {
+        commands
+            .iter()
+            .filter(|command| matches!(command, Command::VertexAttribPointer { .. }))
+            .count()
+    }
+
+    #[test]
+    fn ship_is_drawn_as_two_triangles_in_its_fill_color()
+    {
+        let (device, [commands, _]) = render(RecordingDevice::new(), Theme::classic());
+
+        let triangles = Command::DrawArrays {
+            mode: GL::TRIANGLES,
+            first: 0,
+            count: 6,
+        };
+        assert_eq!(draws(&commands), std::slice::from_ref(&triangles));
+
+        let draw = commands
+            .iter()
+            .position(|command| *command == triangles)
+            .unwrap();
+        let color = commands.iter().rposition(|command| match command {
+            Command::Uniform4fv { location, value } => {
+                device.uniform_name(*location) == "color" && value[..] == Theme::classic().ship.fill
+            }
+            _ => false,
+        });
+        assert!(color.unwrap() < draw);
+    }
+
+    #[test]
+    fn outline_goes_through_the_vertices_in_order()
+    {
+        let (_, [commands, _]) = render(RecordingDevice::new(), Theme::high_contrast());
+
+        assert_eq!(
+            draws(&commands)[1],
+            Command::DrawArrays {
+                mode: GL::LINE_STRIP,
+                first: 0,
+                count: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn vertex_array_keeps_the_vertex_layout_between_frames()
+    {
+        let (_, [first, second]) = render(RecordingDevice::new(), Theme::classic());
+
+        assert_eq!(pointers(&first), 1);
+        assert_eq!(pointers(&second), 0);
+        assert_eq!(draws(&first), draws(&second));
+    }
+
+    #[test]
+    fn vertex_layout_is_enabled_every_frame_without_vertex_arrays()
+    {
+        let (_, [first, second]) = render(
+            RecordingDevice::with_capabilities(Capabilities::none()),
+            Theme::classic(),
+        );
+
+        for commands in &[first, second] {
+            assert_eq!(pointers(commands), 1);
+            assert!(commands.contains(&Command::DisableVertexAttribArray(0)));
+            assert!(!commands
+                .iter()
+                .any(|command| matches!(command, Command::BindVertexArray(_))));
+        }
+    }
+}
@@ -73,9 +73,31 @@ impl Ship
     pub fn update(&mut self)
     {
         self.velocity = self
-            .collisions
+            .collision_velocity_deltas()
+            .fold(self.velocity, vec2_sub);
+
+        self.position = vec2_add(self.position, self.velocity);
+        foreground::position_modulo(&mut self.position);
+        self.yaw += self.yaw_delta;
+
+        self.velocity = vec2_scale(self.velocity, 0.91);
+        self.yaw_delta *= 0.45;
+
+        self.collisions.clear();
+    }
+
+    /// How much the collisions pushed since the last update slow the ship down.
+    pub fn collision_velocity_delta(&self) -> [f32; 2]
+    {
+        self.collision_velocity_deltas().fold([0., 0.], vec2_add)
+    }
+
+    /// The change of velocity caused by each collision.
+    fn collision_velocity_deltas(&self) -> impl Iterator<Item = [f32; 2]> + '_
+    {
+        self.collisions
             .iter()
-            .map(|collision| match collision {
+            .map(move |collision| match collision {
                 Collision::Rock(other) | Collision::Bullet(other) | Collision::Ship(other) => {
                     ElasticCollision::builder()
                         .target(self)
@@ -85,16 +107,6 @@ impl Ship
                         .target_velocity_delta()
                 }
             })
-            .fold(self.velocity, |velocity, delta| vec2_sub(velocity, delta));
-
-        self.position = vec2_add(self.position, self.velocity);
-        foreground::position_modulo(&mut self.position);
-        self.yaw += self.yaw_delta;
-
-        self.velocity = vec2_scale(self.velocity, 0.91);
-        self.yaw_delta *= 0.45;
-
-        self.collisions.clear();
     }
 
     pub fn hitbox(&self) -> CircularHitbox
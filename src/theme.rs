@@ -0,0 +1,213 @@
+use crate::background::BackgroundStyle;
+
+/// The colors something in the foreground is filled and outlined with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntityColors
+{
+    pub fill: [f32; 4],
+
+    /// The outline isn't drawn when transparent.
+    pub outline: [f32; 4],
+}
+
+/// The colors of the bullets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BulletColors
+{
+    pub color: [f32; 4],
+
+    /// How much the color shimmers with the position of the bullet on screen, from 0 to 1.
+    pub shimmer: f32,
+}
+
+/// How the ship and the rocks are drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShapeStyle
+{
+    /// Filled with their fill color, with thin outlines unless their outline color is
+    /// transparent.
+    Filled,
+
+    /// Only thick glowing outlines of their outline color, like on a vector display. The widths
+    /// are in pixels of a render target 1000 pixels high.
+    Vector
+    {
+        stroke_width: f32, glow: f32
+    },
+}
+
+/// How the background is drawn.
+///
+/// The starfield is `base_color` covered by clouds of `colors[0]` and dust of `colors[1]`. The
+/// rings alternate between `base_color` plus `colors[0]` and `base_color` plus `colors[1]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BackgroundTheme
+{
+    pub style: BackgroundStyle,
+    pub base_color: [f32; 4],
+    pub colors: [[f32; 4]; 2],
+
+    /// Brightness of the stars, from 0 to 1.
+    pub star_brightness: f32,
+}
+
+/// The colors of everything rendered, which can be switched while the game runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme
+{
+    /// One of `NAMES`.
+    pub name: &'static str,
+
+    /// The color behind the background, seen where nothing else is drawn.
+    pub clear_color: [f32; 4],
+
+    pub background: BackgroundTheme,
+    pub shapes: ShapeStyle,
+    pub ship: EntityColors,
+    pub rocks: EntityColors,
+    pub bullets: BulletColors,
+}
+
+impl Theme
+{
+    /// The names of the themes `named` knows.
+    pub const NAMES: [&'static str; 4] = ["classic", "rings", "high-contrast", "vector"];
+
+    /// The theme called `name`, one of `NAMES`.
+    pub fn named(name: &str) -> Result<Theme, String>
+    {
+        match name {
+            "classic" => Ok(Theme::classic()),
+            "rings" => Ok(Theme::rings()),
+            "high-contrast" => Ok(Theme::high_contrast()),
+            "vector" => Ok(Theme::vector()),
+            _ => Err(format!(
+                "unknown theme '{}', themes are: {}",
+                name,
+                Theme::NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// The theme after this one in `NAMES`, going back to the first after the last.
+    pub fn next(&self) -> Theme
+    {
+        let index = Theme::NAMES
+            .iter()
+            .position(|name| *name == self.name)
+            .map_or(0, |index| index + 1);
+
+        Theme::named(Theme::NAMES[index % Theme::NAMES.len()]).unwrap()
+    }
+
+    /// Light blue ship and dark rocks over a purple nebula.
+    pub fn classic() -> Theme
+    {
+        Theme {
+            name: "classic",
+            clear_color: [0., 0., 0., 1.],
+            background: BackgroundTheme {
+                style: BackgroundStyle::Starfield,
+                base_color: [0.02, 0.01, 0.05, 1.],
+                colors: [[0.25, 0.08, 0.3, 1.], [0.05, 0.15, 0.25, 1.]],
+                star_brightness: 1.,
+            },
+            shapes: ShapeStyle::Filled,
+            ship: EntityColors {
+                fill: [0.5, 0.8, 0.9, 1.],
+                outline: [0., 0., 0., 0.],
+            },
+            rocks: EntityColors {
+                fill: [0.1, 0.1, 0.05, 1.],
+                outline: [0., 0., 0., 0.],
+            },
+            bullets: BulletColors {
+                color: [0.8, 0.8, 0.8, 1.],
+                shimmer: 1.,
+            },
+        }
+    }
+
+    /// The classic theme over the red and blue rings the game started with.
+    pub fn rings() -> Theme
+    {
+        Theme {
+            name: "rings",
+            background: BackgroundTheme {
+                style: BackgroundStyle::Rings,
+                base_color: [0., 0.1, 0., 1.],
+                colors: [[0.3, 0., 0., 1.], [0., 0., 0.3, 1.]],
+                star_brightness: 1.,
+            },
+            ..Theme::classic()
+        }
+    }
+
+    /// Bright, outlined shapes over a plain dark background, with colors of the Okabe-Ito palette
+    /// which stay distinct with the common kinds of color blindness.
+    pub fn high_contrast() -> Theme
+    {
+        Theme {
+            name: "high-contrast",
+            clear_color: [0., 0., 0., 1.],
+            background: BackgroundTheme {
+                style: BackgroundStyle::Starfield,
+                base_color: [0., 0., 0., 1.],
+                colors: [[0.06, 0.06, 0.06, 1.], [0., 0., 0., 1.]],
+                star_brightness: 0.35,
+            },
+            shapes: ShapeStyle::Filled,
+            ship: EntityColors {
+                fill: [0.94, 0.89, 0.26, 1.],
+                outline: [1., 1., 1., 1.],
+            },
+            rocks: EntityColors {
+                fill: [0., 0.45, 0.7, 1.],
+                outline: [0.34, 0.71, 0.91, 1.],
+            },
+            bullets: BulletColors {
+                color: [0.9, 0.62, 0., 1.],
+                shimmer: 0.,
+            },
+        }
+    }
+
+    /// Glowing outlines over a dark starfield, which look best with bloom.
+    pub fn vector() -> Theme
+    {
+        Theme {
+            name: "vector",
+            clear_color: [0., 0., 0., 1.],
+            background: BackgroundTheme {
+                style: BackgroundStyle::Starfield,
+                base_color: [0., 0., 0.01, 1.],
+                colors: [[0.04, 0.02, 0.08, 1.], [0., 0.04, 0.06, 1.]],
+                star_brightness: 0.5,
+            },
+            shapes: ShapeStyle::Vector {
+                stroke_width: 3.,
+                glow: 10.,
+            },
+            ship: EntityColors {
+                fill: [0., 0., 0., 0.],
+                outline: [0.6, 1., 0.9, 1.],
+            },
+            rocks: EntityColors {
+                fill: [0., 0., 0., 0.],
+                outline: [0.9, 0.9, 1., 1.],
+            },
+            bullets: BulletColors {
+                color: [1., 1., 1., 1.],
+                shimmer: 0.,
+            },
+        }
+    }
+}
+
+impl Default for Theme
+{
+    fn default() -> Theme
+    {
+        Theme::classic()
+    }
+}
@@ -0,0 +1,504 @@
+use std::{
+    cell::{
+        Ref,
+        RefCell,
+    },
+    collections::BTreeMap,
+    rc::Rc,
+};
+
+use getset::Getters;
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use vecmath::{
+    vec2_add,
+    vec2_len,
+    vec2_sub,
+};
+
+use crate::{
+    bullet::{
+        Bullet,
+        UpdateBulletEvent,
+    },
+    collision::{
+        Collision,
+        OtherCollisionObject,
+    },
+    input::{
+        Action,
+        InputState,
+    },
+    rock::{
+        Rock,
+        SpawnRandomizedRocksAnywhere,
+    },
+    ship::{
+        Ship,
+        ShipController,
+        ShipControllerDescriptor,
+    },
+};
+
+/// Number of lives the ship starts with.
+const LIVES: u32 = 3;
+
+/// Number of frames after losing a life, or after a new wave has spawned, during which the ship
+/// can't lose another life.
+const INVULNERABILITY_FRAMES: u32 = 120;
+
+/// Points awarded for destroying a rock.
+const ROCK_SCORE: u32 = 100;
+
+/// Everything needed to create a `World`.
+///
+/// Two worlds created from the same descriptor and fed the same inputs will be in the same state
+/// after each step.
+#[derive(Builder, Clone, Debug, Getters)]
+pub struct WorldDescriptor
+{
+    /// Seed of the random number generator used by the world.
+    #[getset(get = "pub")]
+    seed: u64,
+
+    /// The ship in its initial state.
+    #[getset(get = "pub")]
+    ship: Ship,
+
+    #[getset(get = "pub")]
+    ship_controller: ShipControllerDescriptor,
+
+    #[getset(get = "pub")]
+    rock_spawner: SpawnRandomizedRocksAnywhere,
+
+    /// Number of rocks spawned when the world is created.
+    #[getset(get = "pub")]
+    rock_count: u32,
+}
+
+impl WorldDescriptor
+{
+    pub fn builder() -> WorldDescriptorBuilder
+    {
+        WorldDescriptorBuilder::default()
+    }
+}
+
+/// Something that happened in a `World` during a step.
+///
+/// Events are meant for effects such as particles and don't affect the simulation.
+#[derive(Clone, Debug)]
+pub enum WorldEvent
+{
+    /// The ship thrusted forward, `position` is the tail of the ship.
+    Thrust
+    {
+        position: [f32; 2],
+        velocity: [f32; 2],
+        yaw: f32,
+        boosted: bool,
+    },
+
+    /// A bullet hit a rock at `position`.
+    Impact
+    {
+        position: [f32; 2],
+        velocity: [f32; 2],
+    },
+
+    /// A rock was destroyed.
+    RockDestroyed
+    {
+        position: [f32; 2],
+        velocity: [f32; 2],
+        size: [f32; 2],
+    },
+
+    /// The ship bounced off rocks with an `impulse`, the weight of the ship times the change of
+    /// its velocity. Happens every step the ship touches rocks, even while it can't lose a life.
+    ShipCollision
+    {
+        impulse: f32
+    },
+
+    /// The ship was hit by a rock and lost a life.
+    ShipHit
+    {
+        position: [f32; 2]
+    },
+}
+
+/// The simulated game world.
+pub struct World
+{
+    rng: StdRng,
+    rock_spawner: SpawnRandomizedRocksAnywhere,
+    rock_count: u32,
+    ship: Rc<RefCell<Ship>>,
+    ship_controller: ShipController,
+    rocks: Vec<Rock>,
+    bullets: Vec<Bullet>,
+    events: Vec<WorldEvent>,
+
+    /// The positions returned by the hitbox intersections of the last step.
+    contacts: Vec<[f32; 2]>,
+
+    score: u32,
+    wave: u32,
+    lives: u32,
+
+    /// Number of frames left during which the ship can't lose a life.
+    invulnerability: u32,
+}
+
+impl World
+{
+    pub fn new(descriptor: &WorldDescriptor) -> World
+    {
+        let mut rng = StdRng::seed_from_u64(descriptor.seed);
+
+        let ship = Rc::new(RefCell::new(descriptor.ship.clone()));
+        let ship_controller =
+            ShipController::new(Rc::downgrade(&ship), &descriptor.ship_controller);
+
+        let rocks = spawn_rocks(&mut rng, &descriptor.rock_spawner, descriptor.rock_count);
+
+        World {
+            rng,
+            rock_spawner: descriptor.rock_spawner.clone(),
+            rock_count: descriptor.rock_count,
+            ship,
+            ship_controller,
+            rocks,
+            bullets: Vec::new(),
+            events: Vec::new(),
+            contacts: Vec::new(),
+            score: 0,
+            wave: 1,
+            lives: LIVES,
+            invulnerability: 0,
+        }
+    }
+
+    pub fn ship_controller(&self) -> &ShipController
+    {
+        &self.ship_controller
+    }
+
+    pub fn score(&self) -> u32
+    {
+        self.score
+    }
+
+    /// The current wave, starting at 1. A new wave of rocks spawns when every rock is destroyed.
+    pub fn wave(&self) -> u32
+    {
+        self.wave
+    }
+
+    pub fn lives(&self) -> u32
+    {
+        self.lives
+    }
+
+    /// The ship no longer responds to input once every life is lost.
+    pub fn is_game_over(&self) -> bool
+    {
+        self.lives == 0
+    }
+
+    pub fn ship(&self) -> Ref<'_, Ship>
+    {
+        self.ship.borrow()
+    }
+
+    pub fn rocks(&self) -> &[Rock]
+    {
+        &self.rocks
+    }
+
+    pub fn bullets(&self) -> &[Bullet]
+    {
+        &self.bullets
+    }
+
+    /// The events of the last step.
+    pub fn events(&self) -> &[WorldEvent]
+    {
+        &self.events
+    }
+
+    /// The positions returned by `CircularHitbox::intersects` for every collision detected in the
+    /// last step, which is the position of the other object as seen from the hitbox tested.
+    pub fn contacts(&self) -> &[[f32; 2]]
+    {
+        &self.contacts
+    }
+
+    /// Advances the world one frame with the actions in `input` performed.
+    pub fn step(&mut self, input: &InputState)
+    {
+        self.events.clear();
+
+        if !self.is_game_over() {
+            self.apply_input(input);
+        }
+        self.detect_collisions();
+        self.update();
+    }
+
+    fn apply_input(&mut self, input: &InputState)
+    {
+        let ship_controller = &mut self.ship_controller;
+
+        ship_controller.set_boost(input.contains(Action::Boost));
+
+        if let Some(target) = input.aim() {
+            let position = *self.ship.borrow().position();
+            let direction = vec2_sub(target, position);
+            ship_controller.rotate_towards(direction[1].atan2(direction[0]));
+        }
+
+        for action in Action::iter().filter(|action| input.contains(*action)) {
+            match action {
+                Action::ThrustForward => {
+                    ship_controller.thrust_forward();
+
+                    let ship = self.ship.borrow();
+                    let (sin, cos) = ship.yaw().sin_cos();
+                    let tail = ship.tail_x() * ship.size()[0];
+                    self.events.push(WorldEvent::Thrust {
+                        position: vec2_add(*ship.position(), [tail * cos, tail * sin]),
+                        velocity: *ship.velocity(),
+                        yaw: *ship.yaw(),
+                        boosted: ship_controller.is_boosting(),
+                    });
+                }
+                Action::ThrustBackwards => ship_controller.thrust_backwards(),
+                Action::SteerCounterClockwise => ship_controller.steer_counter_clockwise(),
+                Action::SteerClockwise => ship_controller.steer_clockwise(),
+                Action::Fire => {
+                    if let Some(bullet) = ship_controller.fire_bullet() {
+                        self.bullets.push(bullet);
+                    }
+                }
+                Action::Boost => (),
+            }
+        }
+    }
+
+    fn detect_collisions(&mut self)
+    {
+        let rocks = &mut self.rocks;
+        let contacts = &mut self.contacts;
+        contacts.clear();
+
+        //
+        // Check rocks colliding with other rocks.
+        //
+        // The collisions are kept in an ordered map so that they are pushed in the same order every
+        // time, otherwise the resulting velocities could differ between runs.
+        //
+        let rock_collision_map: BTreeMap<_, Vec<_>> = rocks
+            .iter()
+            .enumerate()
+            .map(|(i, rock)| {
+                (
+                    i,
+                    rocks
+                        .iter()
+                        .enumerate()
+                        .filter_map(move |(j, other)| {
+                            if i != j {
+                                rock.hitbox()
+                                    .intersects(&other.hitbox())
+                                    .map(|position| (j, position))
+                            } else {
+                                None
+                            }
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+
+        for (i, js) in rock_collision_map.iter() {
+            for (j, position) in js.iter() {
+                contacts.push(*position);
+
+                let other = &rocks[*j];
+                let other = OtherCollisionObject::builder()
+                    .position(*position)
+                    .velocity(*other.velocity())
+                    .weight(other.weight())
+                    .build()
+                    .unwrap();
+
+                let rock = &mut rocks[*i];
+                rock.push_collision(Collision::Rock(other));
+            }
+        }
+
+        //
+        // Check if ship has collided with rocks.
+        //
+        let hitbox = self.ship.borrow().hitbox();
+
+        for rock in rocks.iter_mut() {
+            if let Some(position) = hitbox.intersects(&rock.hitbox()) {
+                contacts.push(position);
+                self.ship.borrow_mut().push_collision(Collision::Rock(
+                    OtherCollisionObject::builder()
+                        .position(position)
+                        .velocity(*rock.velocity())
+                        .weight(rock.weight())
+                        .build()
+                        .unwrap(),
+                ));
+            }
+
+            if let Some(position) = rock.hitbox().intersects(&hitbox) {
+                let ship = self.ship.borrow();
+                rock.push_collision(Collision::Ship(
+                    OtherCollisionObject::builder()
+                        .position(position)
+                        .velocity(*ship.velocity())
+                        .weight(*ship.weight())
+                        .build()
+                        .unwrap(),
+                ));
+            }
+        }
+
+        //
+        // Check if bullets has collided with rocks.
+        //
+        for bullet in self.bullets.iter_mut() {
+            for rock in rocks.iter_mut() {
+                if let Some(position) = bullet.hitbox().intersects(&rock.hitbox()) {
+                    contacts.push(position);
+                    bullet.push_collision(Collision::Rock(
+                        OtherCollisionObject::builder()
+                            .position(position)
+                            .velocity(*rock.velocity())
+                            .weight(rock.weight())
+                            .build()
+                            .unwrap(),
+                    ));
+                    rock.push_collision(Collision::Bullet(
+                        OtherCollisionObject::builder()
+                            .position(rock.hitbox().intersects(&bullet.hitbox()).unwrap())
+                            .velocity(*bullet.velocity())
+                            .weight(0.)
+                            .build()
+                            .unwrap(),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn update(&mut self)
+    {
+        let mut countdown_finished = Vec::new();
+        let mut hit_by_rock = Vec::new();
+
+        for (i, bullet) in self.bullets.iter_mut().enumerate() {
+            match bullet.update() {
+                Some(UpdateBulletEvent::CountdownFinished) => countdown_finished.push(i),
+                Some(UpdateBulletEvent::HitByRock) => hit_by_rock.push(i),
+                _ => (),
+            }
+        }
+
+        for i in descending(countdown_finished.into_iter().chain(hit_by_rock)) {
+            self.bullets.remove(i);
+        }
+
+        let mut rocks_hit_by_bullets = Vec::new();
+
+        for (i, rock) in self.rocks.iter_mut().enumerate() {
+            for collision in rock.update() {
+                if let Collision::Bullet(bullet) = collision {
+                    self.events.push(WorldEvent::Impact {
+                        position: *bullet.position(),
+                        velocity: *bullet.velocity(),
+                    });
+                    rocks_hit_by_bullets.push(i);
+                }
+            }
+        }
+        for i in descending(rocks_hit_by_bullets) {
+            let rock = self.rocks.remove(i);
+            self.score += ROCK_SCORE;
+            self.events.push(WorldEvent::RockDestroyed {
+                position: *rock.position(),
+                velocity: *rock.velocity(),
+                size: *rock.size(),
+            });
+        }
+
+        //
+        // Spawn a new wave, with one more rock than the last, once every rock is destroyed.
+        //
+        if self.rocks.is_empty() {
+            self.wave += 1;
+            self.rocks = spawn_rocks(
+                &mut self.rng,
+                &self.rock_spawner,
+                self.rock_count + self.wave - 1,
+            );
+            self.invulnerability = INVULNERABILITY_FRAMES;
+        }
+
+        //
+        // Lose a life when the ship is hit by a rock.
+        //
+        let ship_hit = self
+            .ship
+            .borrow()
+            .collisions()
+            .iter()
+            .any(|collision| matches!(collision, Collision::Rock(_)));
+
+        if ship_hit {
+            let ship = self.ship.borrow();
+            self.events.push(WorldEvent::ShipCollision {
+                impulse: ship.weight() * vec2_len(ship.collision_velocity_delta()),
+            });
+        }
+
+        if self.invulnerability > 0 {
+            self.invulnerability -= 1;
+        } else if ship_hit && !self.is_game_over() {
+            self.lives -= 1;
+            self.invulnerability = INVULNERABILITY_FRAMES;
+            self.events.push(WorldEvent::ShipHit {
+                position: *self.ship.borrow().position(),
+            });
+        }
+
+        self.ship.borrow_mut().update();
+        self.ship_controller.update();
+    }
+}
+
+fn spawn_rocks(rng: &mut StdRng, spawner: &SpawnRandomizedRocksAnywhere, count: u32) -> Vec<Rock>
+{
+    rng.sample_iter(spawner)
+        .take(count as usize)
+        .map(|descriptor| Rock::new(&descriptor))
+        .collect()
+}
+
+/// The unique `indices` from greatest to smallest, so that they can be removed one by one.
+fn descending<I: IntoIterator<Item = usize>>(indices: I) -> impl Iterator<Item = usize>
+{
+    let mut indices: Vec<_> = indices.into_iter().collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices.into_iter().rev()
+}